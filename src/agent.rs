@@ -1,69 +1,252 @@
-use crate::blocks::{block_to_block_distribution, calc_base_fee};
-use crate::config::{AgentConfig, ChainConfig, Config, PendingBlockDataSource, PredictionTrigger};
-use crate::distribution::BlockDistribution;
-use crate::models::{apply_model, ModelError};
+use crate::block_time::BlockTimeEstimator;
+use crate::blocks::{block_to_block_distribution, calc_base_fee, calc_blob_base_fee};
+use crate::chain::records::RecordType;
+use crate::config::{
+    AgentConfig, ChainConfig, Config, GasSource, PendingBlockDataSource, PendingBlockFeed,
+    PendingBlockRequest, PredictionTrigger,
+};
+use crate::distribution::{percentile_wei, BlockDistribution, PendingBlockSnapshot};
+use crate::features::BlockFeatures;
+use crate::mempool::{stream_pending_transactions, Mempool};
+use crate::metrics::{ChainMetrics, SelfTuningMetrics};
+use crate::models::{apply_model, validate_model_config, ModelError, ModelState};
+use crate::op_stack::L1FeeParams;
+use crate::poll_schedule::PollSchedule;
 use crate::publish::publish_agent_payload;
-use crate::rpc::{get_latest_block, get_rpc_client, Block, BlockHeader, RpcClient};
-use crate::types::{AgentKind, AgentPayload, PriceUnit, Settlement, SystemNetworkKey};
-use anyhow::{Context, Result};
-use chrono::Utc;
+use crate::rpc::{
+    get_latest_block, get_rpc_client, Block, BlockHeader, PendingTransactions, RpcClient,
+    Transaction,
+};
+use crate::signer::AgentSigner;
+use crate::types::{
+    AgentKind, AgentPayload, ChainParams, FeeMarket, PayloadRecord, PriceKind, PriceUnit,
+    Settlement,
+};
+use alloy::primitives::Address;
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use opentelemetry::KeyValue;
 use rand::Rng;
 use reqwest::Url;
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
-use tracing::{debug, error, info, warn};
+use tracing::{debug, error, info, info_span, warn, Instrument, Span};
 
 const MAX_NUM_BLOCK_DISTRIBUTIONS: usize = 50;
+const MAX_NUM_BLOCK_FEATURES: usize = 500;
 const ERROR_RETRY_BASE_BACKOFF_MS: u64 = 250;
 const ERROR_RETRY_MAX_BACKOFF_MS: u64 = 5_000;
+const WEBSOCKET_RECONNECT_DELAY_MS: u64 = 1_000;
+/// Blocks skipped between polls that are fetched to evict their transactions from the mempool,
+/// the transactions of older skipped blocks are left to expire
+const MAX_NUM_SKIPPED_BLOCKS_EVICTED: u64 = 32;
 
-pub async fn start_agents(chain_config: ChainConfig, config: &Config) -> Result<()> {
-    let agents = GasAgent::new(chain_config, config).await?;
+pub async fn start_agents(
+    chain_config: ChainConfig,
+    signers: Vec<AgentSigner>,
+    config: &Config,
+) -> Result<()> {
+    let agents = GasAgent::new(chain_config, signers, config).await?;
     agents.run().await
 }
 
 #[derive(Clone)]
 struct GasAgent {
     chain_config: ChainConfig,
+    /// Parameters resolved from `chain_config`
+    chain: ChainParams,
     config: Config,
     rpc_client: RpcClient,
     chain_tip: Arc<RwLock<BlockHeader>>,
+    /// Measured block time, with the static block time of `chain` as its prior
+    block_time: Arc<RwLock<BlockTimeEstimator>>,
     block_distributions: Arc<RwLock<Vec<BlockDistribution>>>,
-    pending_block_distribution: Arc<RwLock<Option<BlockDistribution>>>,
+    /// Lowest effective price of the latest block before outlier filtering
+    raw_block_min: Arc<RwLock<Option<u128>>>,
+    /// Latest pending block polled from the pending block data source
+    pending_block: Arc<RwLock<Option<PendingBlockSnapshot>>>,
+    /// Pending transactions streamed from a WebSocket source, which pending block snapshots
+    /// are derived from instead of `pending_block`
+    mempool: Option<Arc<RwLock<Mempool>>>,
+    /// Pending block last priced from the mempool with the mempool revision it was priced at,
+    /// reused until the mempool or the chain tip changes
+    mempool_pending_block: Arc<RwLock<Option<(u64, PendingBlockSnapshot)>>>,
+    /// Rolling history of block conditions, oldest to newest
+    block_features: Arc<RwLock<Vec<BlockFeatures>>>,
+    /// L1 fee parameters as of the chain tip, only tracked when an agent publishes the L1 data
+    /// fee record
+    l1_fee_params: Arc<RwLock<Option<L1FeeParams>>>,
+    /// Validated agents, in the same order as `chain_config.agents`
+    agents: Arc<Vec<AgentHandle>>,
     client: reqwest::Client,
 }
 
+/// A validated agent configuration with its loaded signer and model state
+struct AgentHandle {
+    config: AgentConfig,
+    signer: AgentSigner,
+    /// Address of `signer`, included in every prediction log line
+    address: Address,
+    model_state: ModelState,
+}
+
+impl AgentHandle {
+    fn new(config: AgentConfig, signer: AgentSigner, block_time_ms: u64) -> Result<Self> {
+        validate_records(&config)?;
+
+        if let AgentKind::Model(model) = &config.kind {
+            validate_model_config(model, &config.model_config)
+                .with_context(|| format!("Invalid {} agent", config.kind))?;
+        }
+
+        Ok(Self {
+            address: signer.address(),
+            config,
+            signer,
+            model_state: ModelState::new(block_time_ms),
+        })
+    }
+
+    fn prediction_span(&self) -> Span {
+        info_span!("prediction", agent = %self.config.kind, signer = %self.address)
+    }
+}
+
 impl GasAgent {
-    pub async fn new(chain_config: ChainConfig, config: &Config) -> Result<Self> {
-        let (rpc_client, rpc_chain_id, latest_block) =
+    pub async fn new(
+        chain_config: ChainConfig,
+        signers: Vec<AgentSigner>,
+        config: &Config,
+    ) -> Result<Self> {
+        let chain = chain_config.chain_params()?;
+
+        let (rpc_client, rpc_chain_id, mut latest_block) =
             init_rpc_client(&chain_config.json_rpc_url).await?;
 
-        let distribution =
-            block_to_block_distribution(&latest_block.transactions, &latest_block.base_fee_per_gas);
+        apply_fee_market(chain.fee_market, &mut latest_block);
+        apply_gas_source(&rpc_client, chain_config.gas_source, &mut latest_block).await;
 
-        let system_network =
-            SystemNetworkKey::new(chain_config.system.clone(), chain_config.network.clone());
+        let filtered = block_to_block_distribution(
+            &latest_block.transactions,
+            &latest_block.base_fee_per_gas,
+            &chain_config.system,
+            chain.fee_market,
+            &chain_config.distribution_filters,
+        );
 
-        if system_network.to_chain_id() != rpc_chain_id {
+        if chain.chain_id != rpc_chain_id {
             panic!(
-                "Configured chain: {} {} does not match RPC chain_id: {}",
-                &chain_config.system, &chain_config.network, rpc_chain_id
+                "Configured chain: {} {} (chain_id: {}) does not match RPC chain_id: {}",
+                &chain_config.system, &chain_config.network, chain.chain_id, rpc_chain_id
             );
         }
 
+        let has_blob_fee_agent = chain_config
+            .agents
+            .iter()
+            .any(|agent| matches!(agent.kind, AgentKind::BlobFee));
+
+        if has_blob_fee_agent && chain.blob_params.is_none() {
+            return Err(anyhow!(
+                "blob_fee agent configured for {} {}, which has no blob parameters. Set blob_params in the chain configuration",
+                &chain_config.system,
+                &chain_config.network
+            ));
+        }
+
+        let has_l1_data_fee_record =
+            uses_record(&chain_config.agents, RecordType::L1DataFeePerByte);
+
+        if has_l1_data_fee_record && !chain.op_stack {
+            return Err(anyhow!(
+                "{} record configured for {} {}, which is not an OP stack chain",
+                RecordType::L1DataFeePerByte,
+                &chain_config.system,
+                &chain_config.network
+            ));
+        }
+
+        if signers.len() != chain_config.agents.len() {
+            return Err(anyhow!(
+                "Expected {} signers, got {}",
+                chain_config.agents.len(),
+                signers.len()
+            ));
+        }
+
+        let agents = chain_config
+            .agents
+            .iter()
+            .cloned()
+            .zip(signers)
+            .map(|(agent, signer)| AgentHandle::new(agent, signer, chain.block_time_ms))
+            .collect::<Result<Vec<_>>>()?;
+
+        let l1_fee_params = if has_l1_data_fee_record {
+            get_l1_fee_params(&rpc_client, &latest_block).await
+        } else {
+            None
+        };
+
+        let mempool = match &chain_config.pending_block_data_source {
+            Some(PendingBlockDataSource::WebSocket { ttl_ms, .. }) => {
+                Some(Arc::new(RwLock::new(Mempool::new(*ttl_ms))))
+            }
+            _ => None,
+        };
+
+        let block_min = filtered.distribution.first().map(|bucket| bucket.wei);
+        let latest_header = BlockHeader::from(latest_block);
+        let latest_features = BlockFeatures::new(&latest_header, None, None, block_min);
+        let mut block_time = BlockTimeEstimator::new(chain.block_time_ms);
+        block_time.observe(&latest_header);
+
         Ok(Self {
             chain_config: chain_config.clone(),
+            chain,
             config: config.clone(),
             rpc_client,
-            chain_tip: Arc::new(RwLock::new(latest_block.into())),
-            block_distributions: Arc::new(RwLock::new(vec![distribution])),
-            pending_block_distribution: Arc::new(RwLock::new(None)),
+            chain_tip: Arc::new(RwLock::new(latest_header)),
+            block_time: Arc::new(RwLock::new(block_time)),
+            block_distributions: Arc::new(RwLock::new(vec![filtered.distribution])),
+            raw_block_min: Arc::new(RwLock::new(filtered.raw_min)),
+            pending_block: Arc::new(RwLock::new(None)),
+            mempool,
+            mempool_pending_block: Arc::new(RwLock::new(None)),
+            block_features: Arc::new(RwLock::new(vec![latest_features])),
+            l1_fee_params: Arc::new(RwLock::new(l1_fee_params)),
+            agents: Arc::new(agents),
             client: reqwest::Client::new(),
         })
     }
 
-    async fn create_prediction(&self, agent: &AgentConfig) -> Result<()> {
+    async fn create_prediction(&self, agent: &AgentHandle) -> Result<()> {
+        let Some(payload) = self
+            .create_payload(&agent.config, &agent.model_state)
+            .await?
+        else {
+            return Ok(());
+        };
+
+        publish_agent_payload(
+            &self.client,
+            self.config.collector_endpoint.as_str(),
+            &agent.signer,
+            self.chain.oracle_chain(),
+            &payload,
+        )
+        .await
+    }
+
+    /// Builds the payload for an agent, or `None` if there is nothing to publish yet
+    async fn create_payload(
+        &self,
+        agent: &AgentConfig,
+        model_state: &ModelState,
+    ) -> Result<Option<AgentPayload>> {
         let block_distributions = {
             let guard = self.block_distributions.read().await;
             guard.clone()
@@ -72,22 +255,27 @@ impl GasAgent {
         let last_distribution = block_distributions.last();
 
         let actual_min = last_distribution
-            .and_then(|dist| dist.first().map(|dist| dist.gwei))
-            .unwrap_or(0.0);
+            .and_then(|dist| dist.first().map(|dist| dist.wei))
+            .unwrap_or(0);
 
         let latest_block = { self.chain_tip.read().await.number };
 
-        match &agent.kind {
+        let mut payload = match &agent.kind {
             AgentKind::Model(model) => {
-                let pending_block_distribution = {
-                    let guard = self.pending_block_distribution.read().await;
+                let pending_block = self.current_pending_block().await;
+
+                let block_features = {
+                    let guard = self.block_features.read().await;
                     guard.clone()
                 };
 
                 let (price, settlement, from_block) = match apply_model(
                     model,
+                    &agent.model_config,
+                    model_state,
                     &block_distributions,
-                    pending_block_distribution,
+                    &block_features,
+                    pending_block,
                     latest_block,
                 )
                 .await
@@ -95,13 +283,16 @@ impl GasAgent {
                     Ok(result) => result,
                     Err(ModelError::InsufficientData { message }) => {
                         debug!("Insufficient data for model prediction: {}", message);
-                        return Ok(());
+                        return Ok(None);
+                    }
+                    Err(ModelError::StaleData { message }) => {
+                        warn!("Stale data for model prediction: {}", message);
+                        return Ok(None);
                     }
                     Err(e) => return Err(e.into()),
                 };
 
-                let price_wei = (price * 1_000_000_000f64).round() as u128;
-                let payload = AgentPayload {
+                AgentPayload {
                     schema_version: "1".to_string(),
                     from_block,
                     settlement,
@@ -109,16 +300,10 @@ impl GasAgent {
                     system: self.chain_config.system.clone(),
                     network: self.chain_config.network.clone(),
                     unit: PriceUnit::Wei,
-                    price: price_wei.to_string(),
-                };
-
-                publish_agent_payload(
-                    &self.client,
-                    self.config.collector_endpoint.as_str(),
-                    &agent.signer_key,
-                    &payload,
-                )
-                .await?;
+                    kind: PriceKind::GasPrice,
+                    price: price.to_string(),
+                    records: vec![],
+                }
             }
             AgentKind::Node => {
                 let node_price = self
@@ -131,33 +316,36 @@ impl GasAgent {
                     })
                     .ok();
 
-                if let Some(node_price) = node_price {
-                    let chain_tip = self.chain_tip.read().await.clone();
-                    let price_wei = (node_price * 1_000_000_000f64).round() as u128;
-                    let payload = AgentPayload {
-                        schema_version: "1".to_string(),
-                        from_block: chain_tip.number + 1,
-                        settlement: Settlement::Fast,
-                        timestamp: Utc::now(),
-                        system: self.chain_config.system.clone(),
-                        network: self.chain_config.network.clone(),
-                        unit: PriceUnit::Wei,
-                        price: price_wei.to_string(),
-                    };
-
-                    publish_agent_payload(
-                        &self.client,
-                        self.config.collector_endpoint.as_str(),
-                        &agent.signer_key,
-                        &payload,
-                    )
-                    .await?;
+                let Some(node_price) = node_price else {
+                    return Ok(None);
+                };
+
+                let chain_tip = self.chain_tip.read().await.clone();
+                AgentPayload {
+                    schema_version: "1".to_string(),
+                    from_block: chain_tip.number + 1,
+                    settlement: Settlement::Fast,
+                    timestamp: Utc::now(),
+                    system: self.chain_config.system.clone(),
+                    network: self.chain_config.network.clone(),
+                    unit: PriceUnit::Wei,
+                    kind: PriceKind::GasPrice,
+                    price: node_price.to_string(),
+                    records: vec![],
                 }
             }
             AgentKind::Target => {
                 let chain_tip = self.chain_tip.read().await.clone();
-                let price_wei = (actual_min * 1_000_000_000f64).round() as u128;
-                let payload = AgentPayload {
+                let raw_min = { *self.raw_block_min.read().await };
+
+                debug!(
+                    block = chain_tip.number,
+                    filtered_min = %actual_min,
+                    raw_min = ?raw_min,
+                    "Target block minimum"
+                );
+
+                AgentPayload {
                     schema_version: "1".to_string(),
                     from_block: chain_tip.number,
                     settlement: Settlement::Immediate,
@@ -165,37 +353,195 @@ impl GasAgent {
                     system: self.chain_config.system.clone(),
                     network: self.chain_config.network.clone(),
                     unit: PriceUnit::Wei,
-                    price: price_wei.to_string(),
+                    kind: PriceKind::GasPrice,
+                    price: actual_min.to_string(),
+                    records: vec![],
+                }
+            }
+            AgentKind::BlobFee => {
+                let chain_tip = self.chain_tip.read().await.clone();
+
+                let Some(params) = &self.chain.blob_params else {
+                    return Err(anyhow!("No blob parameters for blob_fee agent"));
                 };
 
-                publish_agent_payload(
-                    &self.client,
-                    self.config.collector_endpoint.as_str(),
-                    &agent.signer_key,
-                    &payload,
-                )
-                .await?;
+                let Some(blob_base_fee) = calc_blob_base_fee(&chain_tip, params) else {
+                    debug!(
+                        block = chain_tip.number,
+                        "Chain tip has no blob gas fields; skipping blob base fee"
+                    );
+                    return Ok(None);
+                };
+
+                AgentPayload {
+                    schema_version: "1".to_string(),
+                    from_block: chain_tip.number + 1,
+                    settlement: Settlement::Immediate,
+                    timestamp: Utc::now(),
+                    system: self.chain_config.system.clone(),
+                    network: self.chain_config.network.clone(),
+                    unit: PriceUnit::Wei,
+                    kind: PriceKind::BlobBaseFee,
+                    price: blob_base_fee.to_string(),
+                    records: vec![],
+                }
             }
+        };
+
+        payload.records = self.create_records(&agent.records).await;
+
+        Ok(Some(payload))
+    }
+
+    /// Computes the additional oracle records configured for an agent.
+    /// Records that cannot be computed yet, eg. without a base fee, are left out.
+    async fn create_records(&self, record_types: &[RecordType]) -> Vec<PayloadRecord> {
+        if record_types.is_empty() {
+            return vec![];
         }
 
-        Ok(())
+        let chain_tip = self.chain_tip.read().await.clone();
+        let latest_distribution = { self.block_distributions.read().await.last().cloned() };
+        let l1_fee_params = { *self.l1_fee_params.read().await };
+        let next_base_fee_params = self.chain.next_base_fee_params(&chain_tip);
+
+        record_types
+            .iter()
+            .filter_map(|record_type| {
+                let value = match record_type {
+                    RecordType::BaseFeePerGas => {
+                        match calc_base_fee(&chain_tip, next_base_fee_params) {
+                            Ok(base_fee) => base_fee.map(u128::from),
+                            Err(e) => {
+                                warn!(error = %e, "Failed to derive the next base fee");
+                                None
+                            }
+                        }
+                    }
+                    RecordType::BlobBaseFeePerGas => self
+                        .chain
+                        .blob_params
+                        .as_ref()
+                        .and_then(|params| calc_blob_base_fee(&chain_tip, params)),
+                    RecordType::L1DataFeePerByte => {
+                        l1_fee_params.map(|params| params.data_fee_per_byte())
+                    }
+                    _ => record_type.priority_fee_percentile().and_then(|percentile| {
+                        // Effective priority fees of the latest block
+                        let base_fee = chain_tip.base_fee_per_gas.unwrap_or(0) as u128;
+                        percentile_wei(latest_distribution.as_ref()?, percentile)
+                            .map(|wei| wei.saturating_sub(base_fee))
+                    }),
+                };
+
+                if value.is_none() {
+                    debug!(record = %record_type, "Record not available; leaving it out of the payload");
+                }
+
+                value.map(|value| PayloadRecord {
+                    record_type: *record_type,
+                    value: value.to_string(),
+                })
+            })
+            .collect()
     }
 
     async fn handle_new_block(&self, block: Block) -> Result<()> {
         let new_chain_tip = BlockHeader::from(block.clone());
 
-        let new_distribution =
-            block_to_block_distribution(&block.transactions, &block.base_fee_per_gas);
+        let filtered = block_to_block_distribution(
+            &block.transactions,
+            &block.base_fee_per_gas,
+            &self.chain_config.system,
+            self.chain.fee_market,
+            &self.chain_config.distribution_filters,
+        );
 
         // Update chain tip
         {
             *self.chain_tip.write().await = new_chain_tip.clone();
         }
 
+        {
+            *self.raw_block_min.write().await = filtered.raw_min;
+        }
+
+        if uses_record(&self.chain_config.agents, RecordType::L1DataFeePerByte) {
+            let l1_fee_params = get_l1_fee_params(&self.rpc_client, &block).await;
+            *self.l1_fee_params.write().await = l1_fee_params;
+        }
+
+        if let Some(blob_gas_used) = block.blob_gas_used {
+            let blob_transactions = block.transactions.iter().filter(|tx| tx.blob_count > 0);
+
+            debug!(
+                block = block.number,
+                blob_gas_used,
+                excess_blob_gas = ?block.excess_blob_gas,
+                blobs = blob_transactions.clone().map(|tx| tx.blob_count).sum::<usize>(),
+                min_max_fee_per_blob_gas = ?blob_transactions.filter_map(|tx| tx.max_fee_per_blob_gas).min(),
+                "Blob gas"
+            );
+        }
+
+        let block_time_ms = {
+            let mut block_time = self.block_time.write().await;
+            block_time.observe(&new_chain_tip);
+            block_time.block_time_ms()
+        };
+
+        ChainMetrics::get().record_block_time(
+            &[
+                KeyValue::new("system", self.chain_config.system.to_string()),
+                KeyValue::new("network", self.chain_config.network.to_string()),
+            ],
+            block_time_ms,
+        );
+
+        let block_min = filtered.distribution.first().map(|bucket| bucket.wei);
+        self.observe_model_states(block.number, block_min, block_time_ms);
+
+        // Update block features
+        {
+            // The pending block is only representative of this block if it was taken against
+            // the previous chain tip or later
+            let pending_block = self
+                .current_pending_block()
+                .await
+                .filter(|snapshot| snapshot.chain_tip + 1 >= new_chain_tip.number);
+            let mut block_features = self.block_features.write().await;
+
+            let features = BlockFeatures::new(
+                &new_chain_tip,
+                block_features.last(),
+                pending_block
+                    .as_ref()
+                    .map(|snapshot| &snapshot.distribution),
+                block_min,
+            );
+            block_features.push(features);
+
+            let block_features_len = block_features.len();
+            if block_features_len > MAX_NUM_BLOCK_FEATURES {
+                block_features.drain(0..block_features_len - MAX_NUM_BLOCK_FEATURES);
+            }
+        }
+
+        if let Some(mempool) = &self.mempool {
+            let mut mempool = mempool.write().await;
+            mempool.remove_included(&block.transactions);
+            mempool.evict_expired(Utc::now());
+            debug!(
+                block = block.number,
+                pending = mempool.len(),
+                "Evicted included and expired transactions from the mempool"
+            );
+        }
+
         // Update block distributions
         {
             let mut distributions = self.block_distributions.write().await;
-            distributions.push(new_distribution);
+            distributions.push(filtered.distribution);
 
             let distributions_len = distributions.len();
 
@@ -206,31 +552,74 @@ impl GasAgent {
             }
         }
 
-        for agent in self.chain_config.agents.iter() {
-            if matches!(&agent.prediction_trigger, &PredictionTrigger::Block) {
-                let agent_clone = agent.clone();
+        for (index, agent) in self.agents.iter().enumerate() {
+            if matches!(&agent.config.prediction_trigger, &PredictionTrigger::Block) {
                 let self_clone = self.clone();
-                tokio::spawn(async move {
-                    if let Err(e) = self_clone.create_prediction(&agent_clone).await {
-                        error!(error = %e, "Failed to create prediction");
+                let span = agent.prediction_span();
+                tokio::spawn(
+                    async move {
+                        let agent = &self_clone.agents[index];
+                        if let Err(e) = self_clone.create_prediction(agent).await {
+                            error!(error = %e, "Failed to create prediction");
+                        }
                     }
-                });
+                    .instrument(span),
+                );
             }
         }
 
         Ok(())
     }
 
-    pub async fn poll_blocks(&self) {
-        // Get block time from the system network configuration
-        let system_network = SystemNetworkKey::new(
-            self.chain_config.system.clone(),
-            self.chain_config.network.clone(),
-        );
+    /// Lets stateful models learn from the realised minimum of a new block and the measured
+    /// block time
+    fn observe_model_states(&self, block_number: u64, block_min: Option<u128>, block_time_ms: u64) {
+        for (index, agent) in self.agents.iter().enumerate() {
+            agent.model_state.set_block_time_ms(block_time_ms);
 
-        let block_time_ms = system_network.to_block_time();
+            let Some(status) = agent.model_state.observe_block(block_number, block_min) else {
+                continue;
+            };
+
+            info!(
+                system = %self.chain_config.system,
+                network = %self.chain_config.network,
+                agent = index,
+                signer = %agent.address,
+                block = block_number,
+                premium = status.premium,
+                inclusion_rate = ?status.inclusion_rate,
+                included = status.included,
+                resolved = status.resolved,
+                tracked = status.tracked,
+                "Self tuning controller updated"
+            );
+
+            let labels = [
+                KeyValue::new("system", self.chain_config.system.to_string()),
+                KeyValue::new("network", self.chain_config.network.to_string()),
+                KeyValue::new("agent", index as i64),
+            ];
+
+            SelfTuningMetrics::get().record(
+                &labels,
+                status.premium,
+                status.inclusion_rate,
+                status.tracked,
+            );
+        }
+    }
+
+    pub async fn poll_blocks(&self) {
+        let mut schedule = PollSchedule::new();
+        let labels = [
+            KeyValue::new("system", self.chain_config.system.to_string()),
+            KeyValue::new("network", self.chain_config.network.to_string()),
+        ];
 
         loop {
+            let block_time_ms = { self.block_time.read().await.block_time_ms() };
+
             // Calculate wait time based on chain tip timestamp
             let chain_tip_timestamp = {
                 let chain_tip = self.chain_tip.read().await;
@@ -240,12 +629,11 @@ impl GasAgent {
             let now = chrono::Utc::now();
             let time_since_last_block = (now - chain_tip_timestamp).num_milliseconds();
 
-            // Wait time = block_time - time_since_last_block
-            let wait_ms = if time_since_last_block < block_time_ms as i64 {
-                block_time_ms as i64 - time_since_last_block
-            } else {
-                0 // no wait if we're past the expected time
-            };
+            // Wait until the next block usually arrives, no wait if we're past that time
+            let first_poll_ms = block_time_ms + schedule.first_poll_delay_ms(block_time_ms);
+            let wait_ms = (first_poll_ms as i64 - time_since_last_block).max(0);
+            let expected_timestamp =
+                chain_tip_timestamp + chrono::Duration::milliseconds(block_time_ms as i64);
 
             debug!(
                 "Waiting: {wait_ms}ms, Time Since Last Block: {}",
@@ -261,8 +649,11 @@ impl GasAgent {
             let max_backoff_ms: u64 = ERROR_RETRY_MAX_BACKOFF_MS;
 
             while get_new_block {
+                ChainMetrics::get().record_block_poll(&labels);
+
                 match get_latest_block(&self.rpc_client).await {
-                    Ok(block) => {
+                    Ok(mut block) => {
+                        let received_at = chrono::Utc::now();
                         // Reset backoff state after a successful RPC response
                         backoff_ms = ERROR_RETRY_BASE_BACKOFF_MS;
                         debug!(
@@ -275,6 +666,12 @@ impl GasAgent {
                         if block.number > current_height {
                             let gap = block.number - current_height;
 
+                            let latency_ms =
+                                (received_at - block.timestamp).num_milliseconds().max(0) as u64;
+                            schedule.observe_arrival(latency_ms);
+                            ChainMetrics::get().record_block_detection_latency(&labels, latency_ms);
+                            debug!(block = block.number, latency_ms, "Block detected");
+
                             if gap > 1 {
                                 warn!(
                                     "Missed blocks for System: {}, Network: {}! Last block height: {}, new block height: {}, GAP: {}",
@@ -283,14 +680,33 @@ impl GasAgent {
                                 );
                             }
 
+                            let new_height = block.number;
+
+                            apply_fee_market(self.chain.fee_market, &mut block);
+                            apply_gas_source(
+                                &self.rpc_client,
+                                self.chain_config.gas_source,
+                                &mut block,
+                            )
+                            .await;
+
                             if let Err(e) = self.handle_new_block(block).await {
                                 error!(error = %e, "Failed to handle new block");
                             }
 
+                            // Evicted after handling so predictions don't wait on the skipped
+                            // blocks, the mempool snapshot catches up on the next tick
+                            if gap > 1 {
+                                self.evict_skipped_blocks(current_height, new_height).await;
+                            }
+
                             get_new_block = false;
                         } else {
-                            // No new block updated yet, wait 250ms and try again
-                            tokio::time::sleep(Duration::from_millis(250)).await;
+                            // No new block yet, retry on the schedule of usual block arrivals
+                            let elapsed_ms =
+                                (received_at - expected_timestamp).num_milliseconds().max(0) as u64;
+                            let retry_ms = schedule.retry_interval_ms(block_time_ms, elapsed_ms);
+                            tokio::time::sleep(Duration::from_millis(retry_ms)).await;
                         }
                     }
                     Err(e) => {
@@ -310,54 +726,171 @@ impl GasAgent {
         }
     }
 
-    async fn poll_pending_block(&self, pending_block_source: PendingBlockDataSource) {
-        match pending_block_source {
-            PendingBlockDataSource::JsonRpc {
-                url,
-                method,
-                params,
-                poll_rate_ms,
-            } => {
-                info!("Polling pending block from JSON-RPC: url: {}, method: {}, params: {:?}, polling rate: {}ms", url, method, params, poll_rate_ms);
+    /// Evicts the transactions of the blocks between `last_height` and `new_height` from the
+    /// mempool, as only the latest block of a poll is handled. The skipped blocks are fetched
+    /// concurrently.
+    async fn evict_skipped_blocks(&self, last_height: u64, new_height: u64) {
+        let Some(mempool) = &self.mempool else {
+            return;
+        };
 
-                let rpc_url = Url::parse(&url)
-                    .context("Invalid block JSON rpc url")
-                    .expect("Valid JSON RPC url for pending block");
+        let first_skipped =
+            (last_height + 1).max(new_height.saturating_sub(MAX_NUM_SKIPPED_BLOCKS_EVICTED));
 
-                let client = get_rpc_client(rpc_url);
+        let blocks = join_all((first_skipped..new_height).map(|number| async move {
+            (number, self.rpc_client.get_block_by_number(number).await)
+        }))
+        .await;
 
-                loop {
-                    match client.get_pending_block(&method, params.clone()).await {
-                        Ok(transactions) => {
-                            let chain_tip = { self.chain_tip.read().await.clone() };
-                            let next_base_fee = calc_base_fee(&chain_tip);
-                            let distribution =
-                                block_to_block_distribution(&transactions, &next_base_fee);
+        for (number, block) in blocks {
+            match block {
+                Ok(block) => mempool.write().await.remove_included(&block.transactions),
+                Err(e) => {
+                    warn!(error = %e, block = number, "Failed to get skipped block; its transactions are left to expire from the mempool");
+                }
+            }
+        }
+    }
 
-                            {
-                                let mut pending_block_distribution =
-                                    self.pending_block_distribution.write().await;
+    async fn poll_pending_block(
+        &self,
+        url: &str,
+        request: &PendingBlockRequest,
+        poll_rate_ms: u64,
+    ) {
+        info!(url, ?request, "Polling pending block");
 
-                                *pending_block_distribution = Some(distribution);
-                            }
-                        }
-                        Err(e) => {
-                            error!(error = %e, "Failed to get pending block");
-                        }
-                    }
+        let rpc_url = Url::parse(url)
+            .context("Invalid block JSON rpc url")
+            .expect("Valid JSON RPC url for pending block");
+
+        let client = get_rpc_client(rpc_url);
+
+        loop {
+            let transactions = match request {
+                PendingBlockRequest::Block { method, params } => {
+                    client.get_pending_block(method, params.clone()).await
+                }
+                PendingBlockRequest::TxpoolContent => client.get_txpool_content().await,
+            };
+
+            match transactions {
+                Ok(pending) => self.update_pending_block(pending).await,
+                Err(e) => {
+                    error!(error = %e, "Failed to get pending block");
+                }
+            }
 
-                    // Sleep for poll rate duration
-                    tokio::time::sleep(Duration::from_millis(poll_rate_ms)).await;
+            // Sleep for poll rate duration
+            tokio::time::sleep(Duration::from_millis(poll_rate_ms)).await;
+        }
+    }
+
+    /// Keeps the mempool up to date from a pending transaction subscription, reconnecting
+    /// whenever the connection fails
+    async fn stream_pending_transactions(&self, url: &str) {
+        let Some(mempool) = &self.mempool else {
+            return;
+        };
+
+        info!(url, "Streaming pending transactions");
+
+        loop {
+            if let Err(e) = stream_pending_transactions(url, mempool).await {
+                error!(error = %e, retry_ms = WEBSOCKET_RECONNECT_DELAY_MS, "Pending transaction stream failed; reconnecting");
+            }
+
+            tokio::time::sleep(Duration::from_millis(WEBSOCKET_RECONNECT_DELAY_MS)).await;
+        }
+    }
+
+    async fn update_pending_block(&self, pending: PendingTransactions) {
+        let mut snapshot = self
+            .price_pending_transactions(&pending.transactions, Utc::now())
+            .await;
+
+        // A pending block built on an older block than the agent's chain tip, eg. by a lagging
+        // node, is stamped with the block it builds on so it can be told apart as stale
+        if let Some(parent_number) = pending.parent_number {
+            snapshot.chain_tip = parent_number;
+        }
+
+        *self.pending_block.write().await = Some(snapshot);
+    }
+
+    /// Latest pending block, derived from the mempool when pending transactions are streamed
+    async fn current_pending_block(&self) -> Option<PendingBlockSnapshot> {
+        let Some(mempool) = &self.mempool else {
+            return self.pending_block.read().await.clone();
+        };
+
+        let chain_tip = { self.chain_tip.read().await.number };
+
+        let (transactions, updated_at, revision) = {
+            let mempool = mempool.read().await;
+            if mempool.is_empty() {
+                return None;
+            }
+            let updated_at = mempool.updated_at()?;
+
+            if let Some((revision, snapshot)) = &*self.mempool_pending_block.read().await {
+                if *revision == mempool.revision() && snapshot.chain_tip == chain_tip {
+                    // Transactions announced again still show the stream is alive
+                    return Some(PendingBlockSnapshot {
+                        fetched_at: updated_at,
+                        ..snapshot.clone()
+                    });
                 }
             }
+
+            (mempool.transactions(), updated_at, mempool.revision())
+        };
+
+        let snapshot = self
+            .price_pending_transactions(&transactions, updated_at)
+            .await;
+        *self.mempool_pending_block.write().await = Some((revision, snapshot.clone()));
+
+        Some(snapshot)
+    }
+
+    /// Prices pending transactions fetched at `fetched_at` against the next base fee
+    async fn price_pending_transactions(
+        &self,
+        transactions: &[Transaction],
+        fetched_at: DateTime<Utc>,
+    ) -> PendingBlockSnapshot {
+        let chain_tip = { self.chain_tip.read().await.clone() };
+        let next_base_fee = calc_base_fee(&chain_tip, self.chain.next_base_fee_params(&chain_tip))
+            .unwrap_or_else(|e| {
+                warn!(error = %e, "Failed to derive the next base fee");
+                None
+            });
+        let distribution = block_to_block_distribution(
+            transactions,
+            &next_base_fee,
+            &self.chain_config.system,
+            self.chain.fee_market,
+            &self.chain_config.distribution_filters,
+        )
+        .distribution;
+
+        PendingBlockSnapshot {
+            distribution,
+            fetched_at,
+            chain_tip: chain_tip.number,
         }
     }
 
-    async fn poll_predictions(&self, agent: &AgentConfig, rate_ms: u64) {
+    async fn poll_predictions(&self, agent: &AgentHandle, rate_ms: u64) {
         loop {
             tokio::time::sleep(Duration::from_millis(rate_ms)).await;
-            if let Err(e) = self.create_prediction(agent).await {
-                error!("Failed to create prediction: {}", e);
+            if let Err(e) = self
+                .create_prediction(agent)
+                .instrument(agent.prediction_span())
+                .await
+            {
+                error!(agent = %agent.config.kind, signer = %agent.address, "Failed to create prediction: {}", e);
             }
         }
     }
@@ -365,12 +898,25 @@ impl GasAgent {
     pub async fn run(&self) -> Result<()> {
         if let Some(pending_block_source) = &self.chain_config.pending_block_data_source {
             let pending_block_poll_agent_clone = self.clone();
-            let pending_block_source_clone = pending_block_source.clone();
+            let pending_block_feed = pending_block_source.feed();
 
             tokio::spawn(async move {
-                pending_block_poll_agent_clone
-                    .poll_pending_block(pending_block_source_clone)
-                    .await;
+                match pending_block_feed {
+                    PendingBlockFeed::Polled {
+                        url,
+                        request,
+                        poll_rate_ms,
+                    } => {
+                        pending_block_poll_agent_clone
+                            .poll_pending_block(&url, &request, poll_rate_ms)
+                            .await
+                    }
+                    PendingBlockFeed::Streamed { url } => {
+                        pending_block_poll_agent_clone
+                            .stream_pending_transactions(&url)
+                            .await
+                    }
+                }
             });
         }
 
@@ -380,14 +926,14 @@ impl GasAgent {
             block_poll_agent_clone.poll_blocks().await;
         });
 
-        for agent in self.chain_config.agents.iter() {
-            if let PredictionTrigger::Poll { rate_ms } = agent.prediction_trigger {
+        for (index, agent) in self.agents.iter().enumerate() {
+            if let PredictionTrigger::Poll { rate_ms } = agent.config.prediction_trigger {
                 let trigger_poll_agent_clone = self.clone();
-                let agent_clone = agent.clone();
 
                 tokio::spawn(async move {
+                    let agent = &trigger_poll_agent_clone.agents[index];
                     trigger_poll_agent_clone
-                        .poll_predictions(&agent_clone, rate_ms)
+                        .poll_predictions(agent, rate_ms)
                         .await;
                 });
             }
@@ -413,13 +959,100 @@ pub async fn init_rpc_client(url: &str) -> Result<(RpcClient, u64, Block)> {
     Ok((client, chain_id, block))
 }
 
+/// Checks that an agent publishes every record type at most once, leaves the gas price record
+/// type to predicted gas prices and publishes no record types with provisional IDs
+fn validate_records(agent: &AgentConfig) -> Result<()> {
+    if let Some(record_type) = agent.records.iter().find(|record| record.is_provisional()) {
+        return Err(anyhow!(
+            "The {record_type} record has no type ID in the oracle registry yet and cannot be published"
+        ));
+    }
+
+    if agent.records.contains(&RecordType::MaxPriorityFeePerGasP99) {
+        return Err(anyhow!(
+            "The {} record is reserved for predicted gas prices",
+            RecordType::MaxPriorityFeePerGasP99
+        ));
+    }
+
+    let mut record_types = HashSet::from([agent.kind.price_kind().record_type()]);
+
+    for record_type in agent.records.iter() {
+        if !record_types.insert(*record_type) {
+            return Err(anyhow!(
+                "{} agent publishes the {} record more than once",
+                agent.kind,
+                record_type
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn uses_record(agents: &[AgentConfig], record_type: RecordType) -> bool {
+    agents
+        .iter()
+        .any(|agent| agent.records.contains(&record_type))
+}
+
+/// Reads the L1 fee parameters from the L1 attributes transaction of an OP stack block, or from
+/// the L1Block predeploy when the block has none
+async fn get_l1_fee_params(client: &RpcClient, block: &Block) -> Option<L1FeeParams> {
+    if let Some(params) = L1FeeParams::from_block(block) {
+        return Some(params);
+    }
+
+    match L1FeeParams::fetch(client, block.number).await {
+        Ok(params) => Some(params),
+        Err(e) => {
+            warn!(
+                error = %e,
+                block = block.number,
+                "Failed to get L1 fee parameters; leaving out the L1 data fee"
+            );
+            None
+        }
+    }
+}
+
+/// Drops the base fee of blocks on legacy fee market chains, where transactions pay their gas price
+fn apply_fee_market(fee_market: FeeMarket, block: &mut Block) {
+    if fee_market == FeeMarket::Legacy {
+        block.base_fee_per_gas = None;
+    }
+}
+
+/// Fills in the gas used by each transaction from the block receipts when configured to do so.
+/// On failure the transaction gas limits from the block body are used instead.
+async fn apply_gas_source(client: &RpcClient, gas_source: GasSource, block: &mut Block) {
+    if gas_source != GasSource::Receipts {
+        return;
+    }
+
+    match client.get_block_receipts(block.number).await {
+        Ok(gas_used) => block.apply_receipts(&gas_used),
+        Err(e) => {
+            warn!(
+                error = %e,
+                block = block.number,
+                "Failed to get block receipts; using transaction gas limits"
+            );
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::{DistributionFilters, SignerKey};
     use crate::distribution::Bucket;
     use crate::rpc::Transaction;
-    use crate::types::{Network, System};
+    use crate::types::{ModelKind, Network, System};
+    use alloy::primitives::Bytes;
     use chrono::TimeZone;
+    use serde_json::json;
+    use std::collections::HashMap;
     use std::sync::Arc;
     use tokio::sync::RwLock;
 
@@ -431,9 +1064,18 @@ mod tests {
     ) -> Transaction {
         Transaction {
             hash: hash.to_string(),
+            tx_type: None,
+            from: None,
+            nonce: None,
+            to: None,
             gas_price,
             max_fee_per_gas,
             max_priority_fee_per_gas,
+            gas: Some(21_000),
+            gas_used: None,
+            max_fee_per_blob_gas: None,
+            blob_count: 0,
+            input: Bytes::new(),
         }
     }
 
@@ -449,16 +1091,43 @@ mod tests {
             gas_limit: 30_000_000,
             gas_used: 15_000_000,
             base_fee_per_gas,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            extra_data: Default::default(),
             transactions,
         }
     }
 
+    fn create_test_agent_config(kind: AgentKind) -> AgentConfig {
+        AgentConfig {
+            kind,
+            // Fixed private key for reproducibility (DO NOT USE IN PROD)
+            signer_key: SignerKey::PrivateKey(
+                "0x59c6995e998f97a5a0044976f3ac3b8c9f27a7d9b3bcd2b0d7aeb5f3e9eae7c6".to_string(),
+            ),
+            prediction_trigger: PredictionTrigger::Block,
+            model_config: Default::default(),
+            records: vec![],
+        }
+    }
+
+    fn create_test_agent_handle(config: AgentConfig) -> AgentHandle {
+        let signer = config.signer_key.load().unwrap();
+        AgentHandle::new(config, signer, 12_000).unwrap()
+    }
+
     fn create_test_gas_agent() -> GasAgent {
         let chain_config = ChainConfig {
             system: System::Ethereum,
             network: Network::Mainnet,
+            chain: None,
             json_rpc_url: "http://localhost:8545".to_string(),
             pending_block_data_source: None,
+            gas_source: GasSource::Transaction,
+            distribution_filters: DistributionFilters::default(),
+            blob_params: None,
+            base_fee_params: None,
+            base_fee_params_from_extra_data: None,
             agents: vec![],
         };
 
@@ -482,18 +1151,35 @@ mod tests {
             Some(10_000_000_000), // 10 gwei base fee
         );
 
+        let chain = chain_config.chain_params().unwrap();
+
         let initial_distribution = block_to_block_distribution(
             &initial_block.transactions,
             &initial_block.base_fee_per_gas,
+            &chain_config.system,
+            chain.fee_market,
+            &chain_config.distribution_filters,
         );
 
+        let initial_header = BlockHeader::from(initial_block);
+        let mut block_time = BlockTimeEstimator::new(chain.block_time_ms);
+        block_time.observe(&initial_header);
+
         GasAgent {
             chain_config,
+            chain,
             config,
             rpc_client,
-            chain_tip: Arc::new(RwLock::new(initial_block.into())),
-            block_distributions: Arc::new(RwLock::new(vec![initial_distribution])),
-            pending_block_distribution: Arc::new(RwLock::new(None)),
+            block_time: Arc::new(RwLock::new(block_time)),
+            chain_tip: Arc::new(RwLock::new(initial_header)),
+            block_distributions: Arc::new(RwLock::new(vec![initial_distribution.distribution])),
+            raw_block_min: Arc::new(RwLock::new(initial_distribution.raw_min)),
+            pending_block: Arc::new(RwLock::new(None)),
+            mempool: None,
+            mempool_pending_block: Arc::new(RwLock::new(None)),
+            block_features: Arc::new(RwLock::new(vec![])),
+            l1_fee_params: Arc::new(RwLock::new(None)),
+            agents: Arc::new(vec![]),
             client: reqwest::Client::new(),
         }
     }
@@ -534,6 +1220,21 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_block_time_update() {
+        let gas_agent = create_test_gas_agent();
+        assert_eq!(gas_agent.block_time.read().await.block_time_ms(), 12_000);
+
+        // A missed slot lengthens the measured block time
+        let new_block = create_test_block(1001, 1700000024, vec![], Some(10_000_000_000));
+        gas_agent.handle_new_block(new_block).await.unwrap();
+
+        assert_eq!(
+            gas_agent.block_time.read().await.block_time_ms(),
+            (12_000 * 8 + 24_000) / 9
+        );
+    }
+
     #[tokio::test]
     async fn test_block_distributions_update() {
         let gas_agent = create_test_gas_agent();
@@ -570,11 +1271,11 @@ mod tests {
 
             // Check that the distribution is sorted ascending
             for i in 1..last_dist.len() {
-                assert!(last_dist[i].gwei >= last_dist[i - 1].gwei);
+                assert!(last_dist[i].wei >= last_dist[i - 1].wei);
             }
 
             // Check that 0 gwei is not included
-            assert!(last_dist.iter().all(|bucket| bucket.gwei > 0.0));
+            assert!(last_dist.iter().all(|bucket| bucket.wei > 0));
         }
     }
 
@@ -626,15 +1327,13 @@ mod tests {
         gas_agent.handle_new_block(new_block).await.unwrap();
 
         // The Target agent should report the actual minimum (15 gwei) for the current block (1001)
-        // We can't directly test the published payload without mocking the publish function,
-        // but we can verify the distribution was created correctly
         {
             let distributions = gas_agent.block_distributions.read().await;
             let last_dist = distributions.last().unwrap();
 
             // The minimum should be 15 gwei (excluding 0 gas price)
-            let actual_min = last_dist.first().map(|bucket| bucket.gwei).unwrap_or(0.0);
-            assert_eq!(actual_min, 15.0);
+            let actual_min = last_dist.first().map(|bucket| bucket.wei).unwrap_or(0);
+            assert_eq!(actual_min, 15_000_000_000);
         }
 
         // Verify chain tip is correct for Target payload
@@ -729,14 +1428,10 @@ mod tests {
             assert!(last_dist.len() >= 2);
 
             // The EIP-1559 transaction should result in 12 gwei (base + priority fee)
-            assert!(last_dist
-                .iter()
-                .any(|bucket| (bucket.gwei - 12.0).abs() < 0.001));
+            assert!(last_dist.iter().any(|bucket| bucket.wei == 12_000_000_000));
 
             // The legacy transaction should result in 25 gwei
-            assert!(last_dist
-                .iter()
-                .any(|bucket| (bucket.gwei - 25.0).abs() < 0.001));
+            assert!(last_dist.iter().any(|bucket| bucket.wei == 25_000_000_000));
         }
     }
 
@@ -766,46 +1461,749 @@ mod tests {
         }
     }
 
+    /// Serves `eth_getBlockByNumber` for blocks with the given transaction hashes, keyed by
+    /// block number
+    async fn mock_block_rpc(blocks: HashMap<u64, Vec<&'static str>>) -> RpcClient {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = vec![];
+                let mut chunk = [0u8; 1024];
+
+                let body = loop {
+                    let n = stream.read(&mut chunk).await.unwrap();
+                    request.extend_from_slice(&chunk[..n]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some((_, body)) = text.split_once("\r\n\r\n") {
+                        if serde_json::from_str::<serde_json::Value>(body).is_ok() {
+                            break body.to_string();
+                        }
+                    }
+                };
+
+                let request: serde_json::Value = serde_json::from_str(&body).unwrap();
+                let number_hex = request["params"][0].as_str().unwrap();
+                let number = u64::from_str_radix(number_hex.trim_start_matches("0x"), 16).unwrap();
+                let transactions: Vec<serde_json::Value> = blocks[&number]
+                    .iter()
+                    .map(|hash| json!({"hash": hash, "gasPrice": "0x3b9aca00"}))
+                    .collect();
+
+                let body = json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "result": {
+                        "number": number_hex,
+                        "timestamp": "0x6553f100",
+                        "gasUsed": "0x5208",
+                        "gasLimit": "0x1c9c380",
+                        "transactions": transactions
+                    }
+                })
+                .to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        RpcClient::new(url)
+    }
+
+    #[tokio::test]
+    async fn test_evict_skipped_blocks() {
+        let gas_agent = GasAgent {
+            rpc_client: mock_block_rpc(HashMap::from([
+                (1001, vec!["0xa"]),
+                (1002, vec!["0xb", "0xd"]),
+            ]))
+            .await,
+            mempool: Some(Arc::new(RwLock::new(Mempool::new(60_000)))),
+            ..create_test_gas_agent()
+        };
+
+        {
+            let mut mempool = gas_agent.mempool.as_ref().unwrap().write().await;
+            for hash in ["0xa", "0xb", "0xc"] {
+                mempool.insert(
+                    create_test_transaction(hash, Some(25_000_000_000), None, None),
+                    Utc::now(),
+                );
+            }
+        }
+
+        // A poll found block 1003 after the tip at 1000, skipping 1001 and 1002
+        gas_agent.evict_skipped_blocks(1000, 1003).await;
+
+        let mempool = gas_agent.mempool.as_ref().unwrap().read().await;
+        let hashes: Vec<String> = mempool
+            .transactions()
+            .into_iter()
+            .map(|tx| tx.hash)
+            .collect();
+        assert_eq!(hashes, vec!["0xc"]);
+    }
+
+    #[tokio::test]
+    async fn test_mempool_pending_block_distribution() {
+        let gas_agent = GasAgent {
+            mempool: Some(Arc::new(RwLock::new(Mempool::new(60_000)))),
+            ..create_test_gas_agent()
+        };
+
+        // Nothing pending before the first transaction is streamed
+        assert!(gas_agent.current_pending_block().await.is_none());
+
+        {
+            let mut mempool = gas_agent.mempool.as_ref().unwrap().write().await;
+            for (hash, gas_price) in [
+                ("0xa", 25_000_000_000),
+                ("0xb", 30_000_000_000),
+                ("0xc", 40_000_000_000),
+            ] {
+                mempool.insert(
+                    create_test_transaction(hash, Some(gas_price), None, None),
+                    Utc::now(),
+                );
+            }
+        }
+
+        let pending = gas_agent.current_pending_block().await.unwrap();
+        assert_eq!(pending.chain_tip, 1000);
+        assert_eq!(
+            pending
+                .distribution
+                .iter()
+                .map(|bucket| bucket.count)
+                .sum::<u32>(),
+            3
+        );
+
+        // Included transactions leave the mempool with the new block
+        let new_block = create_test_block(
+            1001,
+            1700000012,
+            vec![create_test_transaction(
+                "0xb",
+                Some(30_000_000_000),
+                None,
+                None,
+            )],
+            Some(10_000_000_000),
+        );
+        gas_agent.handle_new_block(new_block).await.unwrap();
+
+        let pending = gas_agent.current_pending_block().await.unwrap();
+        // Priced against the new chain tip
+        assert_eq!(pending.chain_tip, 1001);
+        let pending = pending.distribution;
+        assert_eq!(pending.iter().map(|bucket| bucket.count).sum::<u32>(), 2);
+        assert_eq!(pending.first().unwrap().wei, 25_000_000_000);
+        assert_eq!(pending.last().unwrap().wei, 40_000_000_000);
+
+        // Block features count the pending transactions before the block's were evicted
+        let features = gas_agent.block_features.read().await;
+        assert_eq!(features.last().unwrap().pending_tx_count, 3);
+    }
+
+    #[tokio::test]
+    async fn test_mempool_pending_block_cache() {
+        let gas_agent = GasAgent {
+            mempool: Some(Arc::new(RwLock::new(Mempool::new(60_000)))),
+            ..create_test_gas_agent()
+        };
+        let mempool = gas_agent.mempool.as_ref().unwrap();
+        let insert = |hash: &'static str| async move {
+            mempool.write().await.insert(
+                create_test_transaction(hash, Some(25_000_000_000), None, None),
+                Utc::now(),
+            );
+        };
+        let pending_count = |snapshot: &PendingBlockSnapshot| {
+            snapshot
+                .distribution
+                .iter()
+                .map(|bucket| bucket.count)
+                .sum::<u32>()
+        };
+
+        insert("0xa").await;
+        let pending = gas_agent.current_pending_block().await.unwrap();
+        assert_eq!(pending_count(&pending), 1);
+
+        // Reused while the mempool and the chain tip are unchanged
+        if let Some((_, cached)) = &mut *gas_agent.mempool_pending_block.write().await {
+            cached.distribution.clear();
+        }
+        insert("0xa").await;
+        let pending = gas_agent.current_pending_block().await.unwrap();
+        assert_eq!(pending_count(&pending), 0);
+
+        // Priced again once a new transaction arrives
+        insert("0xb").await;
+        let pending = gas_agent.current_pending_block().await.unwrap();
+        assert_eq!(pending_count(&pending), 2);
+
+        // and once the chain tip moves
+        if let Some((_, cached)) = &mut *gas_agent.mempool_pending_block.write().await {
+            cached.distribution.clear();
+        }
+        gas_agent.chain_tip.write().await.number += 1;
+        let pending = gas_agent.current_pending_block().await.unwrap();
+        assert_eq!(pending_count(&pending), 2);
+        assert_eq!(pending.chain_tip, 1001);
+    }
+
     #[tokio::test]
     async fn test_pending_block_distribution() {
         let gas_agent = create_test_gas_agent();
 
         // Initially no pending block distribution
         {
-            let pending = gas_agent.pending_block_distribution.read().await;
+            let pending = gas_agent.pending_block.read().await;
             assert!(pending.is_none());
         }
 
         // Simulate setting a pending block distribution
         let pending_dist = vec![
             Bucket {
-                gwei: 15.0,
+                wei: 15_000_000_000,
                 count: 5,
+                gas: 105_000,
             },
             Bucket {
-                gwei: 20.0,
+                wei: 20_000_000_000,
                 count: 10,
+                gas: 210_000,
             },
             Bucket {
-                gwei: 25.0,
+                wei: 25_000_000_000,
                 count: 3,
+                gas: 63_000,
             },
         ];
 
         {
-            let mut pending = gas_agent.pending_block_distribution.write().await;
-            *pending = Some(pending_dist.clone());
+            let mut pending = gas_agent.pending_block.write().await;
+            *pending = Some(PendingBlockSnapshot {
+                distribution: pending_dist.clone(),
+                fetched_at: Utc::now(),
+                chain_tip: 1000,
+            });
         }
 
         // Verify it was set
         {
-            let pending = gas_agent.pending_block_distribution.read().await;
+            let pending = gas_agent.pending_block.read().await;
             assert!(pending.is_some());
-            let dist = pending.as_ref().unwrap();
+            let dist = &pending.as_ref().unwrap().distribution;
             assert_eq!(dist.len(), 3);
-            assert_eq!(dist[0].gwei, 15.0);
-            assert_eq!(dist[1].gwei, 20.0);
-            assert_eq!(dist[2].gwei, 25.0);
+            assert_eq!(dist[0].wei, 15_000_000_000);
+            assert_eq!(dist[1].wei, 20_000_000_000);
+            assert_eq!(dist[2].wei, 25_000_000_000);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_block_distribution_gas_weights() {
+        let gas_agent = create_test_gas_agent();
+
+        let mut swap = create_test_transaction("0x2", Some(20_000_000_000), None, None);
+        swap.gas = Some(500_000);
+        swap.gas_used = Some(180_000);
+
+        let new_block = create_test_block(
+            1001,
+            1700000012,
+            vec![
+                create_test_transaction("0x1", Some(20_000_000_000), None, None),
+                swap,
+                create_test_transaction("0x3", Some(30_000_000_000), None, None),
+            ],
+            Some(10_000_000_000),
+        );
+
+        gas_agent.handle_new_block(new_block).await.unwrap();
+
+        {
+            let distributions = gas_agent.block_distributions.read().await;
+            let last_dist = distributions.last().unwrap();
+
+            // Receipt gas used takes precedence over the gas limit
+            assert_eq!(last_dist[0].wei, 20_000_000_000);
+            assert_eq!(last_dist[0].count, 2);
+            assert_eq!(last_dist[0].gas, 201_000);
+
+            assert_eq!(last_dist[1].wei, 30_000_000_000);
+            assert_eq!(last_dist[1].gas, 21_000);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_raw_block_min_kept_with_filters() {
+        let mut gas_agent = create_test_gas_agent();
+        gas_agent.chain_config.distribution_filters = DistributionFilters {
+            min_cluster_size: 2,
+            ..Default::default()
+        };
+
+        let new_block = create_test_block(
+            1001,
+            1700000012,
+            vec![
+                create_test_transaction("0x1", Some(10_000_000_001), None, None), // lone outlier
+                create_test_transaction("0x2", Some(20_000_000_000), None, None),
+                create_test_transaction("0x3", Some(20_000_000_000), None, None),
+            ],
+            Some(10_000_000_000),
+        );
+
+        gas_agent.handle_new_block(new_block).await.unwrap();
+
+        {
+            let distributions = gas_agent.block_distributions.read().await;
+            let last_dist = distributions.last().unwrap();
+            assert_eq!(last_dist.first().unwrap().wei, 20_000_000_000);
+        }
+
+        assert_eq!(*gas_agent.raw_block_min.read().await, Some(10_000_000_001));
+    }
+
+    #[tokio::test]
+    async fn test_target_payload_exact_wei() {
+        let gas_agent = create_test_gas_agent();
+
+        // 2^53 + 1 wei cannot be represented as an f64
+        let high_value_min: u128 = 9_007_199_254_740_993;
+        let new_block = create_test_block(
+            1001,
+            1700000012,
+            vec![
+                create_test_transaction("0x1", Some(high_value_min), None, None),
+                create_test_transaction("0x2", Some(high_value_min + 7), None, None),
+            ],
+            Some(10_000_000_000),
+        );
+
+        gas_agent.handle_new_block(new_block).await.unwrap();
+
+        let payload = gas_agent
+            .create_payload(
+                &create_test_agent_config(AgentKind::Target),
+                &ModelState::new(12_000),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(payload.price, "9007199254740993");
+        assert_eq!(payload.from_block, 1001);
+        assert_eq!(payload.settlement, Settlement::Immediate);
+    }
+
+    #[tokio::test]
+    async fn test_target_payload_exact_sub_gwei() {
+        let gas_agent = create_test_gas_agent();
+
+        // Typical L2 fees: base fee of 1_000_123 wei with a 7 wei priority fee
+        let new_block = create_test_block(
+            1001,
+            1700000012,
+            vec![
+                create_test_transaction("0x1", None, Some(5_000_000), Some(7)),
+                create_test_transaction("0x2", None, Some(5_000_000), Some(1_000)),
+            ],
+            Some(1_000_123),
+        );
+
+        gas_agent.handle_new_block(new_block).await.unwrap();
+
+        let payload = gas_agent
+            .create_payload(
+                &create_test_agent_config(AgentKind::Target),
+                &ModelState::new(12_000),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(payload.price, "1000130");
+
+        // LastMin publishes the same exact minimum for the next block
+        let payload = gas_agent
+            .create_payload(
+                &create_test_agent_config(AgentKind::Model(ModelKind::LastMin)),
+                &ModelState::new(12_000),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(payload.price, "1000130");
+        assert_eq!(payload.from_block, 1002);
+    }
+
+    #[tokio::test]
+    async fn test_self_tuning_learns_from_new_blocks() {
+        let mut gas_agent = create_test_gas_agent();
+
+        let mut agent = create_test_agent_config(AgentKind::Model(ModelKind::SelfTuning));
+        agent.model_config.self_tuning.gain = 0.1;
+        gas_agent.agents = Arc::new(vec![create_test_agent_handle(agent.clone())]);
+
+        // No premium before any prediction has been evaluated
+        let payload = gas_agent
+            .create_payload(&agent, &gas_agent.agents[0].model_state)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(payload.price, "20000000000");
+        assert_eq!(payload.from_block, 1001);
+
+        // Block 1001 clears above the prediction, so the premium is raised
+        let new_block = create_test_block(
+            1001,
+            1700000012,
+            vec![create_test_transaction(
+                "0xdef",
+                Some(30_000_000_000),
+                None,
+                None,
+            )],
+            Some(11_000_000_000),
+        );
+        gas_agent.handle_new_block(new_block).await.unwrap();
+
+        let payload = gas_agent
+            .create_payload(&agent, &gas_agent.agents[0].model_state)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // 30 gwei base prediction with a 9% premium
+        assert_eq!(payload.price, "32700000000");
+        assert_eq!(payload.from_block, 1002);
+    }
+
+    #[tokio::test]
+    async fn test_stale_pending_block() {
+        let gas_agent = create_test_gas_agent();
+        let agent = create_test_agent_config(AgentKind::Model(ModelKind::PendingFloor));
+
+        *gas_agent.pending_block.write().await = Some(PendingBlockSnapshot {
+            distribution: vec![Bucket {
+                wei: 12_000_000_000,
+                count: 4,
+                gas: 84_000,
+            }],
+            fetched_at: Utc::now(),
+            chain_tip: 1000,
+        });
+
+        let payload = gas_agent
+            .create_payload(&agent, &ModelState::new(12_000))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(payload.price, "12000000001");
+
+        // The pending block source stops updating while blocks keep coming
+        let new_block = |number: u64| {
+            create_test_block(
+                number,
+                1700000000 + (number as i64 - 1000) * 12,
+                vec![create_test_transaction(
+                    "0xdef",
+                    Some(25_000_000_000),
+                    None,
+                    None,
+                )],
+                Some(10_000_000_000),
+            )
+        };
+        gas_agent.handle_new_block(new_block(1001)).await.unwrap();
+
+        // A block triggered prediction runs before the next pending block poll, and still
+        // publishes from the snapshot taken against the previous chain tip
+        assert!(matches!(agent.prediction_trigger, PredictionTrigger::Block));
+        let payload = gas_agent
+            .create_payload(&agent, &ModelState::new(12_000))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(payload.price, "12000000001");
+        assert_eq!(payload.from_block, 1002);
+
+        gas_agent.handle_new_block(new_block(1002)).await.unwrap();
+
+        // Nothing is published from a snapshot taken against an older chain tip
+        assert!(gas_agent
+            .create_payload(&agent, &ModelState::new(12_000))
+            .await
+            .unwrap()
+            .is_none());
+
+        // and it only counts towards the features of the block right after it was taken
+        let block_features = gas_agent.block_features.read().await;
+        assert_eq!(block_features[0].pending_tx_count, 4);
+        assert_eq!(block_features[1].pending_tx_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_lagging_pending_block_source() {
+        let gas_agent = create_test_gas_agent();
+        let agent = create_test_agent_config(AgentKind::Model(ModelKind::PendingFloor));
+        let pending = |parent_number| PendingTransactions {
+            transactions: vec![create_test_transaction(
+                "0xa",
+                Some(12_000_000_000),
+                None,
+                None,
+            )],
+            parent_number,
+        };
+
+        // A pending block source two blocks behind the agent's chain tip at 1000
+        gas_agent.update_pending_block(pending(Some(998))).await;
+        assert_eq!(
+            gas_agent.current_pending_block().await.unwrap().chain_tip,
+            998
+        );
+        assert!(gas_agent
+            .create_payload(&agent, &ModelState::new(12_000))
+            .await
+            .unwrap()
+            .is_none());
+
+        gas_agent.update_pending_block(pending(Some(1000))).await;
+        assert!(gas_agent
+            .create_payload(&agent, &ModelState::new(12_000))
+            .await
+            .unwrap()
+            .is_some());
+
+        // Sources without a block number are stamped with the agent's chain tip
+        gas_agent.update_pending_block(pending(None)).await;
+        assert_eq!(
+            gas_agent.current_pending_block().await.unwrap().chain_tip,
+            1000
+        );
+    }
+
+    #[tokio::test]
+    async fn test_block_features_history() {
+        let gas_agent = create_test_gas_agent();
+
+        for (number, base_fee) in [(1001, 10_000_000_000), (1002, 11_000_000_000)] {
+            *gas_agent.pending_block.write().await = Some(PendingBlockSnapshot {
+                distribution: vec![Bucket {
+                    wei: 12_000_000_000,
+                    count: 4,
+                    gas: 84_000,
+                }],
+                fetched_at: Utc::now(),
+                chain_tip: number - 1,
+            });
+
+            let block = create_test_block(
+                number,
+                1700000000 + (number as i64 - 1000) * 12,
+                vec![create_test_transaction(
+                    "0xdef",
+                    Some(25_000_000_000),
+                    None,
+                    None,
+                )],
+                Some(base_fee),
+            );
+            gas_agent.handle_new_block(block).await.unwrap();
         }
+
+        let block_features = gas_agent.block_features.read().await;
+        assert_eq!(block_features.len(), 2);
+
+        let latest = &block_features[1];
+        assert_eq!(latest.block_number, 1002);
+        assert_eq!(latest.utilization, 0.5);
+        assert!((latest.base_fee_change - 0.1).abs() < 1e-12);
+        assert_eq!(latest.pending_tx_count, 4);
+        assert_eq!(latest.block_min, Some(25_000_000_000));
+    }
+
+    #[tokio::test]
+    async fn test_blob_fee_payload() {
+        let gas_agent = create_test_gas_agent();
+
+        let mut new_block = create_test_block(
+            1001,
+            1700000012,
+            vec![create_test_transaction(
+                "0xdef",
+                Some(25_000_000_000),
+                None,
+                None,
+            )],
+            Some(10_000_000_000),
+        );
+        new_block.excess_blob_gas = Some(100_000_000);
+        new_block.blob_gas_used = Some(21 * 131_072);
+
+        gas_agent.handle_new_block(new_block).await.unwrap();
+
+        let payload = gas_agent
+            .create_payload(
+                &create_test_agent_config(AgentKind::BlobFee),
+                &ModelState::new(12_000),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+
+        // Blobs are below their execution cost, so the excess grows by 21 * 7 / 21 blobs
+        // to 100_917_504 under the Ethereum mainnet blob parameters
+        assert_eq!(payload.price, "5634");
+        assert_eq!(payload.kind, PriceKind::BlobBaseFee);
+        assert_eq!(payload.settlement, Settlement::Immediate);
+        assert_eq!(payload.from_block, 1002);
+    }
+
+    #[tokio::test]
+    async fn test_payload_records() {
+        let gas_agent = create_test_gas_agent();
+
+        let new_block = create_test_block(
+            1001,
+            1700000012,
+            vec![
+                create_test_transaction("0xdef", Some(25_000_000_000), None, None),
+                create_test_transaction("0xghi", Some(30_000_000_000), None, None),
+            ],
+            Some(10_000_000_000),
+        );
+        gas_agent.handle_new_block(new_block).await.unwrap();
+
+        let mut agent_config = create_test_agent_config(AgentKind::Target);
+        agent_config.records = vec![
+            RecordType::BaseFeePerGas,
+            RecordType::MaxPriorityFeePerGasP50,
+            RecordType::BlobBaseFeePerGas,
+        ];
+
+        let payload = gas_agent
+            .create_payload(&agent_config, &ModelState::new(12_000))
+            .await
+            .unwrap()
+            .unwrap();
+
+        // The test block has no blob gas fields, so the blob base fee record is left out
+        assert_eq!(
+            payload.records,
+            vec![
+                PayloadRecord {
+                    record_type: RecordType::BaseFeePerGas,
+                    value: "10000000000".to_string(),
+                },
+                PayloadRecord {
+                    record_type: RecordType::MaxPriorityFeePerGasP50,
+                    value: "15000000000".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_l1_data_fee_record() {
+        let gas_agent = create_test_gas_agent();
+
+        let mut agent_config = create_test_agent_config(AgentKind::Target);
+        agent_config.records = vec![RecordType::L1DataFeePerByte];
+
+        // Left out until the L1 fee parameters are known
+        let payload = gas_agent
+            .create_payload(&agent_config, &ModelState::new(12_000))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(payload.records.is_empty());
+
+        *gas_agent.l1_fee_params.write().await = Some(L1FeeParams {
+            l1_base_fee: 7_000_000_000,
+            l1_blob_base_fee: 1,
+            base_fee_scalar: 2269,
+            blob_base_fee_scalar: 1_055_762,
+        });
+
+        let payload = gas_agent
+            .create_payload(&agent_config, &ModelState::new(12_000))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            payload.records,
+            vec![PayloadRecord {
+                record_type: RecordType::L1DataFeePerByte,
+                value: "254128001".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validate_records() {
+        let mut agent_config = create_test_agent_config(AgentKind::Target);
+        agent_config.records = vec![RecordType::BaseFeePerGas];
+        assert!(validate_records(&agent_config).is_ok());
+
+        agent_config.records = vec![RecordType::BaseFeePerGas, RecordType::BaseFeePerGas];
+        assert!(validate_records(&agent_config).is_err());
+
+        // The predicted price is already published in the gas price record type
+        agent_config.records = vec![RecordType::MaxPriorityFeePerGasP99];
+        assert!(validate_records(&agent_config).is_err());
+
+        // even for agents that publish another kind of price
+        let mut blob_agent_config = create_test_agent_config(AgentKind::BlobFee);
+        blob_agent_config.records = vec![RecordType::MaxPriorityFeePerGasP99];
+        assert!(validate_records(&blob_agent_config).is_err());
+
+        // Record types without an assigned ID are not published
+        agent_config.records = vec![RecordType::L1DataFeePerByte];
+        assert!(validate_records(&agent_config).is_err());
+    }
+
+    #[test]
+    fn test_agent_handle() {
+        let config = create_test_agent_config(AgentKind::Target);
+        let handle = create_test_agent_handle(config.clone());
+        assert_eq!(
+            handle.address,
+            "0x59c6995e998f97a5a0044976f3ac3b8c9f27a7d9b3bcd2b0d7aeb5f3e9eae7c6"
+                .parse::<alloy::signers::local::PrivateKeySigner>()
+                .unwrap()
+                .address()
+        );
+
+        // Agents are validated when their handle is created
+        let mut invalid = config;
+        invalid.records = vec![RecordType::MaxPriorityFeePerGasP99];
+        let signer = invalid.signer_key.load().unwrap();
+        assert!(AgentHandle::new(invalid, signer, 12_000).is_err());
+
+        // including their model configuration
+        let ensemble = create_test_agent_config(AgentKind::Model(ModelKind::Ensemble));
+        let signer = ensemble.signer_key.load().unwrap();
+        assert!(AgentHandle::new(ensemble, signer, 12_000).is_err());
+
+        let mut self_tuning = create_test_agent_config(AgentKind::Model(ModelKind::SelfTuning));
+        self_tuning.model_config.self_tuning.target_inclusion_rate = 1.5;
+        let signer = self_tuning.signer_key.load().unwrap();
+        assert!(AgentHandle::new(self_tuning, signer, 12_000).is_err());
     }
 }
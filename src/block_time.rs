@@ -0,0 +1,143 @@
+use crate::rpc::BlockHeader;
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+
+/// Number of recent block intervals the estimate is computed over
+const MAX_NUM_BLOCK_INTERVALS: usize = 64;
+/// Weight of the static block time in blocks, so a handful of observations only nudge the estimate
+const PRIOR_WEIGHT_BLOCKS: u64 = 8;
+
+/// Rolling estimate of the time between blocks from observed block timestamps.
+/// The chain's static block time is used as a prior, so the estimate starts there and follows
+/// the observed block times as they come in.
+#[derive(Debug, Clone)]
+pub struct BlockTimeEstimator {
+    prior_ms: u64,
+    /// Elapsed milliseconds and number of blocks between consecutive observed blocks, oldest first
+    intervals: VecDeque<(u64, u64)>,
+    last_block: Option<(u64, DateTime<Utc>)>,
+}
+
+impl BlockTimeEstimator {
+    pub fn new(prior_ms: u64) -> Self {
+        Self {
+            prior_ms,
+            intervals: VecDeque::with_capacity(MAX_NUM_BLOCK_INTERVALS),
+            last_block: None,
+        }
+    }
+
+    /// Records the timestamp of a new chain tip. Intervals spanning several blocks, eg. after
+    /// missed polls, count for each block they span. Blocks at or below the last observed height
+    /// are ignored.
+    pub fn observe(&mut self, header: &BlockHeader) {
+        if let Some((number, timestamp)) = self.last_block {
+            if header.number <= number {
+                return;
+            }
+
+            let elapsed_ms = (header.timestamp - timestamp).num_milliseconds().max(0) as u64;
+            self.intervals
+                .push_back((elapsed_ms, header.number - number));
+
+            if self.intervals.len() > MAX_NUM_BLOCK_INTERVALS {
+                self.intervals.pop_front();
+            }
+        }
+
+        self.last_block = Some((header.number, header.timestamp));
+    }
+
+    /// Estimated time between blocks in milliseconds, the mean observed block time weighted
+    /// against the prior
+    pub fn block_time_ms(&self) -> u64 {
+        let (elapsed_ms, blocks) = self.intervals.iter().fold(
+            (0, 0),
+            |(elapsed_ms, blocks), (interval_ms, interval_blocks)| {
+                (elapsed_ms + interval_ms, blocks + interval_blocks)
+            },
+        );
+
+        let estimate =
+            (self.prior_ms * PRIOR_WEIGHT_BLOCKS + elapsed_ms) / (PRIOR_WEIGHT_BLOCKS + blocks);
+
+        estimate.max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(number: u64, timestamp_ms: i64) -> BlockHeader {
+        BlockHeader {
+            number,
+            timestamp: DateTime::from_timestamp_millis(timestamp_ms).unwrap(),
+            gas_limit: 30_000_000,
+            gas_used: 15_000_000,
+            base_fee_per_gas: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            extra_data: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_prior_without_observations() {
+        let mut estimator = BlockTimeEstimator::new(2_000);
+        assert_eq!(estimator.block_time_ms(), 2_000);
+
+        estimator.observe(&header(100, 1_700_000_000_000));
+        assert_eq!(estimator.block_time_ms(), 2_000);
+    }
+
+    #[test]
+    fn test_drifting_block_time() {
+        // Polygon blocks drifting to 2.2 seconds
+        let mut estimator = BlockTimeEstimator::new(2_000);
+        for i in 0..=8 {
+            estimator.observe(&header(100 + i, 1_700_000_000_000 + i as i64 * 2_200));
+        }
+
+        // Eight observed blocks weigh as much as the prior
+        assert_eq!(estimator.block_time_ms(), 2_100);
+
+        for i in 9..=200 {
+            estimator.observe(&header(100 + i, 1_700_000_000_000 + i as i64 * 2_200));
+        }
+
+        // Only the latest 64 intervals count
+        assert_eq!(estimator.block_time_ms(), (2_000 * 8 + 2_200 * 64) / 72);
+    }
+
+    #[test]
+    fn test_missed_slots_and_polls() {
+        let mut estimator = BlockTimeEstimator::new(12_000);
+        estimator.observe(&header(100, 0));
+        // A missed slot
+        estimator.observe(&header(101, 24_000));
+        assert_eq!(estimator.block_time_ms(), (12_000 * 8 + 24_000) / 9);
+
+        // Missed polls spanning two blocks in 24 seconds count as two 12 second blocks
+        let mut estimator = BlockTimeEstimator::new(12_000);
+        estimator.observe(&header(100, 0));
+        estimator.observe(&header(102, 24_000));
+        assert_eq!(estimator.block_time_ms(), 12_000);
+
+        // Reorgs and repeated blocks are ignored
+        estimator.observe(&header(102, 30_000));
+        estimator.observe(&header(101, 30_000));
+        assert_eq!(estimator.block_time_ms(), 12_000);
+    }
+
+    #[test]
+    fn test_same_second_timestamps() {
+        // Sub-second blocks with second resolution timestamps average out over the window
+        let mut estimator = BlockTimeEstimator::new(250);
+        for i in 0..=64 {
+            estimator.observe(&header(100 + i, (i as i64 / 4) * 1_000));
+        }
+
+        assert_eq!(estimator.block_time_ms(), (250 * 8 + 16_000) / 72);
+    }
+}
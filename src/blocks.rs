@@ -1,6 +1,9 @@
 use crate::{
+    config::{BaseFeeParams, BlobParams, DistributionFilters},
     distribution::{BlockDistribution, DistributionCreator},
+    op_stack::OP_DEPOSIT_TX_TYPE,
     rpc::{BlockHeader, Transaction},
+    types::{FeeMarket, System},
 };
 use anyhow::{anyhow, Result};
 use rust_decimal::{
@@ -8,31 +11,108 @@ use rust_decimal::{
     Decimal,
 };
 
+/// Transactions are bucketed to the exact wei
+const BUCKET_SIZE_WEI: u128 = 1;
+
+/// Polygon PoS state-sync transaction type
+const POLYGON_STATE_SYNC_TX_TYPE: u8 = 0x7f;
+/// Arbitrum transaction types created by the protocol, from L1 deposits (`0x64`) and retryable
+/// tickets through to the ArbOS internal transaction (`0x6a`)
+const ARBITRUM_SYSTEM_TX_TYPES: std::ops::RangeInclusive<u8> = 0x64..=0x6a;
+/// Sender of protocol transactions that are not signed by a user (eg. Polygon state-sync)
+const ZERO_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
+
+/// Whether a transaction is a system or protocol transaction on the given system.
+/// These are not priced by users competing for block space so are excluded from distributions.
+pub fn is_system_transaction(system: &System, tx: &Transaction) -> bool {
+    match system {
+        System::Base | System::Optimism => tx.tx_type == Some(OP_DEPOSIT_TX_TYPE),
+        System::Polygon => {
+            tx.tx_type == Some(POLYGON_STATE_SYNC_TX_TYPE)
+                || tx.from.as_deref() == Some(ZERO_ADDRESS)
+        }
+        System::Arbitrum => tx
+            .tx_type
+            .is_some_and(|tx_type| ARBITRUM_SYSTEM_TX_TYPES.contains(&tx_type)),
+        // BSC validator system transactions have a zero gas price and are skipped as unpriced
+        System::Ethereum | System::Linea | System::Bsc | System::Avalanche | System::Custom(_) => {
+            false
+        }
+    }
+}
+
+/// A block distribution with outlier filters applied
+#[derive(Debug, Clone)]
+pub struct FilteredBlockDistribution {
+    /// The distribution after outlier filtering
+    pub distribution: BlockDistribution,
+    /// The lowest effective price (wei) in the block before outlier filtering
+    pub raw_min: Option<u128>,
+}
+
 pub fn block_to_block_distribution(
     transactions: &[Transaction],
     base_fee: &Option<u64>,
-) -> BlockDistribution {
-    let mut distribution = DistributionCreator::new(0.000000001);
+    system: &System,
+    fee_market: FeeMarket,
+    filters: &DistributionFilters,
+) -> FilteredBlockDistribution {
+    let mut distribution = DistributionCreator::new(BUCKET_SIZE_WEI);
+    let mut raw_min: Option<u128> = None;
 
-    for tx in transactions.iter() {
+    for tx in transactions
+        .iter()
+        .filter(|tx| !is_system_transaction(system, tx))
+    {
         let Transaction {
             hash,
             gas_price,
             max_fee_per_gas,
             max_priority_fee_per_gas,
+            ..
         } = tx;
 
-        if (gas_price.is_some() && gas_price.unwrap() > 0)
-            || (max_priority_fee_per_gas.is_some() && max_priority_fee_per_gas.unwrap() > 0)
-        {
-            match calc_fee_gwei(
-                gas_price,
-                max_fee_per_gas,
-                max_priority_fee_per_gas,
-                base_fee,
-            ) {
+        let is_priced = match fee_market {
+            // Transactions without a tip still pay the base fee
+            FeeMarket::BaseFeeOnly => {
+                gas_price.is_some_and(|price| price > 0)
+                    || max_fee_per_gas.is_some_and(|price| price > 0)
+            }
+            FeeMarket::Eip1559 | FeeMarket::Legacy => {
+                gas_price.is_some_and(|price| price > 0)
+                    || max_priority_fee_per_gas.is_some_and(|price| price > 0)
+            }
+        };
+
+        if is_priced {
+            let fee = match fee_market {
+                FeeMarket::Eip1559 => calc_fee_wei(
+                    gas_price,
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas,
+                    base_fee,
+                ),
+                FeeMarket::BaseFeeOnly => base_fee
+                    .map(u128::from)
+                    .ok_or(anyhow!("No base fee per gas value")),
+                // Without a base fee, dynamic fee transactions pay their priority fee
+                FeeMarket::Legacy => calc_fee_wei(
+                    gas_price,
+                    max_fee_per_gas,
+                    max_priority_fee_per_gas,
+                    &Some(0),
+                ),
+            };
+
+            match fee {
                 std::result::Result::Ok(effective_gas_price) => {
-                    distribution.add(effective_gas_price)
+                    raw_min = Some(
+                        raw_min.map_or(effective_gas_price, |min| min.min(effective_gas_price)),
+                    );
+
+                    if !is_excluded_sender(filters, tx) {
+                        distribution.add(effective_gas_price, tx.gas_weight());
+                    }
                 }
                 Err(e) => {
                     eprint!(
@@ -44,12 +124,65 @@ pub fn block_to_block_distribution(
         }
     }
 
-    // Sort ASC
-    distribution
-        .buckets
-        .sort_by(|a, b| a.gwei.partial_cmp(&b.gwei).unwrap());
+    // Buckets are sorted ASC by price
+    let mut buckets = distribution.buckets();
+    trim_lower_percentile(&mut buckets, filters.lower_trim_percentile);
+    drop_small_lower_clusters(&mut buckets, filters.min_cluster_size);
+
+    FilteredBlockDistribution {
+        distribution: buckets,
+        raw_min,
+    }
+}
+
+fn is_excluded_sender(filters: &DistributionFilters, tx: &Transaction) -> bool {
+    tx.from.as_deref().is_some_and(|from| {
+        filters
+            .excluded_senders
+            .iter()
+            .any(|sender| sender.eq_ignore_ascii_case(from))
+    })
+}
+
+/// Removes the cheapest `percentile` fraction of transactions from a sorted distribution
+fn trim_lower_percentile(buckets: &mut BlockDistribution, percentile: f64) {
+    if percentile <= 0.0 {
+        return;
+    }
+
+    let total_txs: u32 = buckets.iter().map(|bucket| bucket.count).sum();
+    let mut to_trim = (total_txs as f64 * percentile.min(1.0)).floor() as u32;
+
+    while to_trim > 0 {
+        let Some(lowest) = buckets.first_mut() else {
+            break;
+        };
+
+        if lowest.count <= to_trim {
+            to_trim -= lowest.count;
+            buckets.remove(0);
+        } else {
+            // Remove the average gas of the trimmed transactions along with them
+            let gas_per_tx = lowest.gas / lowest.count as u64;
+            lowest.count -= to_trim;
+            lowest.gas = lowest.gas.saturating_sub(gas_per_tx * to_trim as u64);
+            to_trim = 0;
+        }
+    }
+}
+
+/// Drops the lowest priced buckets of a sorted distribution until one has at least `min_cluster_size` transactions
+fn drop_small_lower_clusters(buckets: &mut BlockDistribution, min_cluster_size: u32) {
+    if min_cluster_size <= 1 {
+        return;
+    }
+
+    let first_cluster = buckets
+        .iter()
+        .position(|bucket| bucket.count >= min_cluster_size)
+        .unwrap_or(buckets.len());
 
-    distribution.buckets
+    buckets.drain(0..first_cluster);
 }
 
 pub fn wei_to_gwei(wei: u128) -> Result<f64> {
@@ -69,16 +202,27 @@ pub fn wei_to_gwei(wei: u128) -> Result<f64> {
         .ok_or(anyhow!("Failed to convert wei to gwei"))
 }
 
-pub fn calc_fee_gwei(
+/// Converts a gwei value produced by a floating point statistic back to integer wei.
+/// Negative or non-finite values are clamped to zero.
+pub fn gwei_to_wei(gwei: f64) -> u128 {
+    Decimal::from_f64(gwei)
+        .map(|gwei| (gwei * Decimal::new(1_000_000_000, 0)).round())
+        .and_then(|wei| wei.to_u128())
+        .unwrap_or(0)
+}
+
+/// The effective price in wei paid per gas by a transaction
+pub fn calc_fee_wei(
     gas_price: &Option<u128>,
     max_fee_per_gas: &Option<u128>,
     max_priority_fee_per_gas: &Option<u128>,
     base_fee_per_gas: &Option<u64>,
-) -> Result<f64> {
-    let base_fee_per_gas = base_fee_per_gas.ok_or(anyhow!("No base fee per gas value"))?;
+) -> Result<u128> {
+    // Legacy transactions pay their gas price, which also holds on chains without a base fee
     if let Some(gas_price) = gas_price {
-        wei_to_gwei(*gas_price)
+        Ok(*gas_price)
     } else {
+        let base_fee_per_gas = base_fee_per_gas.ok_or(anyhow!("No base fee per gas value"))?;
         let max_fee_per_gas =
             max_fee_per_gas.ok_or(anyhow!("Missing max_fee_per_gas for effective calc"))?;
 
@@ -88,44 +232,802 @@ pub fn calc_fee_gwei(
 
         let effective_gas_price =
             max_fee_per_gas.min(base_fee_per_gas as u128 + max_priority_fee_per_gas);
-        let wei = effective_gas_price;
-        wei_to_gwei(wei)
+        Ok(effective_gas_price)
     }
 }
 
-const ELASTICITY_MULTIPLIER: u64 = 2;
-const BASE_FEE_CHANGE_DENOMINATOR: u64 = 8;
+/// Version of the `extraData` encoding introduced by the OP stack Holocene upgrade
+const HOLOCENE_EXTRA_DATA_VERSION: u8 = 0;
+/// Version of the `extraData` encoding introduced by the OP stack Jovian upgrade, adding a minimum base fee
+const JOVIAN_EXTRA_DATA_VERSION: u8 = 1;
+
+/// Decodes the EIP-1559 parameters OP stack blocks encode in `extraData` since Holocene:
+/// a version byte, the `u32` max change denominator and the `u32` elasticity multiplier,
+/// followed by the `u64` minimum base fee since Jovian. These parameters derive the base fee
+/// of the next block. `None` for any other `extraData`.
+pub fn decode_holocene_extra_data(extra_data: &[u8]) -> Option<BaseFeeParams> {
+    let min_base_fee = match (extra_data.first(), extra_data.len()) {
+        (Some(&HOLOCENE_EXTRA_DATA_VERSION), 9) => 0,
+        (Some(&JOVIAN_EXTRA_DATA_VERSION), 17) => {
+            u64::from_be_bytes(extra_data[9..17].try_into().ok()?)
+        }
+        _ => return None,
+    };
+
+    let max_change_denominator = u32::from_be_bytes(extra_data[1..5].try_into().ok()?);
+    let elasticity_multiplier = u32::from_be_bytes(extra_data[5..9].try_into().ok()?);
 
-pub fn calc_base_fee(latest_block: &BlockHeader) -> Option<u64> {
-    if let Some(parent_base_fee) = latest_block.base_fee_per_gas {
-        let parent_gas_target = latest_block.gas_limit / ELASTICITY_MULTIPLIER;
+    if max_change_denominator == 0 || elasticity_multiplier == 0 {
+        return None;
+    }
+
+    Some(BaseFeeParams {
+        elasticity_multiplier: elasticity_multiplier.into(),
+        max_change_denominator: max_change_denominator.into(),
+        min_base_fee,
+    })
+}
 
+/// The base fee of the block after `latest_block`. Without `params` the base fee of
+/// `latest_block` is carried over. Errors when `params` leave no gas target or change bound.
+pub fn calc_base_fee(
+    latest_block: &BlockHeader,
+    params: Option<BaseFeeParams>,
+) -> Result<Option<u64>> {
+    let Some(params) = params else {
+        return Ok(latest_block.base_fee_per_gas);
+    };
+
+    let Some(parent_base_fee) = latest_block.base_fee_per_gas else {
+        return Ok(None);
+    };
+
+    let parent_gas_target = latest_block
+        .gas_limit
+        .checked_div(params.elasticity_multiplier)
+        .filter(|target| *target > 0)
+        .ok_or_else(|| {
+            anyhow!(
+                "No gas target for a gas limit of {} with an elasticity multiplier of {}",
+                latest_block.gas_limit,
+                params.elasticity_multiplier
+            )
+        })?;
+    let bound_change = |y: u128| {
+        y.checked_div(params.max_change_denominator as u128)
+            .ok_or(anyhow!("Max change denominator must not be zero"))
+    };
+
+    let base_fee = if latest_block.gas_used == parent_gas_target {
         // If the parent gasUsed is the same as the target, the baseFee remains unchanged
-        if latest_block.gas_used == parent_gas_target {
-            return Some(parent_base_fee);
+        parent_base_fee
+    } else if latest_block.gas_used > parent_gas_target {
+        // If the parent block used more gas than its target, the baseFee should increase
+        let gas_used_delta = latest_block.gas_used - parent_gas_target;
+        // Widened so high base fees with large gas deltas cannot overflow
+        let x = parent_base_fee as u128 * gas_used_delta as u128;
+        let y = x / parent_gas_target as u128;
+        let base_fee_delta = std::cmp::max(bound_change(y)?, 1) as u64;
+
+        parent_base_fee + base_fee_delta
+    } else {
+        // Otherwise if the parent block used less gas than its target, the baseFee should decrease
+        let gas_used_delta = parent_gas_target - latest_block.gas_used;
+        let x = parent_base_fee as u128 * gas_used_delta as u128;
+        let y = x / parent_gas_target as u128;
+        let base_fee_delta = bound_change(y)? as u64;
+
+        parent_base_fee.saturating_sub(base_fee_delta)
+    };
+
+    Ok(Some(base_fee.max(params.min_base_fee)))
+}
+
+/// Blob gas of a single blob (EIP-4844)
+pub const GAS_PER_BLOB: u64 = 1 << 17;
+const MIN_BASE_FEE_PER_BLOB_GAS: u128 = 1;
+/// Execution gas a blob is priced at when the blob base fee is bounded (EIP-7918)
+const BLOB_BASE_COST: u128 = 1 << 13;
+
+/// Approximates `factor * e ** (numerator / denominator)` using a Taylor expansion (EIP-4844)
+pub fn fake_exponential(factor: u128, numerator: u128, denominator: u128) -> u128 {
+    let mut i = 1;
+    let mut output: u128 = 0;
+    let mut numerator_accum = factor.saturating_mul(denominator);
+
+    while numerator_accum > 0 {
+        output = output.saturating_add(numerator_accum);
+        numerator_accum = numerator_accum.saturating_mul(numerator) / (denominator * i);
+        i += 1;
+    }
+
+    output / denominator
+}
+
+/// The base fee per blob gas of a block with the given excess blob gas
+pub fn blob_base_fee(excess_blob_gas: u64, params: &BlobParams) -> u128 {
+    fake_exponential(
+        MIN_BASE_FEE_PER_BLOB_GAS,
+        excess_blob_gas as u128,
+        params.base_fee_update_fraction as u128,
+    )
+}
+
+/// The excess blob gas of the block after `parent`, `None` before EIP-4844
+pub fn calc_excess_blob_gas(parent: &BlockHeader, params: &BlobParams) -> Option<u64> {
+    let parent_excess_blob_gas = parent.excess_blob_gas?;
+    let parent_blob_gas_used = parent.blob_gas_used.unwrap_or(0);
+    let target_blob_gas = params.target_blobs_per_block * GAS_PER_BLOB;
+
+    if parent_excess_blob_gas + parent_blob_gas_used < target_blob_gas {
+        return Some(0);
+    }
+
+    if params.bounded_by_execution_cost {
+        let parent_base_fee = parent.base_fee_per_gas.unwrap_or(0) as u128;
+        let parent_blob_base_fee = blob_base_fee(parent_excess_blob_gas, params);
+
+        // While blobs are cheaper than their execution cost, excess only grows
+        if BLOB_BASE_COST * parent_base_fee > GAS_PER_BLOB as u128 * parent_blob_base_fee {
+            let max_blobs = params.max_blobs_per_block.max(1);
+            let addendum = parent_blob_gas_used
+                * (max_blobs.saturating_sub(params.target_blobs_per_block))
+                / max_blobs;
+            return Some(parent_excess_blob_gas + addendum);
         }
+    }
 
-        if latest_block.gas_used > parent_gas_target {
-            // If the parent block used more gas than its target, the baseFee should increase
-            let gas_used_delta = latest_block.gas_used - parent_gas_target;
-            let x = parent_base_fee * gas_used_delta;
-            let y = x / parent_gas_target;
-            let base_fee_delta = std::cmp::max(y / BASE_FEE_CHANGE_DENOMINATOR, 1);
+    Some(parent_excess_blob_gas + parent_blob_gas_used - target_blob_gas)
+}
 
-            return Some(parent_base_fee + base_fee_delta);
-        } else {
-            // Otherwise if the parent block used less gas than its target, the baseFee should decrease
-            let gas_used_delta = parent_gas_target - latest_block.gas_used;
-            let x = parent_base_fee * gas_used_delta;
-            let y = x / parent_gas_target;
-            let base_fee_delta = y / BASE_FEE_CHANGE_DENOMINATOR;
-
-            return Some(std::cmp::max(
-                parent_base_fee.saturating_sub(base_fee_delta),
+/// The base fee per blob gas of the block after `latest_block`
+pub fn calc_blob_base_fee(latest_block: &BlockHeader, params: &BlobParams) -> Option<u128> {
+    calc_excess_blob_gas(latest_block, params)
+        .map(|excess_blob_gas| blob_base_fee(excess_blob_gas, params))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Network, SystemNetworkKey};
+    use alloy::{hex, primitives::Bytes};
+
+    fn create_test_transaction(
+        hash: &str,
+        tx_type: u8,
+        from: &str,
+        gas_price: u128,
+    ) -> Transaction {
+        Transaction {
+            hash: hash.to_string(),
+            tx_type: Some(tx_type),
+            from: Some(from.to_string()),
+            nonce: None,
+            to: None,
+            gas_price: Some(gas_price),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            gas: Some(21_000),
+            gas_used: None,
+            max_fee_per_blob_gas: None,
+            blob_count: 0,
+            input: Bytes::new(),
+        }
+    }
+
+    #[test]
+    fn test_base_deposit_transactions_excluded() {
+        let transactions = vec![
+            // L1 attributes deposit transaction
+            create_test_transaction(
+                "0x1",
+                OP_DEPOSIT_TX_TYPE,
+                "0xdeaddeaddeaddeaddeaddeaddeaddeaddead0001",
+                1_000_000,
+            ),
+            // User deposit with a non-zero gas price
+            create_test_transaction(
+                "0x2",
+                OP_DEPOSIT_TX_TYPE,
+                "0x1111111111111111111111111111111111111111",
+                2_000_000,
+            ),
+            create_test_transaction(
+                "0x3",
                 0,
-            ));
+                "0x2222222222222222222222222222222222222222",
+                5_000_000,
+            ),
+        ];
+
+        let distribution = block_to_block_distribution(
+            &transactions,
+            &Some(1),
+            &System::Base,
+            FeeMarket::Eip1559,
+            &DistributionFilters::default(),
+        )
+        .distribution;
+        assert_eq!(distribution.len(), 1);
+        assert_eq!(distribution[0].wei, 5_000_000);
+
+        // Deposit transaction types have no special meaning on Ethereum
+        let distribution = block_to_block_distribution(
+            &transactions,
+            &Some(1),
+            &System::Ethereum,
+            FeeMarket::Eip1559,
+            &DistributionFilters::default(),
+        )
+        .distribution;
+        assert_eq!(distribution.len(), 3);
+    }
+
+    #[test]
+    fn test_polygon_state_sync_transactions_excluded() {
+        let transactions = vec![
+            create_test_transaction("0x1", POLYGON_STATE_SYNC_TX_TYPE, ZERO_ADDRESS, 1_000_000),
+            create_test_transaction("0x2", 0, ZERO_ADDRESS, 1_000_000),
+            create_test_transaction(
+                "0x3",
+                2,
+                "0x2222222222222222222222222222222222222222",
+                30_000_000_000,
+            ),
+        ];
+
+        let distribution = block_to_block_distribution(
+            &transactions,
+            &Some(1),
+            &System::Polygon,
+            FeeMarket::Eip1559,
+            &DistributionFilters::default(),
+        )
+        .distribution;
+        assert_eq!(distribution.len(), 1);
+        assert_eq!(distribution[0].wei, 30_000_000_000);
+    }
+
+    #[test]
+    fn test_fee_markets() {
+        let mut transactions = create_user_transactions(&[3, 5, 0]);
+        // Dynamic fee transaction with a 1 gwei tip
+        transactions[1].gas_price = None;
+        transactions[1].max_fee_per_gas = Some(10_000_000_000);
+        transactions[1].max_priority_fee_per_gas = Some(1_000_000_000);
+        // Dynamic fee transaction without a tip
+        transactions[2].gas_price = None;
+        transactions[2].max_fee_per_gas = Some(10_000_000_000);
+        transactions[2].max_priority_fee_per_gas = Some(0);
+
+        let prices = |base_fee: Option<u64>, fee_market: FeeMarket| -> Vec<(u128, u32)> {
+            block_to_block_distribution(
+                &transactions,
+                &base_fee,
+                &System::Ethereum,
+                fee_market,
+                &DistributionFilters::default(),
+            )
+            .distribution
+            .iter()
+            .map(|bucket| (bucket.wei / 1_000_000_000, bucket.count))
+            .collect()
+        };
+
+        // Untipped transactions are left out, tips are paid on top of the base fee
+        assert_eq!(
+            prices(Some(2_000_000_000), FeeMarket::Eip1559),
+            vec![(3, 2)]
+        );
+        // Without a base fee only gas prices are known
+        assert_eq!(prices(None, FeeMarket::Eip1559), vec![(3, 1)]);
+        // Legacy chains have no base fee, dynamic fee transactions pay their tip
+        assert_eq!(prices(None, FeeMarket::Legacy), vec![(1, 1), (3, 1)]);
+        // Every transaction pays the base fee, with or without a tip
+        assert_eq!(
+            prices(Some(2_000_000_000), FeeMarket::BaseFeeOnly),
+            vec![(2, 3)]
+        );
+    }
+
+    #[test]
+    fn test_arbitrum_system_transactions_excluded() {
+        let mut transactions = create_user_transactions(&[1, 1, 1]);
+        // ArbOS internal transaction and a submitted retryable ticket
+        transactions[0].tx_type = Some(0x6a);
+        transactions[1].tx_type = Some(0x69);
+
+        let distribution = block_to_block_distribution(
+            &transactions,
+            &Some(10_000_000),
+            &System::Arbitrum,
+            FeeMarket::BaseFeeOnly,
+            &DistributionFilters::default(),
+        )
+        .distribution;
+        assert_eq!(distribution.len(), 1);
+        assert_eq!(distribution[0].count, 1);
+        assert_eq!(distribution[0].wei, 10_000_000);
+    }
+
+    fn header(gas_limit: u64, gas_used: u64, base_fee_per_gas: Option<u64>) -> BlockHeader {
+        BlockHeader {
+            number: 1,
+            timestamp: chrono::Utc::now(),
+            gas_limit,
+            gas_used,
+            base_fee_per_gas,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            extra_data: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_decode_holocene_extra_data() {
+        // Holocene: version 0, denominator 250, elasticity 6
+        assert_eq!(
+            decode_holocene_extra_data(&hex!("00000000fa00000006")),
+            Some(BaseFeeParams::OP_STACK)
+        );
+        // Jovian: version 1 with a minimum base fee of 1_000_000 wei
+        assert_eq!(
+            decode_holocene_extra_data(&hex!("01000000fa0000000600000000000f4240")),
+            Some(BaseFeeParams {
+                min_base_fee: 1_000_000,
+                ..BaseFeeParams::OP_STACK
+            })
+        );
+
+        // Pre-Holocene blocks, unknown versions, wrong lengths and unset parameters
+        assert_eq!(decode_holocene_extra_data(&[]), None);
+        assert_eq!(
+            decode_holocene_extra_data(&hex!("02000000fa00000006")),
+            None
+        );
+        assert_eq!(
+            decode_holocene_extra_data(&hex!("00000000fa0000000600")),
+            None
+        );
+        assert_eq!(
+            decode_holocene_extra_data(&hex!("01000000fa00000006")),
+            None
+        );
+        assert_eq!(
+            decode_holocene_extra_data(&hex!("000000000000000000")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_base_base_fee_transitions() {
+        let base = SystemNetworkKey::new(System::Base, Network::Mainnet)
+            .to_chain_params()
+            .unwrap();
+
+        let block = |gas_used: u64, base_fee: u64, extra_data: &[u8]| BlockHeader {
+            extra_data: Bytes::copy_from_slice(extra_data),
+            ..header(150_000_000, gas_used, Some(base_fee))
+        };
+        let next_base_fee = |block: &BlockHeader| {
+            calc_base_fee(block, base.next_base_fee_params(block))
+                .unwrap()
+                .unwrap()
+        };
+
+        // Holocene parameters of 250 and 6 put the gas target at 25M gas of a 150M gas limit
+        let holocene = hex!("00000000fa00000006");
+        assert_eq!(
+            next_base_fee(&block(25_000_000, 5_000_000, &holocene)),
+            5_000_000
+        );
+        assert_eq!(
+            next_base_fee(&block(40_000_000, 5_000_000, &holocene)),
+            5_012_000
+        );
+        assert_eq!(
+            next_base_fee(&block(10_000_000, 5_000_000, &holocene)),
+            4_988_000
+        );
+
+        // With the Ethereum rule the gas target is half the gas limit, so the base fee would drop
+        assert_eq!(
+            calc_base_fee(
+                &block(40_000_000, 5_000_000, &holocene),
+                Some(BaseFeeParams::ETHEREUM)
+            )
+            .unwrap(),
+            Some(4_708_334)
+        );
+
+        // An elasticity change through the system config applies from the next block
+        let elasticity_2 = hex!("00000000fa00000002");
+        assert_eq!(
+            next_base_fee(&block(150_000_000, 5_000_000, &elasticity_2)),
+            5_020_000
+        );
+
+        // Jovian minimum base fee of 1_000_000 wei
+        let jovian = hex!("01000000fa0000000600000000000f4240");
+        assert_eq!(next_base_fee(&block(0, 2_000_000, &jovian)), 1_992_000);
+        assert_eq!(next_base_fee(&block(0, 1_000_000, &jovian)), 1_000_000);
+
+        // Blocks before Holocene fall back to the Canyon defaults
+        assert_eq!(next_base_fee(&block(40_000_000, 5_000_000, &[])), 5_012_000);
+    }
+
+    #[test]
+    fn test_base_consecutive_headers() {
+        let base = SystemNetworkKey::new(System::Base, Network::Mainnet)
+            .to_chain_params()
+            .unwrap();
+
+        // Synthetic consecutive headers in the `eth_getBlockByNumber` encoding, with the
+        // Holocene `extraData` Base mainnet publishes, parsed through the RPC block parser
+        let headers: Vec<BlockHeader> = [
+            ("0x1c9c380", "0x2625a00", "0x4c4b40"),
+            ("0x1c9c381", "0x989680", "0x4c7a20"),
+            ("0x1c9c382", "0x17d7840", "0x4c4b24"),
+            ("0x1c9c383", "0x0", "0x4c4b24"),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, (number, gas_used, base_fee_per_gas))| {
+            let block = crate::rpc::parse_block(&serde_json::json!({
+                "number": number,
+                "timestamp": format!("0x{:x}", 1_735_689_600 + i * 2),
+                "gasLimit": "0x8f0d180",
+                "gasUsed": gas_used,
+                "baseFeePerGas": base_fee_per_gas,
+                "extraData": "0x00000000fa00000006",
+                "transactions": []
+            }))
+            .unwrap();
+            BlockHeader::from(block)
+        })
+        .collect();
+
+        // Each header's base fee follows from its parent under the Holocene parameters it carries
+        for pair in headers.windows(2) {
+            let (parent, child) = (&pair[0], &pair[1]);
+            assert_eq!(
+                calc_base_fee(parent, base.next_base_fee_params(parent)).unwrap(),
+                child.base_fee_per_gas,
+                "base fee of block {}",
+                child.number
+            );
+        }
+    }
+
+    #[test]
+    fn test_calc_base_fee() {
+        let ethereum = Some(BaseFeeParams::ETHEREUM);
+
+        // At target the base fee is unchanged
+        let at_target = header(30_000_000, 15_000_000, Some(1_000_000_000));
+        assert_eq!(
+            calc_base_fee(&at_target, ethereum).unwrap(),
+            Some(1_000_000_000)
+        );
+
+        // Full blocks raise the base fee by 1/8, empty blocks lower it by 1/8
+        let full = header(30_000_000, 30_000_000, Some(1_000_000_000));
+        assert_eq!(calc_base_fee(&full, ethereum).unwrap(), Some(1_125_000_000));
+        let empty = header(30_000_000, 0, Some(1_000_000_000));
+        assert_eq!(calc_base_fee(&empty, ethereum).unwrap(), Some(875_000_000));
+
+        // OP stack: the target is 1/6 of the gas limit and changes are bounded by 1/250
+        let op_full = header(60_000_000, 60_000_000, Some(1_000_000_000));
+        assert_eq!(
+            calc_base_fee(&op_full, Some(BaseFeeParams::OP_STACK)).unwrap(),
+            Some(1_020_000_000)
+        );
+        let op_empty = header(60_000_000, 0, Some(1_000_000_000));
+        assert_eq!(
+            calc_base_fee(&op_empty, Some(BaseFeeParams::OP_STACK)).unwrap(),
+            Some(996_000_000)
+        );
+
+        // No overflow for high base fees
+        let expensive = header(30_000_000, 30_000_000, Some(u64::MAX / 2));
+        assert!(calc_base_fee(&expensive, ethereum).unwrap().is_some());
+
+        // Without parameters the base fee is carried over
+        assert_eq!(calc_base_fee(&full, None).unwrap(), Some(1_000_000_000));
+        assert_eq!(
+            calc_base_fee(&header(30_000_000, 0, None), ethereum).unwrap(),
+            None
+        );
+
+        // Parameters without a gas target or change bound are errors instead of panics
+        for params in [
+            BaseFeeParams {
+                elasticity_multiplier: 0,
+                ..BaseFeeParams::ETHEREUM
+            },
+            BaseFeeParams {
+                max_change_denominator: 0,
+                ..BaseFeeParams::ETHEREUM
+            },
+        ] {
+            assert!(calc_base_fee(&full, Some(params)).is_err());
         }
+        assert!(calc_base_fee(&header(1, 1, Some(1_000_000_000)), ethereum).is_err());
     }
 
-    None
+    fn create_user_transactions(prices_gwei: &[u128]) -> Vec<Transaction> {
+        prices_gwei
+            .iter()
+            .enumerate()
+            .map(|(i, price)| {
+                create_test_transaction(
+                    &format!("0x{i:x}"),
+                    2,
+                    "0x2222222222222222222222222222222222222222",
+                    price * 1_000_000_000,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_excluded_senders_filtered() {
+        let mut transactions = create_user_transactions(&[20, 20, 30]);
+        transactions.push(create_test_transaction(
+            "0xbuilder",
+            2,
+            "0x3333333333333333333333333333333333333333",
+            10_000_000_001,
+        ));
+
+        let filters = DistributionFilters {
+            excluded_senders: vec!["0x3333333333333333333333333333333333333333".to_string()],
+            ..Default::default()
+        };
+
+        let filtered = block_to_block_distribution(
+            &transactions,
+            &Some(1),
+            &System::Ethereum,
+            FeeMarket::Eip1559,
+            &filters,
+        );
+
+        assert_eq!(filtered.distribution[0].wei, 20_000_000_000);
+        assert_eq!(filtered.raw_min, Some(10_000_000_001));
+    }
+
+    #[test]
+    fn test_excluded_senders_case_insensitive() {
+        let transactions = create_user_transactions(&[20]);
+        let filters = DistributionFilters {
+            excluded_senders: vec!["0x2222222222222222222222222222222222222222".to_uppercase()],
+            ..Default::default()
+        };
+
+        let filtered = block_to_block_distribution(
+            &transactions,
+            &Some(1),
+            &System::Ethereum,
+            FeeMarket::Eip1559,
+            &filters,
+        );
+
+        assert!(filtered.distribution.is_empty());
+        assert_eq!(filtered.raw_min, Some(20_000_000_000));
+    }
+
+    #[test]
+    fn test_lower_trim_percentile() {
+        // 10 transactions, trimming 20% drops the two cheapest
+        let transactions = create_user_transactions(&[1, 5, 10, 10, 10, 10, 10, 12, 15, 20]);
+        let filters = DistributionFilters {
+            lower_trim_percentile: 0.2,
+            ..Default::default()
+        };
+
+        let filtered = block_to_block_distribution(
+            &transactions,
+            &Some(1),
+            &System::Ethereum,
+            FeeMarket::Eip1559,
+            &filters,
+        );
+
+        assert_eq!(filtered.distribution[0].wei, 10_000_000_000);
+        assert_eq!(filtered.distribution[0].count, 5);
+        assert_eq!(filtered.raw_min, Some(1_000_000_000));
+
+        // Trimming part of a bucket keeps the remainder and its share of gas
+        let filters = DistributionFilters {
+            lower_trim_percentile: 0.3,
+            ..Default::default()
+        };
+
+        let filtered = block_to_block_distribution(
+            &transactions,
+            &Some(1),
+            &System::Ethereum,
+            FeeMarket::Eip1559,
+            &filters,
+        );
+
+        assert_eq!(filtered.distribution[0].wei, 10_000_000_000);
+        assert_eq!(filtered.distribution[0].count, 4);
+        assert_eq!(filtered.distribution[0].gas, 84_000);
+    }
+
+    #[test]
+    fn test_min_cluster_size() {
+        let transactions = create_user_transactions(&[1, 5, 10, 10, 10, 12, 12, 12, 12]);
+        let filters = DistributionFilters {
+            min_cluster_size: 3,
+            ..Default::default()
+        };
+
+        let filtered = block_to_block_distribution(
+            &transactions,
+            &Some(1),
+            &System::Ethereum,
+            FeeMarket::Eip1559,
+            &filters,
+        );
+
+        assert_eq!(filtered.distribution[0].wei, 10_000_000_000);
+        assert_eq!(filtered.distribution.len(), 2);
+        assert_eq!(filtered.raw_min, Some(1_000_000_000));
+
+        // No bucket large enough leaves an empty distribution
+        let filters = DistributionFilters {
+            min_cluster_size: 10,
+            ..Default::default()
+        };
+
+        let filtered = block_to_block_distribution(
+            &transactions,
+            &Some(1),
+            &System::Ethereum,
+            FeeMarket::Eip1559,
+            &filters,
+        );
+
+        assert!(filtered.distribution.is_empty());
+        assert_eq!(filtered.raw_min, Some(1_000_000_000));
+    }
+
+    #[test]
+    fn test_gwei_to_wei() {
+        assert_eq!(gwei_to_wei(12.5), 12_500_000_000);
+        assert_eq!(gwei_to_wei(0.000000001), 1);
+        assert_eq!(gwei_to_wei(0.0012345678), 1_234_568);
+        assert_eq!(gwei_to_wei(-1.0), 0);
+        assert_eq!(gwei_to_wei(f64::NAN), 0);
+    }
+
+    fn blob_header(
+        excess_blob_gas: Option<u64>,
+        blob_gas_used: Option<u64>,
+        base_fee_per_gas: u64,
+    ) -> BlockHeader {
+        BlockHeader {
+            number: 1,
+            timestamp: chrono::Utc::now(),
+            gas_limit: 30_000_000,
+            gas_used: 15_000_000,
+            base_fee_per_gas: Some(base_fee_per_gas),
+            blob_gas_used,
+            excess_blob_gas,
+            extra_data: Default::default(),
+        }
+    }
+
+    fn prague_blob_params(bounded_by_execution_cost: bool) -> BlobParams {
+        BlobParams {
+            target_blobs_per_block: 6,
+            max_blobs_per_block: 9,
+            base_fee_update_fraction: 5_007_716,
+            bounded_by_execution_cost,
+        }
+    }
+
+    #[test]
+    fn test_fake_exponential() {
+        // Test vectors from EIP-4844
+        let cases = [
+            (1, 0, 1, 1),
+            (38_493, 0, 1_000, 38_493),
+            (0, 1_234, 2_345, 0),
+            (1, 2, 1, 6),
+            (1, 4, 2, 6),
+            (1, 3, 1, 16),
+            (1, 6, 2, 18),
+            (1, 4, 1, 49),
+            (1, 8, 2, 50),
+            (10, 8, 2, 542),
+            (11, 8, 2, 596),
+            (1, 5, 1, 136),
+            (1, 5, 2, 11),
+            (2, 5, 2, 23),
+            (1, 50_000_000, 2_225_652, 5_709_098_764),
+        ];
+
+        for (factor, numerator, denominator, expected) in cases {
+            assert_eq!(
+                fake_exponential(factor, numerator, denominator),
+                expected,
+                "fake_exponential({factor}, {numerator}, {denominator})"
+            );
+        }
+    }
+
+    #[test]
+    fn test_calc_excess_blob_gas() {
+        let params = prague_blob_params(false);
+
+        // Below target the excess resets to zero
+        let header = blob_header(Some(0), Some(3 * GAS_PER_BLOB), 1_000_000_000);
+        assert_eq!(calc_excess_blob_gas(&header, &params), Some(0));
+
+        // Above target the excess grows by the blob gas over target
+        let header = blob_header(
+            Some(10 * GAS_PER_BLOB),
+            Some(9 * GAS_PER_BLOB),
+            1_000_000_000,
+        );
+        assert_eq!(
+            calc_excess_blob_gas(&header, &params),
+            Some(13 * GAS_PER_BLOB)
+        );
+
+        // Before EIP-4844 there is no excess blob gas
+        let header = blob_header(None, None, 1_000_000_000);
+        assert_eq!(calc_excess_blob_gas(&header, &params), None);
+        assert_eq!(calc_blob_base_fee(&header, &params), None);
+    }
+
+    #[test]
+    fn test_calc_excess_blob_gas_bounded_by_execution_cost() {
+        let header = blob_header(
+            Some(10 * GAS_PER_BLOB),
+            Some(3 * GAS_PER_BLOB),
+            1_000_000_000,
+        );
+
+        // Without the bound, blob gas under target lowers the excess
+        assert_eq!(
+            calc_excess_blob_gas(&header, &prague_blob_params(false)),
+            Some(7 * GAS_PER_BLOB)
+        );
+
+        // With a 1 gwei execution base fee, blobs are priced below their execution cost,
+        // so the excess only grows by used * (max - target) / max
+        assert_eq!(
+            calc_excess_blob_gas(&header, &prague_blob_params(true)),
+            Some(11 * GAS_PER_BLOB)
+        );
+
+        // Once the blob base fee covers the execution cost the regular update applies
+        let header = blob_header(Some(10 * GAS_PER_BLOB), Some(3 * GAS_PER_BLOB), 1);
+        assert_eq!(
+            calc_excess_blob_gas(&header, &prague_blob_params(true)),
+            Some(7 * GAS_PER_BLOB)
+        );
+    }
+
+    #[test]
+    fn test_calc_blob_base_fee() {
+        let params = BlobParams {
+            target_blobs_per_block: 3,
+            max_blobs_per_block: 6,
+            base_fee_update_fraction: 2_225_652,
+            bounded_by_execution_cost: false,
+        };
+
+        let header = blob_header(
+            Some(50_000_000 - 3 * GAS_PER_BLOB),
+            Some(6 * GAS_PER_BLOB),
+            1,
+        );
+        assert_eq!(calc_blob_base_fee(&header, &params), Some(5_709_098_764));
+    }
 }
@@ -0,0 +1,338 @@
+use crate::chain::encode::PayloadEncoder;
+use crate::chain::records::RecordType;
+use crate::chain::types::{
+    OraclePayloadHeaderV2, OraclePayloadRecordV2, OraclePayloadV2, SignedOraclePayloadV2,
+    EVM_SYSTEM_ID,
+};
+use crate::types::{Network, System, SystemNetworkKey};
+use alloy::primitives::aliases::{U240, U48};
+use alloy::primitives::{keccak256, Address};
+use alloy::signers::Signature;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use thiserror::Error;
+
+/// Size of an encoded header or record
+pub const WORD_SIZE: usize = 32;
+/// Size of the signature appended to a signed payload (r, s, v)
+pub const SIGNATURE_SIZE: usize = 65;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    #[error("Expected {expected} bytes, got {actual}")]
+    InvalidLength { expected: usize, actual: usize },
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+}
+
+pub trait PayloadDecoder: Sized {
+    fn from_encoded_payload(buf: &[u8]) -> Result<Self, DecodeError>;
+}
+
+fn check_length(buf: &[u8], expected: usize) -> Result<(), DecodeError> {
+    if buf.len() != expected {
+        return Err(DecodeError::InvalidLength {
+            expected,
+            actual: buf.len(),
+        });
+    }
+    Ok(())
+}
+
+fn read_u64(buf: &[u8]) -> u64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&buf[..8]);
+    u64::from_be_bytes(bytes)
+}
+
+impl PayloadDecoder for OraclePayloadHeaderV2 {
+    // Inverse of the header encoding:
+    // 6 empty bytes, length (2), timestamp (6), system id (1), chain id (8), height (8), version (1)
+    fn from_encoded_payload(buf: &[u8]) -> Result<Self, DecodeError> {
+        check_length(buf, WORD_SIZE)?;
+
+        Ok(OraclePayloadHeaderV2 {
+            length: u16::from_be_bytes([buf[6], buf[7]]),
+            timestamp: U48::from_be_slice(&buf[8..14]),
+            system_id: buf[14],
+            chain_id: read_u64(&buf[15..23]),
+            height: read_u64(&buf[23..31]),
+            version: buf[31],
+        })
+    }
+}
+
+impl PayloadDecoder for OraclePayloadRecordV2 {
+    // Inverse of the record encoding: type (2), value (30)
+    fn from_encoded_payload(buf: &[u8]) -> Result<Self, DecodeError> {
+        check_length(buf, WORD_SIZE)?;
+
+        Ok(OraclePayloadRecordV2 {
+            typ: u16::from_be_bytes([buf[0], buf[1]]),
+            value: U240::from_be_slice(&buf[2..]),
+        })
+    }
+}
+
+impl PayloadDecoder for OraclePayloadV2 {
+    // A header followed by exactly `length` records
+    fn from_encoded_payload(buf: &[u8]) -> Result<Self, DecodeError> {
+        if buf.len() < WORD_SIZE {
+            return Err(DecodeError::InvalidLength {
+                expected: WORD_SIZE,
+                actual: buf.len(),
+            });
+        }
+
+        let header = OraclePayloadHeaderV2::from_encoded_payload(&buf[..WORD_SIZE])?;
+        check_length(buf, WORD_SIZE * (1 + header.length as usize))?;
+
+        let records = buf[WORD_SIZE..]
+            .chunks(WORD_SIZE)
+            .map(OraclePayloadRecordV2::from_encoded_payload)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(OraclePayloadV2 { header, records })
+    }
+}
+
+impl PayloadDecoder for SignedOraclePayloadV2 {
+    // An encoded payload followed by its 65 byte signature
+    fn from_encoded_payload(buf: &[u8]) -> Result<Self, DecodeError> {
+        if buf.len() < WORD_SIZE + SIGNATURE_SIZE {
+            return Err(DecodeError::InvalidLength {
+                expected: WORD_SIZE + SIGNATURE_SIZE,
+                actual: buf.len(),
+            });
+        }
+
+        let (payload, signature) = buf.split_at(buf.len() - SIGNATURE_SIZE);
+        let payload = OraclePayloadV2::from_encoded_payload(payload).map_err(|e| match e {
+            DecodeError::InvalidLength { expected, actual } => DecodeError::InvalidLength {
+                expected: expected + SIGNATURE_SIZE,
+                actual: actual + SIGNATURE_SIZE,
+            },
+            e => e,
+        })?;
+        let signature = Signature::from_raw(signature)
+            .map_err(|e| DecodeError::InvalidSignature(e.to_string()))?;
+
+        Ok(SignedOraclePayloadV2 {
+            payload,
+            signature: Some(signature),
+        })
+    }
+}
+
+impl SignedOraclePayloadV2 {
+    /// Recovers the address that signed the keccak256 hash of the encoded payload
+    pub fn recover_signer(&self) -> Result<Address, DecodeError> {
+        let signature = self
+            .signature
+            .ok_or_else(|| DecodeError::InvalidSignature("payload is not signed".to_string()))?;
+
+        let mut buf = vec![];
+        self.payload.to_encoded_payload(&mut buf);
+
+        signature
+            .recover_address_from_prehash(&keccak256(&buf))
+            .map_err(|e| DecodeError::InvalidSignature(e.to_string()))
+    }
+}
+
+/// Human readable view of a decoded payload
+#[derive(Debug, Serialize)]
+pub struct DecodedPayload {
+    pub version: u8,
+    pub system_id: u8,
+    pub chain_id: u64,
+    pub system: Option<System>,
+    pub network: Option<Network>,
+    pub height: u64,
+    pub timestamp: Option<DateTime<Utc>>,
+    pub records: Vec<DecodedRecord>,
+    pub signature: Option<String>,
+    pub signer: Option<Address>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DecodedRecord {
+    pub typ: u16,
+    /// `None` for type IDs missing from the registry
+    #[serde(rename = "type")]
+    pub record_type: Option<RecordType>,
+    pub value: String,
+}
+
+impl DecodedPayload {
+    /// Decodes a payload with or without its trailing signature, recovering the signer when signed
+    pub fn from_bytes(buf: &[u8]) -> Result<Self, DecodeError> {
+        let signed = match OraclePayloadV2::from_encoded_payload(buf) {
+            Ok(payload) => SignedOraclePayloadV2 {
+                payload,
+                signature: None,
+            },
+            Err(_) => SignedOraclePayloadV2::from_encoded_payload(buf)?,
+        };
+
+        let signer = match signed.signature {
+            Some(_) => Some(signed.recover_signer()?),
+            None => None,
+        };
+
+        let header = &signed.payload.header;
+        let key = (header.system_id == EVM_SYSTEM_ID)
+            .then(|| SystemNetworkKey::from_chain_id(header.chain_id))
+            .flatten();
+
+        Ok(DecodedPayload {
+            version: header.version,
+            system_id: header.system_id,
+            chain_id: header.chain_id,
+            system: key.as_ref().map(|key| key.system.clone()),
+            network: key.map(|key| key.network),
+            height: header.height,
+            timestamp: DateTime::from_timestamp_millis(header.timestamp.to::<i64>()),
+            records: signed
+                .payload
+                .records
+                .iter()
+                .map(|record| DecodedRecord {
+                    typ: record.typ,
+                    record_type: RecordType::from_id(record.typ),
+                    value: record.value.to_string(),
+                })
+                .collect(),
+            signature: signed
+                .signature
+                .map(|signature| alloy::hex::encode_prefixed(signature.as_bytes())),
+            signer,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::sign::PayloadSigner;
+    use crate::signer::AgentSigner;
+    use alloy::signers::local::PrivateKeySigner;
+
+    fn test_payload() -> OraclePayloadV2 {
+        OraclePayloadV2 {
+            header: OraclePayloadHeaderV2 {
+                version: 2,
+                height: 1236,
+                chain_id: 1,
+                system_id: 2,
+                timestamp: U48::from(1741250000002_u64),
+                length: 2,
+            },
+            records: vec![
+                OraclePayloadRecordV2 {
+                    typ: 340,
+                    value: U240::from(77_000_000_000_u64),
+                },
+                OraclePayloadRecordV2 {
+                    typ: 999,
+                    value: U240::from(1_u64),
+                },
+            ],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signed_payload_round_trip() {
+        let signer = AgentSigner::Local(PrivateKeySigner::random());
+        let mut signed = SignedOraclePayloadV2 {
+            payload: test_payload(),
+            signature: None,
+        };
+
+        let mut buf = vec![];
+        let size = signed.to_signed_payload(&mut buf, &signer).await.unwrap();
+        assert_eq!(size, 32 * 3 + SIGNATURE_SIZE);
+
+        let decoded = SignedOraclePayloadV2::from_encoded_payload(&buf).unwrap();
+        assert_eq!(decoded.payload, signed.payload);
+        assert_eq!(decoded.signature, signed.signature);
+        assert_eq!(decoded.recover_signer().unwrap(), signer.address());
+    }
+
+    #[tokio::test]
+    async fn test_decoded_payload() {
+        let signer = AgentSigner::Local(PrivateKeySigner::random());
+        let mut signed = SignedOraclePayloadV2 {
+            payload: test_payload(),
+            signature: None,
+        };
+        let mut buf = vec![];
+        signed.to_signed_payload(&mut buf, &signer).await.unwrap();
+
+        let decoded = DecodedPayload::from_bytes(&buf).unwrap();
+        assert_eq!(decoded.system, Some(System::Ethereum));
+        assert_eq!(decoded.network, Some(Network::Mainnet));
+        assert_eq!(decoded.height, 1236);
+        assert_eq!(
+            decoded.timestamp.unwrap().to_rfc3339(),
+            "2025-03-06T08:33:20.002+00:00"
+        );
+        assert_eq!(decoded.signer, Some(signer.address()));
+
+        let json = serde_json::to_value(&decoded).unwrap();
+        assert_eq!(json["records"][0]["type"], "max_priority_fee_per_gas_p99");
+        assert_eq!(json["records"][0]["value"], "77000000000");
+        assert_eq!(json["records"][1]["typ"], 999);
+        assert!(json["records"][1]["type"].is_null());
+
+        // Unsigned payloads are decoded without a signer
+        let unsigned = DecodedPayload::from_bytes(&buf[..buf.len() - SIGNATURE_SIZE]).unwrap();
+        assert_eq!(unsigned.signer, None);
+        assert_eq!(unsigned.signature, None);
+        assert_eq!(unsigned.records.len(), 2);
+    }
+
+    #[test]
+    fn test_decode_collector_payload() {
+        let buf = alloy::hex::decode(
+            "0x000000000000000101956a96748201000000000000000100000000000004d401\
+             00010000000000000000000000000000000000000000000000000000000000061313\
+             b7e8cef1bddd87f000f82e289b177bde13b4e7ffaaa39fc27f6be68c353807c4eb1b\
+             f5c0c9a6829d0f1a9d369544729febf9ab63fabfc8dd7bc92cda37581b",
+        )
+        .unwrap();
+
+        let signed = SignedOraclePayloadV2::from_encoded_payload(&buf).unwrap();
+        assert_eq!(signed.payload.header.height, 1236);
+        assert_eq!(signed.payload.header.length, 1);
+        assert_eq!(signed.payload.records[0].typ, 1);
+        assert_eq!(signed.payload.records[0].value, U240::from(6_u64));
+        assert!(signed.recover_signer().is_ok());
+
+        // System ID 1 is not an EVM system, so its chain ID is not resolved
+        let decoded = DecodedPayload::from_bytes(&buf).unwrap();
+        assert_eq!(decoded.system, None);
+        assert_eq!(decoded.records[0].record_type, None);
+    }
+
+    #[test]
+    fn test_decode_invalid_signed_payload() {
+        let mut buf = vec![];
+        test_payload().to_encoded_payload(&mut buf);
+
+        // Truncated signature
+        buf.extend_from_slice(&[0; 64]);
+        assert!(matches!(
+            SignedOraclePayloadV2::from_encoded_payload(&buf),
+            Err(DecodeError::InvalidLength { .. })
+        ));
+        assert!(DecodedPayload::from_bytes(&buf).is_err());
+
+        // Invalid recovery id
+        buf.push(5);
+        assert!(matches!(
+            SignedOraclePayloadV2::from_encoded_payload(&buf),
+            Err(DecodeError::InvalidSignature(_))
+        ));
+    }
+}
@@ -83,7 +83,13 @@ mod tests {
     use alloy::primitives::aliases::{U240, U48};
 
     use super::*;
+    use crate::chain::decode::{DecodeError, PayloadDecoder};
+    use crate::chain::records::RecordType;
+    use crate::chain::types::{OracleChain, EVM_SYSTEM_ID};
     use crate::logs::init_logs;
+    use crate::types::{
+        AgentPayload, Network, PayloadRecord, PriceKind, PriceUnit, Settlement, System,
+    };
 
     // This ensures metrics are initialized exactly once
     static INIT: Once = Once::new();
@@ -140,4 +146,132 @@ mod tests {
 
         //  0x000000000000000101956a96748201000000000000000100000000000004d40100010000000000000000000000000000000000000000000000000000000000061313b7e8cef1bddd87f000f82e289b177bde13b4e7ffaaa39fc27f6be68c353807c4eb1bf5c0c9a6829d0f1a9d369544729febf9ab63fabfc8dd7bc92cda37581b
     }
+
+    fn encode(payload: &OraclePayloadV2) -> Vec<u8> {
+        let mut buf = Vec::new();
+        payload.to_encoded_payload(&mut buf);
+        buf
+    }
+
+    const MAINNET: OracleChain = OracleChain {
+        system_id: EVM_SYSTEM_ID,
+        chain_id: 1,
+    };
+
+    fn test_agent_payload(records: Vec<PayloadRecord>) -> AgentPayload {
+        AgentPayload {
+            schema_version: "1".to_string(),
+            from_block: 1236,
+            settlement: Settlement::Fast,
+            timestamp: chrono::DateTime::from_timestamp_millis(1741250000002).unwrap(),
+            system: System::Ethereum,
+            network: Network::Mainnet,
+            unit: PriceUnit::Wei,
+            kind: PriceKind::GasPrice,
+            price: "77000000000".to_string(),
+            records,
+        }
+    }
+
+    #[test]
+    fn test_oracle_payload_v2_round_trip() {
+        setup();
+
+        let payload =
+            OraclePayloadV2::from_agent_payload(&test_agent_payload(vec![]), MAINNET).unwrap();
+        assert_eq!(payload.header.length, 1);
+        assert_eq!(
+            payload.records[0].typ,
+            RecordType::MaxPriorityFeePerGasP99.id()
+        );
+
+        let buf = encode(&payload);
+        assert_eq!(buf.len(), 64);
+        assert_eq!(
+            OraclePayloadV2::from_encoded_payload(&buf).unwrap(),
+            payload
+        );
+    }
+
+    #[test]
+    fn test_oracle_payload_v2_multiple_records_round_trip() {
+        setup();
+
+        let records = vec![
+            PayloadRecord {
+                record_type: RecordType::BaseFeePerGas,
+                value: "12000000000".to_string(),
+            },
+            PayloadRecord {
+                record_type: RecordType::MaxPriorityFeePerGasP50,
+                value: "1500000000".to_string(),
+            },
+            PayloadRecord {
+                record_type: RecordType::BlobBaseFeePerGas,
+                value: "1".to_string(),
+            },
+        ];
+        let payload =
+            OraclePayloadV2::from_agent_payload(&test_agent_payload(records), MAINNET).unwrap();
+
+        assert_eq!(payload.header.length, 4);
+        let types: Vec<u16> = payload.records.iter().map(|record| record.typ).collect();
+        assert_eq!(types, vec![340, 107, 337, 112]);
+        assert_eq!(payload.records[1].value, U240::from(12000000000_u64));
+
+        let buf = encode(&payload);
+        assert_eq!(buf.len(), 32 * 5);
+
+        let decoded = OraclePayloadV2::from_encoded_payload(&buf).unwrap();
+        assert_eq!(decoded, payload);
+        assert_eq!(encode(&decoded), buf);
+    }
+
+    #[test]
+    fn test_oracle_payload_v2_custom_chain_header() {
+        setup();
+
+        let mut agent_payload = test_agent_payload(vec![]);
+        agent_payload.system = System::Custom("scroll".to_string());
+
+        let chain = OracleChain {
+            system_id: 7,
+            chain_id: 534352,
+        };
+        let payload = OraclePayloadV2::from_agent_payload(&agent_payload, chain).unwrap();
+        assert_eq!(payload.header.system_id, 7);
+        assert_eq!(payload.header.chain_id, 534352);
+
+        let buf = encode(&payload);
+        assert_eq!(buf[14], 7);
+        assert_eq!(&buf[15..23], &534352_u64.to_be_bytes());
+        assert_eq!(
+            OraclePayloadV2::from_encoded_payload(&buf).unwrap().header,
+            payload.header
+        );
+    }
+
+    #[test]
+    fn test_oracle_payload_v2_decode_invalid_length() {
+        setup();
+
+        let buf = encode(
+            &OraclePayloadV2::from_agent_payload(&test_agent_payload(vec![]), MAINNET).unwrap(),
+        );
+
+        // Missing record
+        assert_eq!(
+            OraclePayloadV2::from_encoded_payload(&buf[..32]),
+            Err(DecodeError::InvalidLength {
+                expected: 64,
+                actual: 32
+            })
+        );
+        // Trailing bytes
+        let mut extended = buf.clone();
+        extended.extend_from_slice(&[0; 32]);
+        assert!(OraclePayloadV2::from_encoded_payload(&extended).is_err());
+        // Truncated header
+        assert!(OraclePayloadV2::from_encoded_payload(&buf[..16]).is_err());
+    }
 }
@@ -1,4 +1,6 @@
 pub mod convert;
+pub mod decode;
 pub mod encode;
+pub mod records;
 pub mod sign;
 pub mod types;
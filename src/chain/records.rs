@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, EnumString};
+
+/// Registry of oracle record types. Each record in an `OraclePayloadV2` carries the
+/// type ID of what its value measures; all values are in wei.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Display, EnumString, EnumIter,
+)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum RecordType {
+    /// Base fee per gas of the next block
+    BaseFeePerGas,
+    /// Base fee per blob gas of the next block (EIP-4844)
+    BlobBaseFeePerGas,
+    /// 25th percentile of the max priority fee per gas
+    MaxPriorityFeePerGasP25,
+    /// 50th percentile of the max priority fee per gas
+    MaxPriorityFeePerGasP50,
+    /// 75th percentile of the max priority fee per gas
+    MaxPriorityFeePerGasP75,
+    /// 90th percentile of the max priority fee per gas
+    MaxPriorityFeePerGasP90,
+    /// Predicted gas price of an agent. The oracle has read agent gas prices from type 340 since
+    /// payloads carried a single record, so it holds the total gas price rather than a priority
+    /// fee percentile and is not available as an additional record.
+    MaxPriorityFeePerGasP99,
+    /// L1 data fee per byte of compressed transaction data on OP stack chains. Its ID is
+    /// provisional until the oracle registry assigns one.
+    L1DataFeePerByte,
+}
+
+impl RecordType {
+    pub const fn id(&self) -> u16 {
+        match self {
+            RecordType::BaseFeePerGas => 107,
+            RecordType::BlobBaseFeePerGas => 112,
+            RecordType::MaxPriorityFeePerGasP25 => 336,
+            RecordType::MaxPriorityFeePerGasP50 => 337,
+            RecordType::MaxPriorityFeePerGasP75 => 338,
+            RecordType::MaxPriorityFeePerGasP90 => 339,
+            RecordType::MaxPriorityFeePerGasP99 => 340,
+            RecordType::L1DataFeePerByte => 360,
+        }
+    }
+
+    pub fn from_id(id: u16) -> Option<Self> {
+        RecordType::iter().find(|record_type| record_type.id() == id)
+    }
+
+    /// Whether the type ID has not been assigned in the oracle registry yet. Records of these
+    /// types are not published in signed payloads.
+    pub const fn is_provisional(&self) -> bool {
+        matches!(self, RecordType::L1DataFeePerByte)
+    }
+
+    /// The percentile (0.0 - 1.0) of priority fee records
+    pub fn priority_fee_percentile(&self) -> Option<f64> {
+        match self {
+            RecordType::MaxPriorityFeePerGasP25 => Some(0.25),
+            RecordType::MaxPriorityFeePerGasP50 => Some(0.5),
+            RecordType::MaxPriorityFeePerGasP75 => Some(0.75),
+            RecordType::MaxPriorityFeePerGasP90 => Some(0.9),
+            RecordType::MaxPriorityFeePerGasP99
+            | RecordType::BaseFeePerGas
+            | RecordType::BlobBaseFeePerGas
+            | RecordType::L1DataFeePerByte => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_record_type_ids_round_trip() {
+        let mut ids = HashSet::new();
+
+        for record_type in RecordType::iter() {
+            assert!(ids.insert(record_type.id()), "duplicate id {record_type}");
+            assert_eq!(RecordType::from_id(record_type.id()), Some(record_type));
+            assert_eq!(
+                RecordType::from_str(&record_type.to_string()).unwrap(),
+                record_type
+            );
+        }
+
+        assert_eq!(RecordType::from_id(0), None);
+        assert_eq!(RecordType::MaxPriorityFeePerGasP99.id(), 340);
+        assert_eq!(
+            RecordType::BlobBaseFeePerGas.to_string(),
+            "blob_base_fee_per_gas"
+        );
+        assert_eq!(
+            serde_json::to_string(&RecordType::L1DataFeePerByte).unwrap(),
+            "\"l1_data_fee_per_byte\""
+        );
+        assert_eq!(
+            RecordType::from_str("l1_data_fee_per_byte").unwrap(),
+            RecordType::L1DataFeePerByte
+        );
+    }
+}
@@ -1,26 +1,33 @@
 use crate::chain::{encode::PayloadEncoder, types::SignedOraclePayloadV2};
-use alloy::{primitives::keccak256, signers::SignerSync};
+use crate::signer::AgentSigner;
+use alloy::primitives::keccak256;
 use bytes::BufMut;
 
 pub trait PayloadSigner {
-    fn to_signed_payload<B, S>(&mut self, buf: &mut B, signer: S) -> Result<usize, SignerError>
+    async fn to_signed_payload<B>(
+        &mut self,
+        buf: &mut B,
+        signer: &AgentSigner,
+    ) -> Result<usize, SignerError>
     where
-        B: bytes::BufMut + AsMut<[u8]>,
-        S: SignerSync;
+        B: bytes::BufMut + AsMut<[u8]>;
 }
 
 impl PayloadSigner for SignedOraclePayloadV2 {
-    fn to_signed_payload<B, S>(&mut self, buf: &mut B, signer: S) -> Result<usize, SignerError>
+    async fn to_signed_payload<B>(
+        &mut self,
+        buf: &mut B,
+        signer: &AgentSigner,
+    ) -> Result<usize, SignerError>
     where
         B: bytes::BufMut + AsMut<[u8]>,
-        S: SignerSync,
     {
         let mut buf_int = vec![];
         let mut size = 0;
         size += self.payload.to_encoded_payload(&mut buf_int);
 
         // sign the keccak256 hash, not the payload
-        match signer.sign_hash_sync(&keccak256(&buf_int)) {
+        match signer.sign_hash(&keccak256(&buf_int)).await {
             Ok(signature) => {
                 self.signature = Some(signature);
             }
@@ -61,8 +68,8 @@ mod tests {
     use super::*;
     use crate::chain::types::{OraclePayloadHeaderV2, OraclePayloadRecordV2, OraclePayloadV2};
 
-    #[test]
-    fn test_to_signed_payload_success() {
+    #[tokio::test]
+    async fn test_to_signed_payload_success() {
         let mut payload = SignedOraclePayloadV2 {
             payload: OraclePayloadV2 {
                 header: OraclePayloadHeaderV2 {
@@ -83,9 +90,9 @@ mod tests {
 
         let mut buf = vec![];
 
-        let signer = PrivateKeySigner::random();
+        let signer = AgentSigner::Local(PrivateKeySigner::random());
         let initial_signer = signer.address();
-        let result = payload.to_signed_payload(&mut buf, signer);
+        let result = payload.to_signed_payload(&mut buf, &signer).await;
 
         assert!(result.is_ok());
         assert_eq!(buf.len(), 129);
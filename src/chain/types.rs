@@ -1,5 +1,4 @@
 use super::super::types::AgentPayload;
-use crate::types::{Network, System, SystemNetworkKey};
 use alloy::{
     primitives::{
         aliases::{U240, U48},
@@ -7,6 +6,23 @@ use alloy::{
     },
     signers::Signature,
 };
+use thiserror::Error;
+
+/// System ID of EVM chains, whose chain ID is the EIP-155 chain ID
+pub const EVM_SYSTEM_ID: u8 = 2;
+
+/// The chain the records of an oracle payload are for, as published in its header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OracleChain {
+    pub system_id: u8,
+    pub chain_id: u64,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ConvertError {
+    #[error("{value:?} is not a decimal wei value")]
+    InvalidWei { value: String },
+}
 
 #[derive(Debug)]
 pub struct SignedOraclePayloadV2 {
@@ -14,13 +30,13 @@ pub struct SignedOraclePayloadV2 {
     pub signature: Option<Signature>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct OraclePayloadV2 {
     pub header: OraclePayloadHeaderV2,
     pub records: Vec<OraclePayloadRecordV2>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct OraclePayloadHeaderV2 {
     // Version of the payload format
     pub version: u8,
@@ -36,7 +52,7 @@ pub struct OraclePayloadHeaderV2 {
     pub length: u16,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct OraclePayloadRecordV2 {
     // TypeID of the record
     pub typ: u16,
@@ -44,38 +60,49 @@ pub struct OraclePayloadRecordV2 {
     pub value: U240,
 }
 
-impl From<AgentPayload> for OraclePayloadV2 {
-    fn from(payload: AgentPayload) -> Self {
-        let (systemid, chainid) = get_network_config_values(&payload.system, &payload.network);
+impl OraclePayloadV2 {
+    pub fn from_agent_payload(
+        payload: &AgentPayload,
+        chain: OracleChain,
+    ) -> Result<Self, ConvertError> {
+        // The price is the first record, followed by any additional records of the payload
+        let records: Vec<OraclePayloadRecordV2> =
+            std::iter::once((payload.kind.record_type(), payload.price.as_str()))
+                .chain(
+                    payload
+                        .records
+                        .iter()
+                        .map(|record| (record.record_type, record.value.as_str())),
+                )
+                .map(|(typ, value)| {
+                    Ok(OraclePayloadRecordV2 {
+                        typ: typ.id(),
+                        value: wei_to_u240(value)?,
+                    })
+                })
+                .collect::<Result<_, _>>()?;
 
-        OraclePayloadV2 {
+        Ok(OraclePayloadV2 {
             header: OraclePayloadHeaderV2 {
                 version: 2,
                 height: payload.from_block,
-                chain_id: chainid,
-                system_id: systemid,
+                chain_id: chain.chain_id,
+                system_id: chain.system_id,
                 timestamp: U48::from(payload.timestamp.timestamp_millis()),
-                length: 1,
+                length: records.len() as u16,
             },
-            records: vec![OraclePayloadRecordV2 {
-                typ: 340, // Hardcoded into type 340 - Max Priority Fee Per Gas 99th.
-                value: {
-                    // Convert decimal string price (wei) to uint240 by truncating high 16 bits (should be zero for realistic prices)
-                    let wei = U256::from_str_radix(&payload.price, 10)
-                        .expect("valid decimal string for wei price");
-                    let bytes32 = wei.to_be_bytes::<32>();
-                    let mut arr30 = [0u8; 30];
-                    arr30.copy_from_slice(&bytes32[2..]);
-                    U240::from_be_bytes::<30>(arr30)
-                },
-            }],
-        }
+            records,
+        })
     }
 }
 
-fn get_network_config_values(system: &System, network: &Network) -> (u8, u64) {
-    (
-        2,
-        SystemNetworkKey::new(system.clone(), network.clone()).to_chain_id(),
-    )
+/// Converts a decimal wei string to uint240 by truncating the high 16 bits (zero for realistic prices)
+fn wei_to_u240(wei: &str) -> Result<U240, ConvertError> {
+    let wei = U256::from_str_radix(wei, 10).map_err(|_| ConvertError::InvalidWei {
+        value: wei.to_string(),
+    })?;
+    let bytes32 = wei.to_be_bytes::<32>();
+    let mut arr30 = [0u8; 30];
+    arr30.copy_from_slice(&bytes32[2..]);
+    Ok(U240::from_be_bytes::<30>(arr30))
 }
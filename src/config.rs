@@ -1,10 +1,20 @@
-use clap::{Parser, Subcommand};
+use alloy::primitives::Address;
+use anyhow::{anyhow, Result};
+use clap::{Args, Parser, Subcommand};
 use reqwest::Url;
 use serde::Deserialize;
-use serde_json::Value;
+use serde_json::{json, Value};
+use std::fmt;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 
-use crate::types::{AgentKind, Network, System};
+use crate::chain::{
+    records::RecordType,
+    types::{OracleChain, EVM_SYSTEM_ID},
+};
+use crate::types::{
+    AgentKind, ChainParams, FeeMarket, ModelKind, Network, System, SystemNetworkKey,
+};
 
 #[derive(Parser)]
 #[command(name = "Gas Agent")]
@@ -18,8 +28,64 @@ pub struct Cli {
 pub enum Commands {
     /// Start the agent(s) to generate gas price predictions
     Start(Config),
-    /// Generate and print a new random key pair to be used as an agent's signer key
-    GenerateKeys,
+    /// Generate a new random key pair to be used as an agent's signer key
+    GenerateKeys(GenerateKeysArgs),
+    /// Decode a hex encoded oracle payload, with or without its signature, and print it as JSON
+    Decode {
+        /// Hex encoded payload (optionally 0x prefixed)
+        payload: String,
+    },
+    /// Verify the signatures of a published payload JSON object with `payload`, `signature` and `network_signature`
+    Verify(VerifyArgs),
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct VerifyArgs {
+    /// Published payload JSON, or `-` to read it from stdin
+    pub published: String,
+
+    /// Chain ID in the oracle payload header, required for chains without built-in parameters
+    #[arg(long)]
+    pub chain_id: Option<u64>,
+
+    /// System ID in the oracle payload header, EVM by default
+    #[arg(long, requires = "chain_id")]
+    pub system_id: Option<u8>,
+}
+
+impl VerifyArgs {
+    /// The oracle chain given on the command line, `None` to use the built-in chain of the payload
+    pub fn oracle_chain(&self) -> Option<OracleChain> {
+        self.chain_id.map(|chain_id| OracleChain {
+            system_id: self.system_id.unwrap_or(EVM_SYSTEM_ID),
+            chain_id,
+        })
+    }
+}
+
+#[derive(Args, Clone, Debug)]
+pub struct GenerateKeysArgs {
+    /// Write the private key to an encrypted JSON keystore file instead of printing it
+    #[arg(long)]
+    pub keystore: Option<PathBuf>,
+
+    /// Environment variable holding the keystore password, prompted for when neither password option is set
+    #[arg(long, requires = "keystore", conflicts_with = "password_file")]
+    pub password_env: Option<String>,
+
+    /// File holding the keystore password
+    #[arg(long, requires = "keystore")]
+    pub password_file: Option<PathBuf>,
+}
+
+impl GenerateKeysArgs {
+    pub fn password_source(&self) -> PasswordSource {
+        match (&self.password_env, &self.password_file) {
+            (Some(var), _) => PasswordSource::Env(var.clone()),
+            (None, Some(path)) => PasswordSource::File(path.clone()),
+            (None, None) => PasswordSource::Prompt,
+        }
+    }
 }
 
 #[derive(Parser, Clone, Debug)]
@@ -43,27 +109,509 @@ pub struct Config {
 pub struct ChainConfig {
     pub system: System,
     pub network: Network,
+    /// Parameters of a chain without built-in support, overriding any built-in parameters
+    pub chain: Option<CustomChainConfig>,
     pub json_rpc_url: String,
     pub pending_block_data_source: Option<PendingBlockDataSource>,
+    /// Where the gas of each transaction is read from for gas-weighted distributions
+    #[serde(default)]
+    pub gas_source: GasSource,
+    /// Outlier filters applied to block distributions
+    #[serde(default)]
+    pub distribution_filters: DistributionFilters,
+    /// Overrides the blob (EIP-4844) parameters of the system, eg. ahead of a fork
+    pub blob_params: Option<BlobParams>,
+    /// Overrides the EIP-1559 parameters used to derive the next base fee
+    pub base_fee_params: Option<BaseFeeParams>,
+    /// Overrides whether the EIP-1559 parameters are read from the latest block's `extraData`
+    pub base_fee_params_from_extra_data: Option<bool>,
     pub agents: Vec<AgentConfig>,
 }
 
+impl ChainConfig {
+    /// Parameters of the configured chain, with the blob and base fee overrides applied
+    pub fn chain_params(&self) -> Result<ChainParams> {
+        let mut params = match &self.chain {
+            Some(chain) => chain.to_chain_params(),
+            None => SystemNetworkKey::new(self.system.clone(), self.network.clone())
+                .to_chain_params()
+                .ok_or_else(|| {
+                    anyhow!(
+                        "No built-in parameters for {} {}. Set chain in the chain configuration",
+                        self.system,
+                        self.network
+                    )
+                })?,
+        };
+
+        if let Some(blob_params) = &self.blob_params {
+            params.blob_params = Some(blob_params.clone());
+        }
+
+        if let Some(base_fee_params) = self.base_fee_params {
+            if base_fee_params.elasticity_multiplier == 0
+                || base_fee_params.max_change_denominator == 0
+            {
+                return Err(anyhow!(
+                    "base_fee_params elasticity_multiplier and max_change_denominator must not be zero"
+                ));
+            }
+            params.base_fee_params = Some(base_fee_params);
+        }
+
+        if let Some(from_extra_data) = self.base_fee_params_from_extra_data {
+            params.base_fee_params_from_extra_data = from_extra_data;
+        }
+
+        Ok(params)
+    }
+}
+
+/// Parameters of a chain without built-in support
+#[derive(Debug, Clone, Deserialize)]
+pub struct CustomChainConfig {
+    pub chain_id: u64,
+    pub block_time_ms: u64,
+    /// System ID published in the oracle payload header, EVM by default
+    #[serde(default = "CustomChainConfig::default_system_id")]
+    pub system_id: u8,
+    #[serde(default)]
+    pub fee_market: FeeMarket,
+    /// Whether the chain is an OP stack rollup, which enables the L1 data fee record and reads
+    /// the EIP-1559 parameters from `extraData`
+    #[serde(default)]
+    pub op_stack: bool,
+}
+
+impl CustomChainConfig {
+    fn default_system_id() -> u8 {
+        EVM_SYSTEM_ID
+    }
+
+    fn to_chain_params(&self) -> ChainParams {
+        ChainParams {
+            chain_id: self.chain_id,
+            system_id: self.system_id,
+            block_time_ms: self.block_time_ms,
+            fee_market: self.fee_market,
+            base_fee_params: Some(BaseFeeParams::ETHEREUM),
+            base_fee_params_from_extra_data: self.op_stack,
+            blob_params: None,
+            op_stack: self.op_stack,
+        }
+    }
+}
+
+/// Blob gas parameters of a fork, used to derive the next blob base fee
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BlobParams {
+    pub target_blobs_per_block: u64,
+    pub max_blobs_per_block: u64,
+    pub base_fee_update_fraction: u64,
+    /// Whether the blob base fee is bounded by the execution base fee (EIP-7918)
+    #[serde(default)]
+    pub bounded_by_execution_cost: bool,
+}
+
+/// EIP-1559 parameters that derive the base fee of a block from its parent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct BaseFeeParams {
+    /// Ratio of the block gas limit to the gas target
+    pub elasticity_multiplier: u64,
+    /// Bounds the base fee change between blocks to `1 / max_change_denominator`
+    pub max_change_denominator: u64,
+    /// Lower bound of the base fee in wei (OP stack since Jovian)
+    #[serde(default)]
+    pub min_base_fee: u64,
+}
+
+impl BaseFeeParams {
+    pub const ETHEREUM: Self = Self {
+        elasticity_multiplier: 2,
+        max_change_denominator: 8,
+        min_base_fee: 0,
+    };
+
+    /// OP stack defaults since the Canyon upgrade
+    pub const OP_STACK: Self = Self {
+        elasticity_multiplier: 6,
+        max_change_denominator: 250,
+        min_base_fee: 0,
+    };
+
+    /// Polygon PoS since the Bhilai hardfork
+    pub const POLYGON: Self = Self {
+        elasticity_multiplier: 2,
+        max_change_denominator: 64,
+        min_base_fee: 0,
+    };
+}
+
+/// Filters that remove outliers from block distributions so the block minimum reflects
+/// what a public transaction could have paid to be included
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DistributionFilters {
+    /// Fraction of the lowest priced transactions to drop, eg. 0.01 drops the cheapest 1%
+    #[serde(default)]
+    pub lower_trim_percentile: f64,
+    /// Minimum number of transactions that must share the lowest price for it to be kept
+    #[serde(default)]
+    pub min_cluster_size: u32,
+    /// Sender addresses whose transactions are excluded, eg. builders paying themselves
+    #[serde(default)]
+    pub excluded_senders: Vec<String>,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct AgentConfig {
     pub kind: AgentKind,
-    pub signer_key: String,
+    pub signer_key: SignerKey,
     pub prediction_trigger: PredictionTrigger,
+    #[serde(default)]
+    pub model_config: ModelConfig,
+    /// Additional oracle records published in the same signed payload as the price
+    #[serde(default)]
+    pub records: Vec<RecordType>,
+}
+
+/// Private key used to sign an agent's payloads
+#[derive(Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(untagged)]
+pub enum SignerKey {
+    /// Hex encoded private key
+    PrivateKey(String),
+    /// Encrypted JSON keystore (Web3 Secret Storage) and where to read its password from
+    Keystore {
+        keystore: PathBuf,
+        password: PasswordSource,
+    },
+    /// Key held by a Web3Signer compatible signing service
+    Remote {
+        remote_signer: String,
+        address: Address,
+    },
+}
+
+// Keeps private keys out of logs
+impl fmt::Debug for SignerKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignerKey::PrivateKey(_) => f.write_str("PrivateKey(<redacted>)"),
+            SignerKey::Keystore { keystore, password } => f
+                .debug_struct("Keystore")
+                .field("keystore", keystore)
+                .field("password", password)
+                .finish(),
+            SignerKey::Remote {
+                remote_signer,
+                address,
+            } => f
+                .debug_struct("Remote")
+                .field("remote_signer", remote_signer)
+                .field("address", address)
+                .finish(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PasswordSource {
+    /// Name of an environment variable holding the password
+    Env(String),
+    /// File holding the password, trailing newlines are ignored
+    File(PathBuf),
+    /// Prompt for the password on the terminal at startup
+    Prompt,
+}
+
+/// Optional parameters passed to the model of a model agent
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ModelConfig {
+    /// How transactions are weighted when computing distribution statistics
+    #[serde(default)]
+    pub weighting: Weighting,
+    /// Members and combination method for the `ensemble` model
+    pub ensemble: Option<EnsembleConfig>,
+    /// Base model and controller parameters for the `self_tuning` model
+    #[serde(default)]
+    pub self_tuning: SelfTuningConfig,
+    /// Smoothing and published quantile for the `ewma` model
+    #[serde(default)]
+    pub ewma: EwmaConfig,
+    /// Noise parameters and published quantile for the `kalman` model
+    #[serde(default)]
+    pub kalman: KalmanConfig,
+    /// Quantile and fitting parameters for the `quantile_regression` model
+    #[serde(default)]
+    pub quantile_regression: QuantileRegressionConfig,
+    /// Limits on the age of pending block snapshots used by pending block models
+    #[serde(default)]
+    pub pending_staleness: PendingStalenessConfig,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PendingStalenessConfig {
+    /// Maximum age of a snapshot in milliseconds, two block times when not set
+    pub max_age_ms: Option<u64>,
+    /// Number of blocks the chain tip may have advanced since the snapshot was taken. A snapshot
+    /// one block behind is expected between a new block and the next pending block poll.
+    pub max_tip_lag: u64,
+    /// Whether stale snapshots are refused or used with a warning
+    pub on_stale: StalePendingPolicy,
+}
+
+impl Default for PendingStalenessConfig {
+    fn default() -> Self {
+        Self {
+            max_age_ms: None,
+            max_tip_lag: 1,
+            on_stale: StalePendingPolicy::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StalePendingPolicy {
+    /// The model fails and the agent publishes nothing
+    #[default]
+    Refuse,
+    /// The snapshot is used and a warning is logged
+    Flag,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EwmaConfig {
+    /// Weight of the newest block minimum (0.0 - 1.0]
+    pub alpha: f64,
+    /// Quantile of the predictive distribution to publish (0.0 - 1.0)
+    pub quantile: f64,
+}
+
+impl Default for EwmaConfig {
+    fn default() -> Self {
+        Self {
+            alpha: 0.2,
+            quantile: 0.9,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct KalmanConfig {
+    /// Variance added to the log price between blocks
+    pub process_noise: f64,
+    /// Variance of a block minimum around the underlying log price
+    pub measurement_noise: f64,
+    /// Quantile of the predictive distribution to publish (0.0 - 1.0)
+    pub quantile: f64,
+}
+
+impl Default for KalmanConfig {
+    fn default() -> Self {
+        Self {
+            process_noise: 0.01,
+            measurement_noise: 0.05,
+            quantile: 0.9,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct QuantileRegressionConfig {
+    /// Quantile of the next block minimum to predict (0.0 - 1.0)
+    pub quantile: f64,
+    /// Step size of each gradient update on standardized features
+    pub learning_rate: f64,
+}
+
+impl Default for QuantileRegressionConfig {
+    fn default() -> Self {
+        Self {
+            quantile: 0.9,
+            learning_rate: 0.05,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SelfTuningConfig {
+    /// The model whose prediction the premium is applied to
+    pub base_model: ModelKind,
+    /// Fraction of predictions that should be at or above the block window minimum
+    pub target_inclusion_rate: f64,
+    /// How far the premium moves for each resolved prediction
+    pub gain: f64,
+    /// Lower bound of the premium, as a fraction of the base prediction
+    pub min_premium: f64,
+    /// Upper bound of the premium, as a fraction of the base prediction
+    pub max_premium: f64,
+}
+
+impl Default for SelfTuningConfig {
+    fn default() -> Self {
+        Self {
+            base_model: ModelKind::LastMin,
+            target_inclusion_rate: 0.9,
+            gain: 0.01,
+            min_premium: 0.0,
+            max_premium: 0.5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EnsembleConfig {
+    /// The base models to run over the same block distributions
+    pub members: Vec<EnsembleMember>,
+    /// How the member predictions are combined into a single price
+    #[serde(default)]
+    pub combine: EnsembleCombine,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EnsembleMember {
+    pub model: ModelKind,
+    /// Relative weight of this member's prediction, must be positive
+    #[serde(default = "EnsembleMember::default_weight")]
+    pub weight: f64,
+}
+
+impl EnsembleMember {
+    fn default_weight() -> f64 {
+        1.0
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnsembleCombine {
+    /// The weighted median of the member predictions
+    #[default]
+    WeightedMedian,
+    /// The weighted quantile `q` (0.0 - 1.0) of the member predictions
+    Quantile { q: f64 },
+    /// The highest member prediction
+    Max,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Weighting {
+    /// Every transaction counts once
+    #[default]
+    Count,
+    /// Transactions are weighted by the gas they consume
+    Gas,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GasSource {
+    /// Use the `gas` limit from the transaction in the block body
+    #[default]
+    Transaction,
+    /// Fetch `gasUsed` for every transaction with `eth_getBlockReceipts`
+    Receipts,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum PendingBlockDataSource {
+    /// Any method returning an object with a `transactions` array, eg. a builder's pending block
     JsonRpc {
         url: String,
         method: String,
         params: Option<Value>,
         poll_rate_ms: u64,
     },
+    /// Pending transactions of a Geth compatible `txpool_content`
+    TxpoolContent { url: String, poll_rate_ms: u64 },
+    /// The node's pending block from `eth_getBlockByNumber("pending", true)`
+    PendingBlock { url: String, poll_rate_ms: u64 },
+    /// Full pending transactions streamed with a `newPendingTransactions` WebSocket subscription
+    #[serde(rename = "websocket")]
+    WebSocket {
+        url: String,
+        /// How long a transaction is kept without being included
+        #[serde(default = "PendingBlockDataSource::default_ttl_ms")]
+        ttl_ms: u64,
+    },
+}
+
+impl PendingBlockDataSource {
+    fn default_ttl_ms() -> u64 {
+        300_000
+    }
+
+    /// How pending transactions are received from this source
+    pub fn feed(&self) -> PendingBlockFeed {
+        match self {
+            PendingBlockDataSource::JsonRpc {
+                url,
+                method,
+                params,
+                poll_rate_ms,
+            } => PendingBlockFeed::Polled {
+                url: url.clone(),
+                request: PendingBlockRequest::Block {
+                    method: method.clone(),
+                    params: params.clone(),
+                },
+                poll_rate_ms: *poll_rate_ms,
+            },
+            PendingBlockDataSource::TxpoolContent { url, poll_rate_ms } => {
+                PendingBlockFeed::Polled {
+                    url: url.clone(),
+                    request: PendingBlockRequest::TxpoolContent,
+                    poll_rate_ms: *poll_rate_ms,
+                }
+            }
+            PendingBlockDataSource::PendingBlock { url, poll_rate_ms } => {
+                PendingBlockFeed::Polled {
+                    url: url.clone(),
+                    request: PendingBlockRequest::Block {
+                        method: "eth_getBlockByNumber".to_string(),
+                        params: Some(json!(["pending", true])),
+                    },
+                    poll_rate_ms: *poll_rate_ms,
+                }
+            }
+            PendingBlockDataSource::WebSocket { url, .. } => {
+                PendingBlockFeed::Streamed { url: url.clone() }
+            }
+        }
+    }
+}
+
+/// A pending block data source by how its pending transactions are received
+#[derive(Debug, Clone)]
+pub enum PendingBlockFeed {
+    Polled {
+        url: String,
+        request: PendingBlockRequest,
+        poll_rate_ms: u64,
+    },
+    Streamed {
+        url: String,
+    },
+}
+
+/// The request made on every poll of a polled pending block data source
+#[derive(Debug, Clone, PartialEq)]
+pub enum PendingBlockRequest {
+    /// A method returning an object with a `transactions` array
+    Block {
+        method: String,
+        params: Option<Value>,
+    },
+    TxpoolContent,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -72,3 +620,177 @@ pub enum PredictionTrigger {
     Block,
     Poll { rate_ms: u64 },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_chain_config(json: &str) -> ChainConfig {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_pending_block_data_sources() {
+        let sources: Vec<PendingBlockDataSource> = serde_json::from_str(
+            r#"[
+                {"json_rpc": {"url": "http://builder:8545", "method": "eth_getPendingBlock", "params": ["pending"], "poll_rate_ms": 1000}},
+                {"txpool_content": {"url": "http://geth:8545", "poll_rate_ms": 500}},
+                {"pending_block": {"url": "http://reth:8545", "poll_rate_ms": 250}},
+                {"websocket": {"url": "ws://geth:8546"}}
+            ]"#,
+        )
+        .unwrap();
+
+        let feeds: Vec<PendingBlockFeed> = sources.iter().map(|source| source.feed()).collect();
+
+        assert!(matches!(
+            &feeds[0],
+            PendingBlockFeed::Polled {
+                url,
+                request: PendingBlockRequest::Block { method, params: Some(_) },
+                poll_rate_ms: 1000,
+            } if url == "http://builder:8545" && method == "eth_getPendingBlock"
+        ));
+        assert!(matches!(
+            &feeds[1],
+            PendingBlockFeed::Polled {
+                request: PendingBlockRequest::TxpoolContent,
+                poll_rate_ms: 500,
+                ..
+            }
+        ));
+        assert!(matches!(
+            &feeds[2],
+            PendingBlockFeed::Polled { request, poll_rate_ms: 250, .. }
+                if *request == PendingBlockRequest::Block {
+                    method: "eth_getBlockByNumber".to_string(),
+                    params: Some(json!(["pending", true])),
+                }
+        ));
+        assert!(matches!(
+            &feeds[3],
+            PendingBlockFeed::Streamed { url } if url == "ws://geth:8546"
+        ));
+        assert!(matches!(
+            sources[3],
+            PendingBlockDataSource::WebSocket {
+                ttl_ms: 300_000,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_pending_staleness_config() {
+        let model_config: ModelConfig = serde_json::from_str(
+            r#"{"pending_staleness": {"max_age_ms": 3000, "on_stale": "flag"}}"#,
+        )
+        .unwrap();
+
+        let staleness = model_config.pending_staleness;
+        assert_eq!(staleness.max_age_ms, Some(3000));
+        assert_eq!(staleness.max_tip_lag, 1);
+        assert_eq!(staleness.on_stale, StalePendingPolicy::Flag);
+
+        let defaults = ModelConfig::default().pending_staleness;
+        assert_eq!(defaults.max_age_ms, None);
+        assert_eq!(defaults.on_stale, StalePendingPolicy::Refuse);
+    }
+
+    #[test]
+    fn test_built_in_chain_params() {
+        let chain_config = parse_chain_config(
+            r#"{"system": "base", "network": "sepolia", "json_rpc_url": "http://localhost:8545", "agents": []}"#,
+        );
+
+        let params = chain_config.chain_params().unwrap();
+        assert_eq!(params.chain_id, 84532);
+        assert_eq!(params.block_time_ms, 2000);
+        assert_eq!(params.blob_params, None);
+    }
+
+    #[test]
+    fn test_custom_chain_params() {
+        let chain_config = parse_chain_config(
+            r#"{
+                "system": "custom:bnb",
+                "network": "mainnet",
+                "json_rpc_url": "http://localhost:8545",
+                "chain": {"chain_id": 56, "block_time_ms": 750, "fee_market": "legacy"},
+                "blob_params": {"target_blobs_per_block": 3, "max_blobs_per_block": 6, "base_fee_update_fraction": 3338477},
+                "agents": []
+            }"#,
+        );
+
+        assert_eq!(chain_config.system, System::Custom("bnb".to_string()));
+
+        let params = chain_config.chain_params().unwrap();
+        assert_eq!(params.chain_id, 56);
+        assert_eq!(params.system_id, EVM_SYSTEM_ID);
+        assert_eq!(params.block_time_ms, 750);
+        assert_eq!(params.fee_market, FeeMarket::Legacy);
+        assert_eq!(params.blob_params.unwrap().max_blobs_per_block, 6);
+    }
+
+    #[test]
+    fn test_base_fee_params_overrides() {
+        let chain_config = parse_chain_config(
+            r#"{
+                "system": "base",
+                "network": "mainnet",
+                "json_rpc_url": "http://localhost:8545",
+                "base_fee_params": {"elasticity_multiplier": 2, "max_change_denominator": 50},
+                "base_fee_params_from_extra_data": false,
+                "agents": []
+            }"#,
+        );
+
+        let params = chain_config.chain_params().unwrap();
+        assert_eq!(
+            params.base_fee_params,
+            Some(BaseFeeParams {
+                elasticity_multiplier: 2,
+                max_change_denominator: 50,
+                min_base_fee: 0,
+            })
+        );
+        assert!(!params.base_fee_params_from_extra_data);
+
+        let defaults = parse_chain_config(
+            r#"{"system": "base", "network": "mainnet", "json_rpc_url": "http://localhost:8545", "agents": []}"#,
+        )
+        .chain_params()
+        .unwrap();
+        assert_eq!(defaults.base_fee_params, Some(BaseFeeParams::OP_STACK));
+        assert!(defaults.base_fee_params_from_extra_data);
+
+        for base_fee_params in [
+            r#"{"elasticity_multiplier": 0, "max_change_denominator": 50}"#,
+            r#"{"elasticity_multiplier": 2, "max_change_denominator": 0}"#,
+        ] {
+            let chain_config = parse_chain_config(&format!(
+                r#"{{"system": "base", "network": "mainnet", "json_rpc_url": "http://localhost:8545", "base_fee_params": {base_fee_params}, "agents": []}}"#
+            ));
+            assert!(chain_config.chain_params().is_err());
+        }
+    }
+
+    #[test]
+    fn test_unknown_chain_without_params() {
+        let chain_config = parse_chain_config(
+            r#"{"system": "custom:bnb", "network": "mainnet", "json_rpc_url": "http://localhost:8545", "agents": []}"#,
+        );
+
+        let err = chain_config.chain_params().unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("No built-in parameters for custom:bnb mainnet"));
+
+        // Names without the custom prefix must be built-in
+        let err = serde_json::from_str::<ChainConfig>(
+            r#"{"system": "bnb", "network": "mainnet", "json_rpc_url": "http://localhost:8545", "agents": []}"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("Unknown system \"bnb\""));
+    }
+}
@@ -1,45 +1,252 @@
+use crate::blocks::wei_to_gwei;
+use crate::config::Weighting;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
 pub type BlockDistribution = Vec<Bucket>;
 
+/// A pending block distribution with the time its transactions were fetched and the chain tip
+/// they were priced against
+#[derive(Debug, Clone)]
+pub struct PendingBlockSnapshot {
+    pub distribution: BlockDistribution,
+    pub fetched_at: DateTime<Utc>,
+    pub chain_tip: u64,
+}
+
 #[derive(Debug, Clone)]
 pub struct Bucket {
-    pub gwei: f64,
+    /// Lower bound of the bucket price in wei
+    pub wei: u128,
     pub count: u32,
+    /// Total gas of the transactions in this bucket
+    pub gas: u64,
+}
+
+impl Bucket {
+    /// The bucket price in gwei, for statistics that need floating point arithmetic
+    pub fn gwei(&self) -> f64 {
+        wei_to_gwei(self.wei).unwrap_or_default()
+    }
+
+    /// The weight of this bucket for distribution statistics
+    pub fn weight(&self, weighting: Weighting) -> f64 {
+        match weighting {
+            Weighting::Count => self.count as f64,
+            Weighting::Gas => self.gas as f64,
+        }
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, Default)]
+struct BucketTotals {
+    count: u32,
+    gas: u64,
+}
+
+/// Histogram of transaction prices, bucketed and ordered by integer wei.
+/// Histograms with the same bucket size can be merged to build statistics over several blocks.
+#[derive(Debug, Clone)]
 pub struct DistributionCreator {
-    pub buckets: Vec<Bucket>,
-    bucket_size: f64,
+    /// Totals keyed by the lower bound of each bucket in wei
+    buckets: BTreeMap<u128, BucketTotals>,
+    bucket_size_wei: u128,
 }
 
 impl DistributionCreator {
-    pub fn new(bucket_size: f64) -> Self {
+    pub fn new(bucket_size_wei: u128) -> Self {
         Self {
-            buckets: Vec::new(),
-            bucket_size,
+            buckets: BTreeMap::new(),
+            bucket_size_wei: bucket_size_wei.max(1),
+        }
+    }
+
+    /// Builds a histogram from an existing block distribution
+    pub fn from_distribution(distribution: &BlockDistribution, bucket_size_wei: u128) -> Self {
+        let mut creator = Self::new(bucket_size_wei);
+
+        for bucket in distribution {
+            creator.add_totals(
+                bucket.wei,
+                BucketTotals {
+                    count: bucket.count,
+                    gas: bucket.gas,
+                },
+            );
         }
+
+        creator
     }
 
-    pub fn add(&mut self, value: f64) {
-        // Calculate the rounding factor based on bucket_size
-        let decimal_places = (-self.bucket_size.log10().floor()) as i32;
-        let rounding_factor = 10.0f64.powi(decimal_places);
+    pub fn add(&mut self, wei: u128, gas: u64) {
+        self.add_totals(wei, BucketTotals { count: 1, gas });
+    }
 
-        let bucket_index =
-            (((value / self.bucket_size).floor() * self.bucket_size) * rounding_factor).round()
-                / rounding_factor;
+    /// Adds all buckets of another histogram into this one
+    pub fn merge(&mut self, other: &DistributionCreator) {
+        for (wei, totals) in other.buckets.iter() {
+            self.add_totals(*wei, *totals);
+        }
+    }
 
-        if let Some(pos) = self
-            .buckets
+    /// The buckets as a distribution sorted by ascending price
+    pub fn buckets(&self) -> BlockDistribution {
+        self.buckets
             .iter()
-            .position(|bucket| bucket.gwei == bucket_index)
-        {
-            self.buckets[pos].count += 1;
-        } else {
-            self.buckets.push(Bucket {
-                gwei: bucket_index,
+            .map(|(wei, totals)| Bucket {
+                wei: *wei,
+                count: totals.count,
+                gas: totals.gas,
+            })
+            .collect()
+    }
+
+    fn add_totals(&mut self, wei: u128, totals: BucketTotals) {
+        let bucket_index = wei / self.bucket_size_wei * self.bucket_size_wei;
+        let bucket = self.buckets.entry(bucket_index).or_default();
+        bucket.count += totals.count;
+        bucket.gas += totals.gas;
+    }
+}
+
+/// Combines several block distributions into a single distribution sorted by ascending price
+pub fn merge_distributions(distributions: &[BlockDistribution]) -> BlockDistribution {
+    let mut merged = DistributionCreator::new(1);
+
+    for distribution in distributions {
+        merged.merge(&DistributionCreator::from_distribution(distribution, 1));
+    }
+
+    merged.buckets()
+}
+
+/// The lowest bucket price at or below which `percentile` (0.0 - 1.0) of the transactions are priced.
+/// Expects a distribution sorted by ascending price.
+pub fn percentile_wei(distribution: &BlockDistribution, percentile: f64) -> Option<u128> {
+    let total: u64 = distribution.iter().map(|bucket| bucket.count as u64).sum();
+    let target = ((total as f64 * percentile).ceil() as u64).max(1);
+    let mut cumulative = 0;
+
+    distribution.iter().find_map(|bucket| {
+        cumulative += bucket.count as u64;
+        (cumulative >= target).then_some(bucket.wei)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_groups_by_bucket_and_sorts() {
+        let mut creator = DistributionCreator::new(1_000_000_000);
+
+        creator.add(25_000_000_000, 21_000);
+        creator.add(10_500_000_000, 50_000);
+        creator.add(10_000_000_000, 21_000);
+        creator.add(25_999_999_999, 100_000);
+
+        let buckets = creator.buckets();
+        assert_eq!(buckets.len(), 2);
+
+        assert_eq!(buckets[0].wei, 10_000_000_000);
+        assert_eq!(buckets[0].count, 2);
+        assert_eq!(buckets[0].gas, 71_000);
+
+        assert_eq!(buckets[1].wei, 25_000_000_000);
+        assert_eq!(buckets[1].count, 2);
+        assert_eq!(buckets[1].gas, 121_000);
+    }
+
+    #[test]
+    fn test_single_wei_buckets_are_exact() {
+        let mut creator = DistributionCreator::new(1);
+
+        creator.add(1_000_000_001, 21_000);
+        creator.add(1_000_000_000, 21_000);
+        creator.add(1_000_000_001, 21_000);
+
+        let buckets = creator.buckets();
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].wei, 1_000_000_000);
+        assert_eq!(buckets[0].count, 1);
+        assert_eq!(buckets[1].wei, 1_000_000_001);
+        assert_eq!(buckets[1].count, 2);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut first = DistributionCreator::new(1);
+        first.add(10_000_000_000, 21_000);
+        first.add(20_000_000_000, 21_000);
+
+        let mut second = DistributionCreator::new(1);
+        second.add(5_000_000_000, 21_000);
+        second.add(20_000_000_000, 42_000);
+
+        first.merge(&second);
+
+        let buckets = first.buckets();
+        assert_eq!(buckets.len(), 3);
+        assert_eq!(buckets[0].wei, 5_000_000_000);
+        assert_eq!(buckets[1].wei, 10_000_000_000);
+        assert_eq!(buckets[2].wei, 20_000_000_000);
+        assert_eq!(buckets[2].count, 2);
+        assert_eq!(buckets[2].gas, 63_000);
+    }
+
+    #[test]
+    fn test_merge_distributions() {
+        let first = vec![
+            Bucket {
+                wei: 10_000_000_000,
                 count: 1,
-            });
-        }
+                gas: 21_000,
+            },
+            Bucket {
+                wei: 12_500_000_000,
+                count: 2,
+                gas: 42_000,
+            },
+        ];
+        let second = vec![Bucket {
+            wei: 12_500_000_000,
+            count: 1,
+            gas: 21_000,
+        }];
+
+        let merged = merge_distributions(&[first, second]);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[1].wei, 12_500_000_000);
+        assert_eq!(merged[1].count, 3);
+        assert_eq!(merged[1].gas, 63_000);
+    }
+
+    #[test]
+    fn test_percentile_wei() {
+        let distribution = vec![
+            Bucket {
+                wei: 10,
+                count: 50,
+                gas: 0,
+            },
+            Bucket {
+                wei: 20,
+                count: 40,
+                gas: 0,
+            },
+            Bucket {
+                wei: 30,
+                count: 10,
+                gas: 0,
+            },
+        ];
+
+        assert_eq!(percentile_wei(&distribution, 0.0), Some(10));
+        assert_eq!(percentile_wei(&distribution, 0.5), Some(10));
+        assert_eq!(percentile_wei(&distribution, 0.51), Some(20));
+        assert_eq!(percentile_wei(&distribution, 0.9), Some(20));
+        assert_eq!(percentile_wei(&distribution, 0.99), Some(30));
+        assert_eq!(percentile_wei(&vec![], 0.5), None);
     }
 }
@@ -0,0 +1,117 @@
+use crate::distribution::BlockDistribution;
+use crate::rpc::BlockHeader;
+use chrono::Timelike;
+
+/// Observable conditions of a block, kept as a rolling history so models can
+/// relate them to the minimum price of the block that followed
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockFeatures {
+    pub block_number: u64,
+    /// `gas_used / gas_limit` of the block
+    pub utilization: f64,
+    pub base_fee_per_gas: Option<u64>,
+    /// Relative base fee change from the previous recorded block, 0.0 without base fees
+    pub base_fee_change: f64,
+    /// Number of transactions in the pending block when this block arrived
+    pub pending_tx_count: u32,
+    /// Seconds since midnight UTC of the block timestamp
+    pub seconds_of_day: u32,
+    /// Lowest effective price of the block in wei, after distribution filters
+    pub block_min: Option<u128>,
+}
+
+impl BlockFeatures {
+    pub fn new(
+        header: &BlockHeader,
+        previous: Option<&BlockFeatures>,
+        pending_block_distribution: Option<&BlockDistribution>,
+        block_min: Option<u128>,
+    ) -> Self {
+        let utilization = if header.gas_limit > 0 {
+            header.gas_used as f64 / header.gas_limit as f64
+        } else {
+            0.0
+        };
+
+        let base_fee_change = match (
+            header.base_fee_per_gas,
+            previous.and_then(|previous| previous.base_fee_per_gas),
+        ) {
+            (Some(base_fee), Some(previous_base_fee)) if previous_base_fee > 0 => {
+                (base_fee as f64 - previous_base_fee as f64) / previous_base_fee as f64
+            }
+            _ => 0.0,
+        };
+
+        let pending_tx_count = pending_block_distribution
+            .map(|distribution| distribution.iter().map(|bucket| bucket.count).sum())
+            .unwrap_or(0);
+
+        Self {
+            block_number: header.number,
+            utilization,
+            base_fee_per_gas: header.base_fee_per_gas,
+            base_fee_change,
+            pending_tx_count,
+            seconds_of_day: header.timestamp.num_seconds_from_midnight(),
+            block_min,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distribution::Bucket;
+    use chrono::{TimeZone, Utc};
+
+    fn header(number: u64, gas_used: u64, base_fee_per_gas: Option<u64>) -> BlockHeader {
+        BlockHeader {
+            number,
+            // 2023-11-14 22:13:20 UTC
+            timestamp: Utc.timestamp_opt(1_700_000_000, 0).unwrap(),
+            gas_limit: 30_000_000,
+            gas_used,
+            base_fee_per_gas,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            extra_data: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_block_features() {
+        let first = BlockFeatures::new(&header(1, 15_000_000, Some(100)), None, None, Some(120));
+
+        assert_eq!(first.utilization, 0.5);
+        assert_eq!(first.base_fee_change, 0.0);
+        assert_eq!(first.pending_tx_count, 0);
+        assert_eq!(first.seconds_of_day, 22 * 3600 + 13 * 60 + 20);
+        assert_eq!(first.block_min, Some(120));
+
+        let pending = vec![
+            Bucket {
+                wei: 100,
+                count: 3,
+                gas: 63_000,
+            },
+            Bucket {
+                wei: 200,
+                count: 2,
+                gas: 42_000,
+            },
+        ];
+
+        let second = BlockFeatures::new(
+            &header(2, 30_000_000, Some(112)),
+            Some(&first),
+            Some(&pending),
+            None,
+        );
+
+        assert_eq!(second.utilization, 1.0);
+        assert!((second.base_fee_change - 0.12).abs() < 1e-12);
+        assert_eq!(second.pending_tx_count, 5);
+        assert_eq!(second.block_min, None);
+    }
+}
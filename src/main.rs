@@ -6,27 +6,37 @@ use config::{ChainConfig, Cli, Commands};
 use dotenv::dotenv;
 use interrupts::{on_panic, on_sigterm};
 use logs::init_logs;
+use metrics::init_metrics;
 use server::start_server_without_state;
+use signer::load_signers;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use tokio::task::JoinSet;
 use tracing::{error, info};
-use utils::generate_key_pair;
+use utils::{decode_payload, generate_key_pair, verify_payload};
 
 mod agent;
+mod block_time;
 mod blocks;
 mod chain;
 mod config;
 mod constants;
 mod distribution;
+mod features;
 mod interrupts;
 mod logs;
+mod mempool;
+mod metrics;
 mod models;
+mod op_stack;
+mod poll_schedule;
 mod publish;
 mod rpc;
 mod server;
+mod signer;
 mod types;
 mod utils;
+mod verify;
 
 #[ntex::main]
 async fn main() -> Result<()> {
@@ -36,7 +46,9 @@ async fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::GenerateKeys => generate_key_pair(),
+        Commands::GenerateKeys(args) => generate_key_pair(&args),
+        Commands::Decode { payload } => decode_payload(&payload),
+        Commands::Verify(args) => verify_payload(&args),
         Commands::Start(config) => {
             let chain_configs: Vec<ChainConfig> =
                 serde_json::from_str(&config.chains).context("Loading Chain Configurations")?;
@@ -45,22 +57,31 @@ async fn main() -> Result<()> {
                 return Err(anyhow!("No chains configured"));
             }
 
+            for chain_config in chain_configs.iter() {
+                chain_config.chain_params()?;
+            }
+
             let server_address = config.server_address;
 
+            // Keystores are decrypted up front so password prompts do not interleave between chains
+            let signers = load_signers(&chain_configs)?;
+
+            init_metrics().context("Initializing metrics")?;
+
             // log panics
             on_panic(|panic_info| error!(error = %panic_info, "Panic detected!!"));
 
             let agents_handles = Arc::new(Mutex::new(JoinSet::new()));
             let agents_handles_clone = agents_handles.clone();
 
-            for chain_config in chain_configs {
+            for (chain_config, signers) in chain_configs.into_iter().zip(signers) {
                 let config_clone = config.clone();
 
                 agents_handles_clone.lock().await.spawn(async move {
                     let system = chain_config.system.clone();
                     let network = chain_config.network.clone();
 
-                    if let Err(e) = start_agents(chain_config, &config_clone).await {
+                    if let Err(e) = start_agents(chain_config, signers, &config_clone).await {
                         error!(
                             "Failed to start agent for system: {}, network: {}, error: {}",
                             &system,
@@ -0,0 +1,456 @@
+use crate::rpc::{parse_pending_transaction, Transaction};
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Utc};
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use tokio::sync::RwLock;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tracing::{debug, info};
+
+/// Upper bound on the transactions kept, the oldest are evicted first
+const MAX_NUM_MEMPOOL_TRANSACTIONS: usize = 100_000;
+
+/// Incremental model of the pending transactions seen by a node.
+/// Transactions are evicted when they or a later transaction of their sender are included in a
+/// block, when they are replaced by a transaction with the same sender and nonce, when they have
+/// been pending for longer than the TTL, eg. after being dropped by the node, or when the mempool
+/// is full.
+#[derive(Debug)]
+pub struct Mempool {
+    ttl: Duration,
+    transactions: HashMap<String, PendingTransaction>,
+    /// Hashes of pending transactions by sender and nonce
+    senders: HashMap<String, BTreeMap<u64, String>>,
+    /// Transaction hashes in the order they were first seen, oldest first. Entries of removed
+    /// transactions stay here until they expire, and are told apart from a later arrival of the
+    /// same hash by their timestamp.
+    arrivals: VecDeque<(String, DateTime<Utc>)>,
+    /// When the latest pending transaction was seen
+    updated_at: Option<DateTime<Utc>>,
+    /// Incremented whenever transactions are added or removed
+    revision: u64,
+}
+
+#[derive(Debug)]
+struct PendingTransaction {
+    transaction: Transaction,
+    seen: DateTime<Utc>,
+}
+
+impl Mempool {
+    pub fn new(ttl_ms: u64) -> Self {
+        Self {
+            ttl: Duration::milliseconds(ttl_ms as i64),
+            transactions: HashMap::new(),
+            senders: HashMap::new(),
+            arrivals: VecDeque::new(),
+            updated_at: None,
+            revision: 0,
+        }
+    }
+
+    /// Adds a pending transaction seen at `now`, replacing a pending transaction with the same
+    /// sender and nonce. Transactions already in the mempool are ignored.
+    pub fn insert(&mut self, transaction: Transaction, now: DateTime<Utc>) {
+        self.updated_at = Some(now);
+
+        if self.transactions.contains_key(&transaction.hash) {
+            return;
+        }
+
+        if let Some((from, nonce)) = sender_nonce(&transaction) {
+            let nonces = self.senders.entry(from.to_string()).or_default();
+            if let Some(replaced) = nonces.insert(nonce, transaction.hash.clone()) {
+                self.transactions.remove(&replaced);
+            }
+        }
+
+        self.revision += 1;
+        self.arrivals.push_back((transaction.hash.clone(), now));
+        self.transactions.insert(
+            transaction.hash.clone(),
+            PendingTransaction {
+                transaction,
+                seen: now,
+            },
+        );
+
+        while self.transactions.len() > MAX_NUM_MEMPOOL_TRANSACTIONS {
+            let Some((hash, seen)) = self.arrivals.pop_front() else {
+                break;
+            };
+            self.remove_if_seen_at(&hash, seen);
+        }
+    }
+
+    /// Removes the transactions included in a new block, along with pending transactions of
+    /// their senders at or below the included nonces, which can no longer be included
+    pub fn remove_included<'a>(&mut self, transactions: impl IntoIterator<Item = &'a Transaction>) {
+        for transaction in transactions {
+            self.remove(&transaction.hash);
+
+            let Some((from, nonce)) = sender_nonce(transaction) else {
+                continue;
+            };
+            let Some(nonces) = self.senders.get(from) else {
+                continue;
+            };
+
+            let superseded: Vec<String> = nonces
+                .range(..=nonce)
+                .map(|(_, hash)| hash.clone())
+                .collect();
+            for hash in superseded {
+                self.remove(&hash);
+            }
+        }
+    }
+
+    /// Removes transactions first seen more than the TTL before `now`
+    pub fn evict_expired(&mut self, now: DateTime<Utc>) {
+        while self
+            .arrivals
+            .front()
+            .is_some_and(|(_, seen)| now - *seen > self.ttl)
+        {
+            if let Some((hash, seen)) = self.arrivals.pop_front() {
+                self.remove_if_seen_at(&hash, seen);
+            }
+        }
+    }
+
+    pub fn transactions(&self) -> Vec<Transaction> {
+        self.transactions
+            .values()
+            .map(|pending| pending.transaction.clone())
+            .collect()
+    }
+
+    pub fn updated_at(&self) -> Option<DateTime<Utc>> {
+        self.updated_at
+    }
+
+    /// Changes whenever the pending transactions change, so anything derived from them can be
+    /// reused until then
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    fn remove(&mut self, hash: &str) {
+        let Some(pending) = self.transactions.remove(hash) else {
+            return;
+        };
+        self.revision += 1;
+
+        let Some((from, nonce)) = sender_nonce(&pending.transaction) else {
+            return;
+        };
+        let Some(nonces) = self.senders.get_mut(from) else {
+            return;
+        };
+
+        if nonces
+            .get(&nonce)
+            .is_some_and(|pending_hash| pending_hash == hash)
+        {
+            nonces.remove(&nonce);
+        }
+        if nonces.is_empty() {
+            self.senders.remove(from);
+        }
+    }
+
+    /// Removes a transaction only if it is the arrival seen at `seen`, not a later one
+    fn remove_if_seen_at(&mut self, hash: &str, seen: DateTime<Utc>) {
+        if self
+            .transactions
+            .get(hash)
+            .is_some_and(|pending| pending.seen == seen)
+        {
+            self.remove(hash);
+        }
+    }
+}
+
+fn sender_nonce(transaction: &Transaction) -> Option<(&str, u64)> {
+    Some((transaction.from.as_deref()?, transaction.nonce?))
+}
+
+/// Subscribes to full pending transactions with `eth_subscribe("newPendingTransactions", true)`
+/// over WebSocket and adds them to the mempool. Returns when the connection fails or closes.
+pub async fn stream_pending_transactions(url: &str, mempool: &RwLock<Mempool>) -> Result<()> {
+    let (mut socket, _) = connect_async(url).await?;
+
+    let subscribe = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "eth_subscribe",
+        "params": ["newPendingTransactions", true]
+    });
+    socket.send(Message::text(subscribe.to_string())).await?;
+
+    while let Some(message) = socket.next().await {
+        let text = match message? {
+            Message::Text(text) => text,
+            Message::Close(frame) => {
+                return Err(anyhow!("WebSocket closed by the node: {frame:?}"));
+            }
+            _ => continue,
+        };
+
+        let value: Value = serde_json::from_str(&text)?;
+
+        if let Some(error) = value.get("error") {
+            return Err(anyhow!("Pending transaction subscription failed: {error}"));
+        }
+
+        if let Some(subscription) = value.get("result") {
+            info!(%subscription, "Subscribed to pending transactions");
+            continue;
+        }
+
+        let transaction = &value["params"]["result"];
+
+        if transaction.is_string() {
+            return Err(anyhow!(
+                "Node sent pending transaction hashes, full pending transactions are not supported"
+            ));
+        }
+
+        match parse_pending_transaction(transaction) {
+            Ok(transaction) => mempool.write().await.insert(transaction, Utc::now()),
+            Err(e) => debug!(error = %e, "Skipping pending transaction"),
+        }
+    }
+
+    Err(anyhow!("WebSocket connection closed"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocks::calc_fee_wei;
+
+    fn transaction(hash: &str) -> Transaction {
+        Transaction {
+            hash: hash.to_string(),
+            tx_type: Some(2),
+            from: None,
+            nonce: None,
+            to: None,
+            gas_price: None,
+            max_fee_per_gas: Some(20_000_000_000),
+            max_priority_fee_per_gas: Some(1_000_000_000),
+            gas: Some(21_000),
+            gas_used: None,
+            max_fee_per_blob_gas: None,
+            blob_count: 0,
+            input: Default::default(),
+        }
+    }
+
+    fn sent(hash: &str, from: &str, nonce: u64, max_priority_fee_per_gas: u128) -> Transaction {
+        Transaction {
+            from: Some(from.to_string()),
+            nonce: Some(nonce),
+            max_priority_fee_per_gas: Some(max_priority_fee_per_gas),
+            ..transaction(hash)
+        }
+    }
+
+    fn hashes(mempool: &Mempool) -> Vec<String> {
+        let mut hashes: Vec<String> = mempool
+            .transactions()
+            .into_iter()
+            .map(|transaction| transaction.hash)
+            .collect();
+        hashes.sort();
+        hashes
+    }
+
+    #[test]
+    fn test_inclusion_eviction() {
+        let now = Utc::now();
+        let mut mempool = Mempool::new(60_000);
+        assert!(mempool.is_empty());
+
+        mempool.insert(transaction("0x1"), now);
+        mempool.insert(transaction("0x2"), now);
+        mempool.insert(transaction("0x3"), now);
+        // Announced again
+        mempool.insert(transaction("0x1"), now);
+        assert_eq!(mempool.len(), 3);
+
+        mempool.remove_included(&[transaction("0x1"), transaction("0x3"), transaction("0x4")]);
+        assert_eq!(hashes(&mempool), vec!["0x2"]);
+    }
+
+    #[test]
+    fn test_replacement() {
+        let now = Utc::now();
+        let mut mempool = Mempool::new(60_000);
+
+        mempool.insert(sent("0x1", "0xaa", 7, 1_000_000_000), now);
+        mempool.insert(sent("0x2", "0xaa", 8, 1_000_000_000), now);
+        mempool.insert(sent("0x3", "0xbb", 7, 1_000_000_000), now);
+
+        // Speeding up nonce 7 of 0xaa replaces the transaction it was sent for
+        mempool.insert(sent("0x4", "0xaa", 7, 3_000_000_000), now);
+        assert_eq!(hashes(&mempool), vec!["0x2", "0x3", "0x4"]);
+        let replacement = &mempool.transactions["0x4"].transaction;
+        assert_eq!(replacement.max_priority_fee_per_gas, Some(3_000_000_000));
+
+        // Nonce 8 mined under another hash makes nonce 7 unincludable too
+        mempool.remove_included(&[sent("0x5", "0xaa", 8, 2_000_000_000)]);
+        assert_eq!(hashes(&mempool), vec!["0x3"]);
+        assert!(!mempool.senders.contains_key("0xaa"));
+    }
+
+    #[test]
+    fn test_ttl_eviction() {
+        let start = Utc::now();
+        let mut mempool = Mempool::new(60_000);
+
+        mempool.insert(transaction("0x1"), start);
+        mempool.insert(transaction("0x2"), start + Duration::seconds(30));
+        mempool.remove_included(&[transaction("0x2")]);
+        mempool.insert(transaction("0x3"), start + Duration::seconds(45));
+        // Re-broadcast after its block was reorged out
+        mempool.insert(transaction("0x2"), start + Duration::seconds(50));
+
+        mempool.evict_expired(start + Duration::seconds(60));
+        assert_eq!(hashes(&mempool), vec!["0x1", "0x2", "0x3"]);
+
+        // The first arrival of 0x2 expires without evicting its re-broadcast
+        mempool.evict_expired(start + Duration::seconds(91));
+        assert_eq!(hashes(&mempool), vec!["0x2", "0x3"]);
+
+        mempool.evict_expired(start + Duration::seconds(106));
+        assert_eq!(hashes(&mempool), vec!["0x2"]);
+
+        mempool.evict_expired(start + Duration::seconds(111));
+        assert!(mempool.is_empty());
+        assert!(mempool.arrivals.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stream_pending_transactions() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        let node = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+
+            let subscribe = socket.next().await.unwrap().unwrap();
+            let subscribe: Value = serde_json::from_str(subscribe.to_text().unwrap()).unwrap();
+            assert_eq!(subscribe["method"], "eth_subscribe");
+            assert_eq!(subscribe["params"], json!(["newPendingTransactions", true]));
+
+            let notification = |result: Value| {
+                Message::text(
+                    json!({
+                        "jsonrpc": "2.0",
+                        "method": "eth_subscription",
+                        "params": {"subscription": "0x1", "result": result}
+                    })
+                    .to_string(),
+                )
+            };
+
+            for message in [
+                Message::text(json!({"jsonrpc": "2.0", "id": 1, "result": "0x1"}).to_string()),
+                // Geth reports the fee cap of pending dynamic fee transactions as their gas price
+                notification(json!({
+                    "hash": "0xa",
+                    "type": "0x2",
+                    "gas": "0x5208",
+                    "gasPrice": "0x4a817c800",
+                    "maxFeePerGas": "0x4a817c800",
+                    "maxPriorityFeePerGas": "0x3b9aca00"
+                })),
+                // Without gas pricing
+                notification(json!({"hash": "0xb"})),
+                notification(json!({"hash": "0xc", "gasPrice": "0x3b9aca00"})),
+            ] {
+                socket.send(message).await.unwrap();
+            }
+            socket.close(None).await.unwrap();
+        });
+
+        let mempool = RwLock::new(Mempool::new(60_000));
+        assert!(stream_pending_transactions(&url, &mempool).await.is_err());
+        node.await.unwrap();
+
+        let mempool = mempool.read().await;
+        assert_eq!(hashes(&mempool), vec!["0xa", "0xc"]);
+
+        let dynamic_fee = &mempool.transactions["0xa"].transaction;
+        assert_eq!(dynamic_fee.gas_price, None);
+        assert_eq!(
+            calc_fee_wei(
+                &dynamic_fee.gas_price,
+                &dynamic_fee.max_fee_per_gas,
+                &dynamic_fee.max_priority_fee_per_gas,
+                &Some(10_000_000_000),
+            )
+            .unwrap(),
+            11_000_000_000
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_pending_transaction_hashes() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut socket = tokio_tungstenite::accept_async(stream).await.unwrap();
+            socket.next().await.unwrap().unwrap();
+
+            // A node ignoring the full transactions flag
+            let notification = json!({
+                "jsonrpc": "2.0",
+                "method": "eth_subscription",
+                "params": {"subscription": "0x1", "result": "0xa"}
+            });
+            socket
+                .send(Message::text(notification.to_string()))
+                .await
+                .unwrap();
+            // Keep the connection open until the client gives up
+            while socket.next().await.is_some() {}
+        });
+
+        let mempool = RwLock::new(Mempool::new(60_000));
+        let error = stream_pending_transactions(&url, &mempool)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("not supported"));
+        assert!(mempool.read().await.is_empty());
+    }
+
+    #[test]
+    fn test_capacity_eviction() {
+        let now = Utc::now();
+        let mut mempool = Mempool::new(60_000);
+
+        for i in 0..=MAX_NUM_MEMPOOL_TRANSACTIONS {
+            mempool.insert(transaction(&format!("0x{i:x}")), now);
+        }
+
+        assert_eq!(mempool.len(), MAX_NUM_MEMPOOL_TRANSACTIONS);
+        assert!(!mempool.transactions.contains_key("0x0"));
+        assert!(mempool.transactions.contains_key("0x1"));
+    }
+}
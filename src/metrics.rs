@@ -0,0 +1,132 @@
+use anyhow::{anyhow, Result};
+use opentelemetry::global;
+use opentelemetry::metrics::{Counter, Gauge, Histogram};
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::metrics::SdkMeterProvider;
+use prometheus::{Encoder, Registry, TextEncoder};
+use std::sync::OnceLock;
+
+const METER_NAME: &str = "gas_agent";
+
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+static CHAIN_METRICS: OnceLock<ChainMetrics> = OnceLock::new();
+static SELF_TUNING_METRICS: OnceLock<SelfTuningMetrics> = OnceLock::new();
+
+/// Installs a Prometheus exporter as the global meter provider.
+/// Until this is called all instruments are no-ops.
+pub fn init_metrics() -> Result<()> {
+    let registry = Registry::new();
+
+    let exporter = opentelemetry_prometheus::exporter()
+        .with_registry(registry.clone())
+        .build()?;
+
+    let provider = SdkMeterProvider::builder().with_reader(exporter).build();
+    global::set_meter_provider(provider);
+
+    REGISTRY
+        .set(registry)
+        .map_err(|_| anyhow!("Metrics already initialized"))
+}
+
+/// Renders all metrics in the Prometheus text format
+pub fn encode_metrics() -> Result<String> {
+    let Some(registry) = REGISTRY.get() else {
+        return Ok(String::new());
+    };
+
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&registry.gather(), &mut buffer)?;
+
+    Ok(String::from_utf8(buffer)?)
+}
+
+pub struct ChainMetrics {
+    block_time_ms: Gauge<u64>,
+    block_detection_latency_ms: Histogram<f64>,
+    block_polls: Counter<u64>,
+}
+
+impl ChainMetrics {
+    pub fn get() -> &'static Self {
+        CHAIN_METRICS.get_or_init(|| {
+            let meter = global::meter(METER_NAME);
+
+            Self {
+                block_time_ms: meter
+                    .u64_gauge("block_time_ms")
+                    .with_description("Measured time between blocks in milliseconds")
+                    .init(),
+                block_detection_latency_ms: meter
+                    .f64_histogram("block_detection_latency_ms")
+                    .with_description(
+                        "Time from a block's timestamp to the agent receiving it in milliseconds",
+                    )
+                    .init(),
+                block_polls: meter
+                    .u64_counter("block_polls")
+                    .with_description("Requests made for the latest block")
+                    .init(),
+            }
+        })
+    }
+
+    pub fn record_block_time(&self, labels: &[KeyValue], block_time_ms: u64) {
+        self.block_time_ms.record(block_time_ms, labels);
+    }
+
+    pub fn record_block_poll(&self, labels: &[KeyValue]) {
+        self.block_polls.add(1, labels);
+    }
+
+    pub fn record_block_detection_latency(&self, labels: &[KeyValue], latency_ms: u64) {
+        self.block_detection_latency_ms
+            .record(latency_ms as f64, labels);
+    }
+}
+
+pub struct SelfTuningMetrics {
+    premium: Gauge<f64>,
+    inclusion_rate: Gauge<f64>,
+    tracked_predictions: Gauge<u64>,
+}
+
+impl SelfTuningMetrics {
+    pub fn get() -> &'static Self {
+        SELF_TUNING_METRICS.get_or_init(|| {
+            let meter = global::meter(METER_NAME);
+
+            Self {
+                premium: meter
+                    .f64_gauge("self_tuning_premium")
+                    .with_description("Premium applied to the base model prediction")
+                    .init(),
+                inclusion_rate: meter
+                    .f64_gauge("self_tuning_inclusion_rate")
+                    .with_description(
+                        "Smoothed rate of predictions at or above the block window minimum",
+                    )
+                    .init(),
+                tracked_predictions: meter
+                    .u64_gauge("self_tuning_tracked_predictions")
+                    .with_description("Predictions waiting for their block window to close")
+                    .init(),
+            }
+        })
+    }
+
+    pub fn record(
+        &self,
+        labels: &[KeyValue],
+        premium: f64,
+        inclusion_rate: Option<f64>,
+        tracked_predictions: usize,
+    ) {
+        self.premium.record(premium, labels);
+        if let Some(inclusion_rate) = inclusion_rate {
+            self.inclusion_rate.record(inclusion_rate, labels);
+        }
+        self.tracked_predictions
+            .record(tracked_predictions as u64, labels);
+    }
+}
@@ -1,10 +1,6 @@
 use crate::models::{FromBlock, ModelError, Prediction};
 use crate::types::Settlement;
-use crate::{distribution::BlockDistribution, utils::round_to_9_places};
-use rust_decimal::{
-    prelude::{FromPrimitive, ToPrimitive},
-    Decimal,
-};
+use crate::{blocks::gwei_to_wei, distribution::BlockDistribution};
 
 /*
 Adaptive Threshold Method
@@ -38,12 +34,8 @@ pub fn get_prediction_adaptive_threshold(
 
         let min_price = block
             .iter()
-            .min_by(|a, b| {
-                a.gwei
-                    .partial_cmp(&b.gwei)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            })
-            .map(|bucket| bucket.gwei)
+            .min_by_key(|bucket| bucket.wei)
+            .map(|bucket| bucket.gwei())
             .unwrap_or(0.0);
 
         min_included_prices.push(min_price);
@@ -81,14 +73,9 @@ pub fn get_prediction_adaptive_threshold(
     let premium_factor = 1.0 + (std_dev / base_price).min(0.5);
 
     let predicted_price = base_price * premium_factor;
-    let predicted_price = Decimal::from_f64(predicted_price)
-        .unwrap()
-        .round_dp(9)
-        .to_f64()
-        .unwrap();
 
     Ok((
-        round_to_9_places(predicted_price),
+        gwei_to_wei(predicted_price),
         Settlement::Fast,
         latest_block + 1,
     ))
@@ -5,9 +5,9 @@ This approach analyzes the cumulative distribution function (CDF) of gas prices
 How it works: This algorithm analyzes how gas prices are distributed in the most recent block, constructing a cumulative distribution function. It then identifies the "sweet spot" where the rate of change in the CDF decreases significantly. This is often where many transactions are being included, representing an efficient gas price.
 */
 
+use crate::distribution::BlockDistribution;
 use crate::models::{FromBlock, ModelError, Prediction};
 use crate::types::Settlement;
-use crate::{distribution::BlockDistribution, utils::round_to_9_places};
 
 pub fn get_prediction_distribution(
     block_distributions: &[BlockDistribution],
@@ -30,11 +30,7 @@ pub fn get_prediction_distribution(
 
     // Sort buckets by gas price
     let mut sorted_buckets = latest_block_distribution.clone();
-    sorted_buckets.sort_by(|a, b| {
-        a.gwei
-            .partial_cmp(&b.gwei)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
+    sorted_buckets.sort_by_key(|bucket| bucket.wei);
 
     // Calculate cumulative distribution function (CDF)
     let total_txs: u32 = sorted_buckets.iter().map(|bucket| bucket.count).sum();
@@ -44,11 +40,11 @@ pub fn get_prediction_distribution(
     for bucket in &sorted_buckets {
         cumulative_count += bucket.count;
         let cumulative_percent = cumulative_count as f64 / total_txs as f64;
-        cdf.push((bucket.gwei, cumulative_percent));
+        cdf.push((bucket.wei, cumulative_percent));
     }
 
     // Find the "sweet spot" - where the rate of increase in the CDF slows down
-    let mut sweet_spot = sorted_buckets[0].gwei;
+    let mut sweet_spot = sorted_buckets[0].wei;
     let mut max_derivative_change = 0.0;
 
     // Need at least 3 points to calculate derivatives
@@ -59,8 +55,9 @@ pub fn get_prediction_distribution(
                 continue;
             }
 
-            let prev_derivative = (cdf[i].1 - cdf[i - 1].1) / (cdf[i].0 - cdf[i - 1].0);
-            let next_derivative = (cdf[i + 1].1 - cdf[i].1) / (cdf[i + 1].0 - cdf[i].0);
+            // Price differences are exact in wei, only the ratio needs floating point
+            let prev_derivative = (cdf[i].1 - cdf[i - 1].1) / (cdf[i].0 - cdf[i - 1].0) as f64;
+            let next_derivative = (cdf[i + 1].1 - cdf[i].1) / (cdf[i + 1].0 - cdf[i].0) as f64;
             let derivative_change = prev_derivative - next_derivative;
 
             if derivative_change > max_derivative_change {
@@ -71,15 +68,11 @@ pub fn get_prediction_distribution(
     } else {
         // Not enough points, use the median
         let mid_index = sorted_buckets.len() / 2;
-        sweet_spot = sorted_buckets[mid_index].gwei;
+        sweet_spot = sorted_buckets[mid_index].wei;
     }
 
-    // Apply a small premium to ensure higher probability of inclusion
-    let predicted_price = sweet_spot * 1.1;
+    // Apply a small premium (10%) to ensure higher probability of inclusion
+    let predicted_price = sweet_spot * 11 / 10;
 
-    Ok((
-        round_to_9_places(predicted_price),
-        Settlement::Fast,
-        latest_block + 1,
-    ))
+    Ok((predicted_price, Settlement::Fast, latest_block + 1))
 }
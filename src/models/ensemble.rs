@@ -0,0 +1,243 @@
+/*
+Ensemble Model
+This approach runs a configured set of base models over the same block distributions and combines their predictions.
+
+How it works: Each base model has its own failure modes (LastMin is noisy, TimeSeries overshoots on spikes, DistributionAnalysis only looks at one block). The ensemble runs every configured member, skips members that cannot produce a prediction, and combines the remaining predictions by weighted median, a weighted quantile, or the maximum of the members. Each member's contribution is logged at debug level.
+*/
+
+use crate::config::{EnsembleCombine, EnsembleConfig, EnsembleMember};
+use crate::models::{FromBlock, ModelError, Prediction};
+use crate::types::{ModelKind, Settlement};
+use tracing::debug;
+
+pub type MemberPrediction<'a> = (
+    &'a EnsembleMember,
+    Result<(Prediction, Settlement, FromBlock), ModelError>,
+);
+
+/// Checks an ensemble configuration before any member is run
+pub fn validate_ensemble_config(config: &EnsembleConfig) -> Result<(), ModelError> {
+    if config.members.is_empty() {
+        return Err(ModelError::invalid_config(
+            "Ensemble model requires at least one member",
+        ));
+    }
+
+    for member in &config.members {
+        if member.model == ModelKind::Ensemble {
+            return Err(ModelError::invalid_config(
+                "Ensemble model members cannot be ensembles",
+            ));
+        }
+
+        if !member.weight.is_finite() || member.weight <= 0.0 {
+            return Err(ModelError::invalid_config(format!(
+                "Ensemble member {} must have a positive weight",
+                member.model
+            )));
+        }
+    }
+
+    if let EnsembleCombine::Quantile { q } = config.combine {
+        if !(0.0..=1.0).contains(&q) {
+            return Err(ModelError::invalid_config(
+                "Ensemble quantile must be between 0.0 and 1.0",
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn get_prediction_ensemble(
+    config: &EnsembleConfig,
+    member_predictions: Vec<MemberPrediction>,
+    latest_block: u64,
+) -> Result<(Prediction, Settlement, FromBlock), ModelError> {
+    let mut predictions: Vec<(Prediction, f64)> = Vec::with_capacity(member_predictions.len());
+
+    for (member, result) in member_predictions {
+        match result {
+            Ok((price, settlement, from_block)) => {
+                debug!(
+                    member = %member.model,
+                    weight = member.weight,
+                    price = %price,
+                    settlement = %settlement,
+                    from_block,
+                    "Ensemble member prediction"
+                );
+                predictions.push((price, member.weight));
+            }
+            Err(e) => {
+                debug!(member = %member.model, error = %e, "Ensemble member skipped");
+            }
+        }
+    }
+
+    if predictions.is_empty() {
+        return Err(ModelError::insufficient_data(
+            "Ensemble model requires at least one member prediction",
+        ));
+    }
+
+    let price = match config.combine {
+        EnsembleCombine::WeightedMedian => weighted_quantile(&mut predictions, 0.5),
+        EnsembleCombine::Quantile { q } => weighted_quantile(&mut predictions, q),
+        EnsembleCombine::Max => predictions
+            .iter()
+            .map(|(price, _)| *price)
+            .max()
+            .unwrap_or(0),
+    };
+
+    debug!(
+        combine = ?config.combine,
+        members = predictions.len(),
+        price = %price,
+        "Ensemble prediction"
+    );
+
+    Ok((price, Settlement::Fast, latest_block + 1))
+}
+
+/// The lowest prediction whose cumulative weight reaches `q` of the total weight
+fn weighted_quantile(predictions: &mut [(Prediction, f64)], q: f64) -> Prediction {
+    predictions.sort_by_key(|(price, _)| *price);
+
+    let total_weight: f64 = predictions.iter().map(|(_, weight)| *weight).sum();
+    let target_weight = total_weight * q;
+    let mut cumulative_weight = 0.0;
+
+    for (price, weight) in predictions.iter() {
+        cumulative_weight += weight;
+        if cumulative_weight >= target_weight {
+            return *price;
+        }
+    }
+
+    predictions.last().map(|(price, _)| *price).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(model: ModelKind, weight: f64) -> EnsembleMember {
+        EnsembleMember { model, weight }
+    }
+
+    fn ensemble(members: Vec<EnsembleMember>, combine: EnsembleCombine) -> EnsembleConfig {
+        EnsembleConfig { members, combine }
+    }
+
+    #[test]
+    fn test_weighted_median() {
+        let config = ensemble(
+            vec![
+                member(ModelKind::LastMin, 1.0),
+                member(ModelKind::Percentile, 3.0),
+                member(ModelKind::TimeSeries, 1.0),
+            ],
+            EnsembleCombine::WeightedMedian,
+        );
+
+        let predictions = vec![
+            (&config.members[0], Ok((10, Settlement::Fast, 101))),
+            (&config.members[1], Ok((20, Settlement::Fast, 101))),
+            (&config.members[2], Ok((100, Settlement::Fast, 101))),
+        ];
+
+        let (price, settlement, from_block) =
+            get_prediction_ensemble(&config, predictions, 100).unwrap();
+
+        // Percentile carries 3/5 of the weight so it is the weighted median
+        assert_eq!(price, 20);
+        assert_eq!(settlement, Settlement::Fast);
+        assert_eq!(from_block, 101);
+    }
+
+    #[test]
+    fn test_quantile_and_max() {
+        let members = vec![
+            member(ModelKind::LastMin, 1.0),
+            member(ModelKind::Percentile, 1.0),
+            member(ModelKind::TimeSeries, 1.0),
+            member(ModelKind::MovingAverage, 1.0),
+        ];
+        let prices = [40, 10, 30, 20];
+
+        let config = ensemble(members.clone(), EnsembleCombine::Quantile { q: 0.75 });
+        let predictions = config
+            .members
+            .iter()
+            .zip(prices)
+            .map(|(member, price)| (member, Ok((price, Settlement::Fast, 101))))
+            .collect();
+        let (price, _, _) = get_prediction_ensemble(&config, predictions, 100).unwrap();
+        assert_eq!(price, 30);
+
+        let config = ensemble(members, EnsembleCombine::Max);
+        let predictions = config
+            .members
+            .iter()
+            .zip(prices)
+            .map(|(member, price)| (member, Ok((price, Settlement::Fast, 101))))
+            .collect();
+        let (price, _, _) = get_prediction_ensemble(&config, predictions, 100).unwrap();
+        assert_eq!(price, 40);
+    }
+
+    #[test]
+    fn test_failed_members_skipped() {
+        let config = ensemble(
+            vec![
+                member(ModelKind::PendingFloor, 10.0),
+                member(ModelKind::LastMin, 1.0),
+            ],
+            EnsembleCombine::WeightedMedian,
+        );
+
+        let predictions = vec![
+            (
+                &config.members[0],
+                Err(ModelError::missing_data("no pending block")),
+            ),
+            (&config.members[1], Ok((15, Settlement::Fast, 101))),
+        ];
+
+        let (price, _, _) = get_prediction_ensemble(&config, predictions, 100).unwrap();
+        assert_eq!(price, 15);
+
+        let predictions = vec![(
+            &config.members[0],
+            Err(ModelError::missing_data("no pending block")),
+        )];
+        let result = get_prediction_ensemble(&config, predictions, 100);
+        assert!(matches!(result, Err(ModelError::InsufficientData { .. })));
+    }
+
+    #[test]
+    fn test_validate_ensemble_config() {
+        let valid = ensemble(
+            vec![member(ModelKind::LastMin, 1.0)],
+            EnsembleCombine::Quantile { q: 0.9 },
+        );
+        assert!(validate_ensemble_config(&valid).is_ok());
+
+        let empty = ensemble(vec![], EnsembleCombine::Max);
+        assert!(validate_ensemble_config(&empty).is_err());
+
+        let nested = ensemble(vec![member(ModelKind::Ensemble, 1.0)], EnsembleCombine::Max);
+        assert!(validate_ensemble_config(&nested).is_err());
+
+        let zero_weight = ensemble(vec![member(ModelKind::LastMin, 0.0)], EnsembleCombine::Max);
+        assert!(validate_ensemble_config(&zero_weight).is_err());
+
+        let bad_quantile = ensemble(
+            vec![member(ModelKind::LastMin, 1.0)],
+            EnsembleCombine::Quantile { q: 1.5 },
+        );
+        assert!(validate_ensemble_config(&bad_quantile).is_err());
+    }
+}
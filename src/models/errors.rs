@@ -7,6 +7,12 @@ pub enum ModelError {
 
     #[error("Insufficient data for computation: {message}")]
     InsufficientData { message: String },
+
+    #[error("Invalid model configuration: {message}")]
+    InvalidConfig { message: String },
+
+    #[error("Stale data: {message}")]
+    StaleData { message: String },
 }
 
 impl ModelError {
@@ -21,4 +27,16 @@ impl ModelError {
             message: message.into(),
         }
     }
+
+    pub fn invalid_config(message: impl Into<String>) -> Self {
+        Self::InvalidConfig {
+            message: message.into(),
+        }
+    }
+
+    pub fn stale_data(message: impl Into<String>) -> Self {
+        Self::StaleData {
+            message: message.into(),
+        }
+    }
 }
@@ -0,0 +1,153 @@
+/*
+Exponentially Weighted Moving Average (EWMA) with Volatility Band
+This approach tracks an exponentially weighted mean and variance of block minimum prices and publishes an upper quantile of the resulting band.
+
+How it works: Every block minimum moves the mean by `alpha` times its distance from the previous mean, and the exponentially weighted variance is updated from the same deviation. Unlike a linear fit over medians it follows regime changes within a few blocks and forgets spikes geometrically instead of extrapolating them. The next block minimum is modelled as normally distributed around the mean with the tracked standard deviation, and the configured quantile of that distribution is published.
+*/
+
+use crate::blocks::{gwei_to_wei, wei_to_gwei};
+use crate::config::EwmaConfig;
+use crate::distribution::BlockDistribution;
+use crate::models::stats::normal_quantile;
+use crate::models::{FromBlock, ModelError, Prediction};
+use crate::types::Settlement;
+use tracing::debug;
+
+pub fn validate_ewma_config(config: &EwmaConfig) -> Result<(), ModelError> {
+    if !(config.alpha > 0.0 && config.alpha <= 1.0) {
+        return Err(ModelError::invalid_config(
+            "Ewma alpha must be greater than 0.0 and at most 1.0",
+        ));
+    }
+
+    if !(config.quantile > 0.0 && config.quantile < 1.0) {
+        return Err(ModelError::invalid_config(
+            "Ewma quantile must be between 0.0 and 1.0",
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn get_prediction_ewma(
+    block_distributions: &[BlockDistribution],
+    config: &EwmaConfig,
+    latest_block: u64,
+) -> Result<(Prediction, Settlement, FromBlock), ModelError> {
+    let mut block_mins = block_distributions.iter().filter_map(|block| {
+        block
+            .iter()
+            .map(|bucket| bucket.wei)
+            .min()
+            .and_then(|wei| wei_to_gwei(wei).ok())
+    });
+
+    let Some(first_min) = block_mins.next() else {
+        return Err(ModelError::insufficient_data(
+            "Ewma model requires at least one block with transactions",
+        ));
+    };
+
+    let mut mean = first_min;
+    let mut variance = 0.0;
+
+    for block_min in block_mins {
+        let deviation = block_min - mean;
+        mean += config.alpha * deviation;
+        variance = (1.0 - config.alpha) * (variance + config.alpha * deviation * deviation);
+    }
+
+    let std_dev = variance.sqrt();
+    let predicted_price = (mean + normal_quantile(config.quantile) * std_dev).max(0.0);
+
+    debug!(
+        mean_gwei = mean,
+        std_dev_gwei = std_dev,
+        quantile = config.quantile,
+        price_gwei = predicted_price,
+        "Ewma predictive distribution"
+    );
+
+    Ok((
+        gwei_to_wei(predicted_price),
+        Settlement::Fast,
+        latest_block + 1,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distribution::Bucket;
+
+    fn block(gwei: u128) -> BlockDistribution {
+        vec![Bucket {
+            wei: gwei * 1_000_000_000,
+            count: 1,
+            gas: 21_000,
+        }]
+    }
+
+    #[test]
+    fn test_stable_prices_have_no_band() {
+        let blocks = vec![block(10), block(10), block(10)];
+
+        let (price, settlement, from_block) =
+            get_prediction_ewma(&blocks, &EwmaConfig::default(), 100).unwrap();
+
+        assert_eq!(price, 10_000_000_000);
+        assert_eq!(settlement, Settlement::Fast);
+        assert_eq!(from_block, 101);
+    }
+
+    #[test]
+    fn test_band_widens_with_volatility() {
+        let stable = vec![block(20), block(20), block(20), block(20)];
+        let volatile = vec![block(10), block(30), block(10), block(30)];
+
+        let (stable_price, _, _) =
+            get_prediction_ewma(&stable, &EwmaConfig::default(), 100).unwrap();
+        let (volatile_price, _, _) =
+            get_prediction_ewma(&volatile, &EwmaConfig::default(), 100).unwrap();
+
+        assert_eq!(stable_price, 20_000_000_000);
+        assert!(volatile_price > 20_000_000_000);
+
+        // The median of the band is the mean itself
+        let median_config = EwmaConfig {
+            quantile: 0.5,
+            ..Default::default()
+        };
+        let (median_price, _, _) = get_prediction_ewma(&volatile, &median_config, 100).unwrap();
+        assert!(median_price < volatile_price);
+    }
+
+    #[test]
+    fn test_follows_regime_change() {
+        let mut blocks = vec![block(10); 20];
+        blocks.extend(vec![block(50); 10]);
+
+        let config = EwmaConfig {
+            alpha: 0.5,
+            quantile: 0.5,
+        };
+        let (price, _, _) = get_prediction_ewma(&blocks, &config, 100).unwrap();
+
+        // After 10 blocks at the new level the mean is within 0.1% of it
+        assert!(price > 49_950_000_000 && price <= 50_000_000_000);
+    }
+
+    #[test]
+    fn test_errors() {
+        let result = get_prediction_ewma(&[vec![]], &EwmaConfig::default(), 100);
+        assert!(matches!(result, Err(ModelError::InsufficientData { .. })));
+
+        let config = EwmaConfig {
+            alpha: 0.0,
+            ..Default::default()
+        };
+        let result = validate_ewma_config(&config);
+        assert!(matches!(result, Err(ModelError::InvalidConfig { .. })));
+        assert!(validate_ewma_config(&EwmaConfig::default()).is_ok());
+    }
+}
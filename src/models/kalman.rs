@@ -0,0 +1,162 @@
+/*
+Kalman Filter on Log Minimum Price
+This approach treats the log of the block minimum price as a random walk observed with noise and estimates it with a one dimensional Kalman filter.
+
+How it works: Between blocks the variance of the log price estimate grows by the process noise. Each block minimum is an observation with the measurement noise as its variance, and the Kalman gain decides how far the estimate moves towards it. A large process noise relative to the measurement noise reacts quickly to regime changes, a small one smooths out spikes. Working on the log price makes the uncertainty relative, so it scales with the price level. The next block minimum is modelled as log-normal with the estimate's variance plus both noises, and the configured quantile of that distribution is published.
+*/
+
+use crate::config::KalmanConfig;
+use crate::distribution::BlockDistribution;
+use crate::models::stats::normal_quantile;
+use crate::models::{FromBlock, ModelError, Prediction};
+use crate::types::Settlement;
+use tracing::debug;
+
+pub fn validate_kalman_config(config: &KalmanConfig) -> Result<(), ModelError> {
+    if !(config.process_noise > 0.0
+        && config.process_noise.is_finite()
+        && config.measurement_noise > 0.0
+        && config.measurement_noise.is_finite())
+    {
+        return Err(ModelError::invalid_config(
+            "Kalman process and measurement noise must be positive",
+        ));
+    }
+
+    if !(config.quantile > 0.0 && config.quantile < 1.0) {
+        return Err(ModelError::invalid_config(
+            "Kalman quantile must be between 0.0 and 1.0",
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn get_prediction_kalman(
+    block_distributions: &[BlockDistribution],
+    config: &KalmanConfig,
+    latest_block: u64,
+) -> Result<(Prediction, Settlement, FromBlock), ModelError> {
+    // Zero priced blocks have no log price and are skipped
+    let mut observations = block_distributions.iter().filter_map(|block| {
+        block
+            .iter()
+            .map(|bucket| bucket.wei)
+            .min()
+            .filter(|wei| *wei > 0)
+            .map(|wei| (wei as f64).ln())
+    });
+
+    let Some(first_observation) = observations.next() else {
+        return Err(ModelError::insufficient_data(
+            "Kalman model requires at least one block with a non-zero minimum price",
+        ));
+    };
+
+    let mut estimate = first_observation;
+    let mut variance = config.measurement_noise;
+
+    for observation in observations {
+        // Predict: the log price drifts by the process noise between blocks
+        variance += config.process_noise;
+
+        // Update: move towards the observation by the Kalman gain
+        let gain = variance / (variance + config.measurement_noise);
+        estimate += gain * (observation - estimate);
+        variance *= 1.0 - gain;
+    }
+
+    // Predictive distribution of the next block minimum in log space
+    let predictive_std_dev = (variance + config.process_noise + config.measurement_noise).sqrt();
+    let log_price = estimate + normal_quantile(config.quantile) * predictive_std_dev;
+    let predicted_price = log_price.exp().round();
+
+    debug!(
+        log_estimate = estimate,
+        estimate_variance = variance,
+        predictive_std_dev,
+        quantile = config.quantile,
+        price_wei = predicted_price,
+        "Kalman predictive distribution"
+    );
+
+    Ok((
+        predicted_price as Prediction,
+        Settlement::Fast,
+        latest_block + 1,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::distribution::Bucket;
+
+    fn block(gwei: u128) -> BlockDistribution {
+        vec![Bucket {
+            wei: gwei * 1_000_000_000,
+            count: 1,
+            gas: 21_000,
+        }]
+    }
+
+    fn median_config() -> KalmanConfig {
+        KalmanConfig {
+            quantile: 0.5,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_stable_prices() {
+        let blocks = vec![block(10); 10];
+
+        let (price, settlement, from_block) =
+            get_prediction_kalman(&blocks, &median_config(), 100).unwrap();
+
+        assert!(price.abs_diff(10_000_000_000) < 1_000);
+        assert_eq!(settlement, Settlement::Fast);
+        assert_eq!(from_block, 101);
+
+        // Upper quantiles add a band proportional to the price level
+        let (upper, _, _) = get_prediction_kalman(&blocks, &KalmanConfig::default(), 100).unwrap();
+        assert!(upper > 10_000_000_000);
+    }
+
+    #[test]
+    fn test_noise_controls_reaction_speed() {
+        let mut blocks = vec![block(10); 20];
+        blocks.extend(vec![block(40); 3]);
+
+        let responsive = KalmanConfig {
+            process_noise: 1.0,
+            measurement_noise: 0.01,
+            quantile: 0.5,
+        };
+        let smooth = KalmanConfig {
+            process_noise: 0.0001,
+            measurement_noise: 1.0,
+            quantile: 0.5,
+        };
+
+        let (responsive_price, _, _) = get_prediction_kalman(&blocks, &responsive, 100).unwrap();
+        let (smooth_price, _, _) = get_prediction_kalman(&blocks, &smooth, 100).unwrap();
+
+        assert!(responsive_price > 39_000_000_000);
+        assert!(smooth_price < 15_000_000_000);
+    }
+
+    #[test]
+    fn test_errors() {
+        let result = get_prediction_kalman(&[vec![]], &KalmanConfig::default(), 100);
+        assert!(matches!(result, Err(ModelError::InsufficientData { .. })));
+
+        let config = KalmanConfig {
+            measurement_noise: 0.0,
+            ..Default::default()
+        };
+        let result = validate_kalman_config(&config);
+        assert!(matches!(result, Err(ModelError::InvalidConfig { .. })));
+        assert!(validate_kalman_config(&KalmanConfig::default()).is_ok());
+    }
+}
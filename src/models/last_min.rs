@@ -2,9 +2,9 @@
 Simply takes the minimum of the last block.
 */
 
+use crate::distribution::BlockDistribution;
 use crate::models::{FromBlock, ModelError, Prediction};
 use crate::types::Settlement;
-use crate::{distribution::BlockDistribution, utils::round_to_9_places};
 
 pub fn get_prediction_last_min(
     block_distributions: &[BlockDistribution],
@@ -22,13 +22,9 @@ pub fn get_prediction_last_min(
 
     let last_min = last_block_distribution
         .iter()
-        .map(|bucket| bucket.gwei)
-        .min_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
-        .unwrap_or(0.0);
+        .map(|bucket| bucket.wei)
+        .min()
+        .unwrap_or(0);
 
-    Ok((
-        round_to_9_places(last_min),
-        Settlement::Fast,
-        latest_block + 1,
-    ))
+    Ok((last_min, Settlement::Fast, latest_block + 1))
 }
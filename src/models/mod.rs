@@ -1,33 +1,143 @@
-use crate::distribution::BlockDistribution;
+use crate::config::{ModelConfig, PendingStalenessConfig, StalePendingPolicy};
+use crate::distribution::{BlockDistribution, PendingBlockSnapshot};
+use crate::features::BlockFeatures;
 use crate::types::{ModelKind, Settlement};
 use adaptive_threshold::get_prediction_adaptive_threshold;
+use chrono::Utc;
 use distribution_analysis::get_prediction_distribution;
+use ensemble::{get_prediction_ensemble, validate_ensemble_config};
+use ewma::{get_prediction_ewma, validate_ewma_config};
+use kalman::{get_prediction_kalman, validate_kalman_config};
 use last_min::get_prediction_last_min;
 use moving_average::get_prediction_swma;
 use pending_floor::get_prediction_pending_floor;
 use percentile::get_prediction_percentile;
+use quantile_regression::{
+    get_prediction_quantile_regression, validate_quantile_regression_config, QuantileRegression,
+};
+use self_tuning::{validate_self_tuning_config, SelfTuningController};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
 use time_series::get_prediction_time_series;
+use tracing::{debug, warn};
 
 mod adaptive_threshold;
 mod distribution_analysis;
+mod ensemble;
 mod errors;
+mod ewma;
+mod kalman;
 mod last_min;
 mod moving_average;
 mod pending_floor;
 mod percentile;
+mod quantile_regression;
+mod self_tuning;
+mod stats;
 mod time_series;
 
 pub use errors::ModelError;
+pub use self_tuning::SelfTuningStatus;
 
-pub type Prediction = f64;
+/// Predicted price in wei
+pub type Prediction = u128;
 pub type FromBlock = u64;
 
+/// State an agent keeps between predictions for models that learn from their own results
+#[derive(Debug)]
+pub struct ModelState {
+    /// Used to translate a settlement into the block window a prediction is evaluated over.
+    /// Follows the measured block time of the chain.
+    block_time_ms: AtomicU64,
+    self_tuning: Mutex<Option<SelfTuningController>>,
+    quantile_regression: Mutex<Option<QuantileRegression>>,
+}
+
+impl ModelState {
+    pub fn new(block_time_ms: u64) -> Self {
+        Self {
+            block_time_ms: AtomicU64::new(block_time_ms),
+            self_tuning: Mutex::new(None),
+            quantile_regression: Mutex::new(None),
+        }
+    }
+
+    pub fn block_time_ms(&self) -> u64 {
+        self.block_time_ms.load(Ordering::Relaxed)
+    }
+
+    pub fn set_block_time_ms(&self, block_time_ms: u64) {
+        self.block_time_ms.store(block_time_ms, Ordering::Relaxed);
+    }
+
+    /// Feeds the realised minimum of a new block to stateful models.
+    /// Returns the self tuning controller state once the agent has made a self tuning prediction.
+    pub fn observe_block(
+        &self,
+        block_number: u64,
+        block_min: Option<u128>,
+    ) -> Option<SelfTuningStatus> {
+        let mut self_tuning = self.lock_self_tuning();
+        let controller = self_tuning.as_mut()?;
+        controller.observe_block(block_number, block_min);
+        Some(controller.status())
+    }
+
+    fn lock_self_tuning(&self) -> MutexGuard<'_, Option<SelfTuningController>> {
+        self.self_tuning
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn lock_quantile_regression(&self) -> MutexGuard<'_, Option<QuantileRegression>> {
+        self.quantile_regression
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Checks the configuration a model needs, so agents fail at startup instead of on every
+/// prediction
+pub fn validate_model_config(
+    model: &ModelKind,
+    model_config: &ModelConfig,
+) -> Result<(), ModelError> {
+    match model {
+        ModelKind::Ensemble => {
+            let ensemble_config = model_config.ensemble.as_ref().ok_or_else(|| {
+                ModelError::invalid_config("Ensemble model requires an ensemble configuration")
+            })?;
+
+            validate_ensemble_config(ensemble_config)?;
+
+            for member in &ensemble_config.members {
+                validate_model_config(&member.model, model_config)?;
+            }
+
+            Ok(())
+        }
+        ModelKind::SelfTuning => {
+            validate_self_tuning_config(&model_config.self_tuning)?;
+            validate_model_config(&model_config.self_tuning.base_model, model_config)
+        }
+        ModelKind::Ewma => validate_ewma_config(&model_config.ewma),
+        ModelKind::Kalman => validate_kalman_config(&model_config.kalman),
+        ModelKind::QuantileRegression => {
+            validate_quantile_regression_config(&model_config.quantile_regression)
+        }
+        _ => Ok(()),
+    }
+}
+
 /// Will apply a model to a list of block distribution and return a price
-/// Block distributions are sorted oldest to newest.
+/// Block distributions and block features are sorted oldest to newest.
 pub async fn apply_model(
     model: &ModelKind,
+    model_config: &ModelConfig,
+    model_state: &ModelState,
     block_distributions: &[BlockDistribution],
-    pending_block_distribution: Option<BlockDistribution>,
+    block_features: &[BlockFeatures],
+    pending_block: Option<PendingBlockSnapshot>,
     latest_block: u64,
 ) -> Result<(Prediction, Settlement, FromBlock), ModelError> {
     match model {
@@ -37,57 +147,217 @@ pub async fn apply_model(
         ModelKind::DistributionAnalysis => {
             get_prediction_distribution(block_distributions, latest_block)
         }
-        ModelKind::MovingAverage => get_prediction_swma(block_distributions, latest_block),
-        ModelKind::Percentile => get_prediction_percentile(block_distributions, latest_block),
+        ModelKind::MovingAverage => {
+            get_prediction_swma(block_distributions, model_config.weighting, latest_block)
+        }
+        ModelKind::Percentile => {
+            get_prediction_percentile(block_distributions, model_config.weighting, latest_block)
+        }
         ModelKind::TimeSeries => get_prediction_time_series(block_distributions, latest_block),
         ModelKind::LastMin => get_prediction_last_min(block_distributions, latest_block),
         ModelKind::PendingFloor => {
+            let pending_block_distribution = fresh_pending_block_distribution(
+                pending_block,
+                &model_config.pending_staleness,
+                model_state.block_time_ms(),
+                latest_block,
+            )?;
             get_prediction_pending_floor(pending_block_distribution, latest_block)
         }
+        ModelKind::Ewma => {
+            get_prediction_ewma(block_distributions, &model_config.ewma, latest_block)
+        }
+        ModelKind::Kalman => {
+            get_prediction_kalman(block_distributions, &model_config.kalman, latest_block)
+        }
+        ModelKind::QuantileRegression => {
+            let mut quantile_regression = model_state.lock_quantile_regression();
+            let model = quantile_regression
+                .get_or_insert_with(|| QuantileRegression::new(&model_config.quantile_regression));
+
+            get_prediction_quantile_regression(
+                model,
+                block_features,
+                &model_config.quantile_regression,
+                latest_block,
+            )
+        }
+        ModelKind::Ensemble => {
+            let ensemble_config = model_config.ensemble.as_ref().ok_or_else(|| {
+                ModelError::invalid_config("Ensemble model requires an ensemble configuration")
+            })?;
+
+            let mut member_predictions = Vec::with_capacity(ensemble_config.members.len());
+
+            for member in &ensemble_config.members {
+                let result = Box::pin(apply_model(
+                    &member.model,
+                    model_config,
+                    model_state,
+                    block_distributions,
+                    block_features,
+                    pending_block.clone(),
+                    latest_block,
+                ))
+                .await;
+
+                member_predictions.push((member, result));
+            }
+
+            get_prediction_ensemble(ensemble_config, member_predictions, latest_block)
+        }
+        ModelKind::SelfTuning => {
+            let self_tuning_config = &model_config.self_tuning;
+
+            let (base_price, settlement, from_block) = Box::pin(apply_model(
+                &self_tuning_config.base_model,
+                model_config,
+                model_state,
+                block_distributions,
+                block_features,
+                pending_block,
+                latest_block,
+            ))
+            .await?;
+
+            let mut self_tuning = model_state.lock_self_tuning();
+            let controller = self_tuning
+                .get_or_insert_with(|| SelfTuningController::new(self_tuning_config.clone()));
+
+            let price = controller.apply_premium(base_price);
+            controller.track(
+                price,
+                from_block,
+                settlement.to_block_window(model_state.block_time_ms()),
+            );
+
+            let status = controller.status();
+            debug!(
+                base_model = %self_tuning_config.base_model,
+                base_price = %base_price,
+                premium = status.premium,
+                inclusion_rate = ?status.inclusion_rate,
+                price = %price,
+                "Self tuning prediction"
+            );
+
+            Ok((price, settlement, from_block))
+        }
+    }
+}
+
+/// The distribution of a pending block snapshot that is within the staleness limits.
+/// Stale snapshots are refused, or used with a warning when configured to flag them.
+fn fresh_pending_block_distribution(
+    pending_block: Option<PendingBlockSnapshot>,
+    config: &PendingStalenessConfig,
+    block_time_ms: u64,
+    latest_block: u64,
+) -> Result<Option<BlockDistribution>, ModelError> {
+    let Some(snapshot) = pending_block else {
+        return Ok(None);
+    };
+
+    let age_ms = (Utc::now() - snapshot.fetched_at).num_milliseconds();
+    let max_age_ms = config.max_age_ms.unwrap_or(block_time_ms * 2);
+    let tip_lag = latest_block.saturating_sub(snapshot.chain_tip);
+
+    let stale_reason = if age_ms > max_age_ms as i64 {
+        Some(format!(
+            "Pending block snapshot is {age_ms}ms old, more than {max_age_ms}ms"
+        ))
+    } else if tip_lag > config.max_tip_lag {
+        Some(format!(
+            "Pending block snapshot was taken at block {}, {tip_lag} blocks behind the chain tip",
+            snapshot.chain_tip
+        ))
+    } else {
+        None
+    };
+
+    match (stale_reason, config.on_stale) {
+        (None, _) => Ok(Some(snapshot.distribution)),
+        (Some(reason), StalePendingPolicy::Refuse) => Err(ModelError::stale_data(reason)),
+        (Some(reason), StalePendingPolicy::Flag) => {
+            warn!(reason, "Using stale pending block snapshot");
+            Ok(Some(snapshot.distribution))
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::{SelfTuningConfig, Weighting};
     use crate::distribution::Bucket;
+    use chrono::Duration;
+
+    fn pending_snapshot(age_ms: i64, chain_tip: u64) -> PendingBlockSnapshot {
+        PendingBlockSnapshot {
+            distribution: vec![Bucket {
+                wei: 5_000_000_000,
+                count: 3,
+                gas: 63_000,
+            }],
+            fetched_at: Utc::now() - Duration::milliseconds(age_ms),
+            chain_tip,
+        }
+    }
 
     #[tokio::test]
     async fn test_apply_model_pending_floor() {
         let pending_distribution = vec![
             Bucket {
-                gwei: 10.0,
+                wei: 10_000_000_000,
                 count: 5,
+                gas: 105_000,
             },
             Bucket {
-                gwei: 5.0,
+                wei: 5_000_000_000,
                 count: 3,
+                gas: 63_000,
             },
             Bucket {
-                gwei: 15.0,
+                wei: 15_000_000_000,
                 count: 2,
+                gas: 42_000,
             },
         ];
 
         let (price, settlement, from_block) = apply_model(
             &ModelKind::PendingFloor,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &[],
             &[],
-            Some(pending_distribution),
+            Some(PendingBlockSnapshot {
+                distribution: pending_distribution,
+                fetched_at: Utc::now(),
+                chain_tip: 100,
+            }),
             100,
         )
         .await
         .unwrap();
 
-        // Should be minimum (5.0) + 1 wei (0.000000001)
-        let expected = 5.0 + 0.000000001;
-        assert_eq!(price, crate::utils::round_to_9_places(expected));
+        // Should be minimum (5 gwei) + 1 wei
+        assert_eq!(price, 5_000_000_001);
         assert_eq!(settlement, Settlement::Fast);
         assert_eq!(from_block, 101);
     }
 
     #[tokio::test]
     async fn test_apply_model_pending_floor_no_pending() {
-        let result = apply_model(&ModelKind::PendingFloor, &[], None, 100).await;
+        let result = apply_model(
+            &ModelKind::PendingFloor,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &[],
+            &[],
+            None,
+            100,
+        )
+        .await;
 
         // Should return an error when no pending distribution
         assert!(result.is_err());
@@ -97,6 +367,70 @@ mod tests {
             .contains("requires pending block distribution"));
     }
 
+    #[tokio::test]
+    async fn test_apply_model_pending_floor_stale() {
+        let apply = |model_config: ModelConfig, snapshot: PendingBlockSnapshot| async move {
+            apply_model(
+                &ModelKind::PendingFloor,
+                &model_config,
+                &ModelState::new(12_000),
+                &[],
+                &[],
+                Some(snapshot),
+                100,
+            )
+            .await
+        };
+
+        // Within two block times of the chain tip it was taken against
+        let (price, _, _) = apply(ModelConfig::default(), pending_snapshot(20_000, 100))
+            .await
+            .unwrap();
+        assert_eq!(price, 5_000_000_001);
+
+        // Older than two block times
+        let error = apply(ModelConfig::default(), pending_snapshot(30_000, 100))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, ModelError::StaleData { .. }));
+
+        // Taken against the previous chain tip, before the next pending block poll
+        assert!(apply(ModelConfig::default(), pending_snapshot(0, 99))
+            .await
+            .is_ok());
+
+        // Taken against an older chain tip
+        let error = apply(ModelConfig::default(), pending_snapshot(0, 98))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("2 blocks behind"));
+
+        // Within configured limits
+        let lenient = ModelConfig {
+            pending_staleness: PendingStalenessConfig {
+                max_age_ms: Some(60_000),
+                max_tip_lag: 2,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(apply(lenient.clone(), pending_snapshot(30_000, 98))
+            .await
+            .is_ok());
+        assert!(apply(lenient, pending_snapshot(0, 97)).await.is_err());
+
+        // Flagged snapshots are still used
+        let flag = ModelConfig {
+            pending_staleness: PendingStalenessConfig {
+                on_stale: StalePendingPolicy::Flag,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let (price, _, _) = apply(flag, pending_snapshot(60_000, 90)).await.unwrap();
+        assert_eq!(price, 5_000_000_001);
+    }
+
     #[test]
     fn test_pending_floor_model_kind_parsing() {
         use std::str::FromStr;
@@ -112,7 +446,16 @@ mod tests {
     #[tokio::test]
     async fn test_last_min_model_errors() {
         // Test empty block distributions
-        let result = apply_model(&ModelKind::LastMin, &[], None, 100).await;
+        let result = apply_model(
+            &ModelKind::LastMin,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &[],
+            &[],
+            None,
+            100,
+        )
+        .await;
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -121,7 +464,16 @@ mod tests {
 
         // Test empty last block
         let empty_block = vec![];
-        let result = apply_model(&ModelKind::LastMin, &[empty_block], None, 100).await;
+        let result = apply_model(
+            &ModelKind::LastMin,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &[empty_block],
+            &[],
+            None,
+            100,
+        )
+        .await;
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -132,7 +484,16 @@ mod tests {
     #[tokio::test]
     async fn test_percentile_model_errors() {
         // Test empty block distributions
-        let result = apply_model(&ModelKind::Percentile, &[], None, 100).await;
+        let result = apply_model(
+            &ModelKind::Percentile,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &[],
+            &[],
+            None,
+            100,
+        )
+        .await;
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -141,7 +502,16 @@ mod tests {
 
         // Test blocks with no transactions
         let empty_blocks = vec![vec![], vec![]];
-        let result = apply_model(&ModelKind::Percentile, &empty_blocks, None, 100).await;
+        let result = apply_model(
+            &ModelKind::Percentile,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &empty_blocks,
+            &[],
+            None,
+            100,
+        )
+        .await;
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -152,7 +522,16 @@ mod tests {
     #[tokio::test]
     async fn test_moving_average_model_errors() {
         // Test empty block distributions
-        let result = apply_model(&ModelKind::MovingAverage, &[], None, 100).await;
+        let result = apply_model(
+            &ModelKind::MovingAverage,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &[],
+            &[],
+            None,
+            100,
+        )
+        .await;
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -161,7 +540,16 @@ mod tests {
 
         // Test blocks with no transactions (should result in zero weight_sum)
         let empty_blocks = vec![vec![], vec![]];
-        let result = apply_model(&ModelKind::MovingAverage, &empty_blocks, None, 100).await;
+        let result = apply_model(
+            &ModelKind::MovingAverage,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &empty_blocks,
+            &[],
+            None,
+            100,
+        )
+        .await;
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -172,7 +560,16 @@ mod tests {
     #[tokio::test]
     async fn test_adaptive_threshold_model_errors() {
         // Test empty block distributions
-        let result = apply_model(&ModelKind::AdaptiveThreshold, &[], None, 100).await;
+        let result = apply_model(
+            &ModelKind::AdaptiveThreshold,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &[],
+            &[],
+            None,
+            100,
+        )
+        .await;
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -181,7 +578,16 @@ mod tests {
 
         // Test blocks with no transactions
         let empty_blocks = vec![vec![], vec![]];
-        let result = apply_model(&ModelKind::AdaptiveThreshold, &empty_blocks, None, 100).await;
+        let result = apply_model(
+            &ModelKind::AdaptiveThreshold,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &empty_blocks,
+            &[],
+            None,
+            100,
+        )
+        .await;
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -192,7 +598,16 @@ mod tests {
     #[tokio::test]
     async fn test_time_series_model_errors() {
         // Test empty block distributions
-        let result = apply_model(&ModelKind::TimeSeries, &[], None, 100).await;
+        let result = apply_model(
+            &ModelKind::TimeSeries,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &[],
+            &[],
+            None,
+            100,
+        )
+        .await;
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -201,7 +616,16 @@ mod tests {
 
         // Test blocks with no transactions
         let empty_blocks = vec![vec![], vec![], vec![]];
-        let result = apply_model(&ModelKind::TimeSeries, &empty_blocks, None, 100).await;
+        let result = apply_model(
+            &ModelKind::TimeSeries,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &empty_blocks,
+            &[],
+            None,
+            100,
+        )
+        .await;
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -212,7 +636,16 @@ mod tests {
     #[tokio::test]
     async fn test_distribution_analysis_model_errors() {
         // Test empty block distributions
-        let result = apply_model(&ModelKind::DistributionAnalysis, &[], None, 100).await;
+        let result = apply_model(
+            &ModelKind::DistributionAnalysis,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &[],
+            &[],
+            None,
+            100,
+        )
+        .await;
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -221,7 +654,16 @@ mod tests {
 
         // Test empty latest block
         let empty_block = vec![];
-        let result = apply_model(&ModelKind::DistributionAnalysis, &[empty_block], None, 100).await;
+        let result = apply_model(
+            &ModelKind::DistributionAnalysis,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &[empty_block],
+            &[],
+            None,
+            100,
+        )
+        .await;
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -233,37 +675,359 @@ mod tests {
     async fn test_models_with_valid_data() {
         let valid_block = vec![
             Bucket {
-                gwei: 10.0,
+                wei: 10_000_000_000,
                 count: 5,
+                gas: 105_000,
             },
             Bucket {
-                gwei: 15.0,
+                wei: 15_000_000_000,
                 count: 3,
+                gas: 63_000,
             },
             Bucket {
-                gwei: 8.0,
+                wei: 8_000_000_000,
                 count: 2,
+                gas: 42_000,
             },
         ];
         let blocks = vec![valid_block.clone(), valid_block.clone()];
 
         // Test all models with valid data
-        let result = apply_model(&ModelKind::LastMin, &blocks, None, 100).await;
+        let result = apply_model(
+            &ModelKind::LastMin,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &blocks,
+            &[],
+            None,
+            100,
+        )
+        .await;
         assert!(result.is_ok());
 
-        let result = apply_model(&ModelKind::Percentile, &blocks, None, 100).await;
+        let result = apply_model(
+            &ModelKind::Percentile,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &blocks,
+            &[],
+            None,
+            100,
+        )
+        .await;
         assert!(result.is_ok());
 
-        let result = apply_model(&ModelKind::MovingAverage, &blocks, None, 100).await;
+        let result = apply_model(
+            &ModelKind::MovingAverage,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &blocks,
+            &[],
+            None,
+            100,
+        )
+        .await;
         assert!(result.is_ok());
 
-        let result = apply_model(&ModelKind::AdaptiveThreshold, &blocks, None, 100).await;
+        let result = apply_model(
+            &ModelKind::AdaptiveThreshold,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &blocks,
+            &[],
+            None,
+            100,
+        )
+        .await;
         assert!(result.is_ok());
 
-        let result = apply_model(&ModelKind::TimeSeries, &blocks, None, 100).await;
+        let result = apply_model(
+            &ModelKind::TimeSeries,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &blocks,
+            &[],
+            None,
+            100,
+        )
+        .await;
         assert!(result.is_ok());
 
-        let result = apply_model(&ModelKind::DistributionAnalysis, &blocks, None, 100).await;
+        let result = apply_model(
+            &ModelKind::DistributionAnalysis,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &blocks,
+            &[],
+            None,
+            100,
+        )
+        .await;
         assert!(result.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_percentile_gas_weighting() {
+        // Many cheap transfers and a single expensive transaction using most of the gas
+        let block = vec![
+            Bucket {
+                wei: 10_000_000_000,
+                count: 9,
+                gas: 189_000,
+            },
+            Bucket {
+                wei: 20_000_000_000,
+                count: 1,
+                gas: 10_000_000,
+            },
+        ];
+
+        let (count_price, _, _) = apply_model(
+            &ModelKind::Percentile,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            std::slice::from_ref(&block),
+            &[],
+            None,
+            100,
+        )
+        .await
+        .unwrap();
+        assert_eq!(count_price, 10_000_000_000);
+
+        let gas_config = ModelConfig {
+            weighting: Weighting::Gas,
+            ..Default::default()
+        };
+        let (gas_price, _, _) = apply_model(
+            &ModelKind::Percentile,
+            &gas_config,
+            &ModelState::new(12_000),
+            &[block],
+            &[],
+            None,
+            100,
+        )
+        .await
+        .unwrap();
+        assert_eq!(gas_price, 20_000_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_moving_average_gas_weighting() {
+        let block = vec![
+            Bucket {
+                wei: 10_000_000_000,
+                count: 1,
+                gas: 30_000,
+            },
+            Bucket {
+                wei: 20_000_000_000,
+                count: 1,
+                gas: 10_000,
+            },
+        ];
+
+        let gas_config = ModelConfig {
+            weighting: Weighting::Gas,
+            ..Default::default()
+        };
+        let (price, _, _) = apply_model(
+            &ModelKind::MovingAverage,
+            &gas_config,
+            &ModelState::new(12_000),
+            std::slice::from_ref(&block),
+            &[],
+            None,
+            100,
+        )
+        .await
+        .unwrap();
+        assert_eq!(price, 12_500_000_000);
+
+        // Without gas data there is nothing to weight by
+        let no_gas_block: Vec<Bucket> = block
+            .into_iter()
+            .map(|bucket| Bucket { gas: 0, ..bucket })
+            .collect();
+        let result = apply_model(
+            &ModelKind::MovingAverage,
+            &gas_config,
+            &ModelState::new(12_000),
+            &[no_gas_block],
+            &[],
+            None,
+            100,
+        )
+        .await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ensemble_model() {
+        use crate::config::{EnsembleCombine, EnsembleConfig, EnsembleMember};
+
+        let block = vec![
+            Bucket {
+                wei: 10_000_000_000,
+                count: 5,
+                gas: 105_000,
+            },
+            Bucket {
+                wei: 20_000_000_000,
+                count: 5,
+                gas: 105_000,
+            },
+        ];
+
+        let model_config = ModelConfig {
+            ensemble: Some(EnsembleConfig {
+                members: vec![
+                    EnsembleMember {
+                        model: ModelKind::LastMin,
+                        weight: 1.0,
+                    },
+                    EnsembleMember {
+                        model: ModelKind::Percentile,
+                        weight: 1.0,
+                    },
+                    // No pending data so this member is skipped
+                    EnsembleMember {
+                        model: ModelKind::PendingFloor,
+                        weight: 5.0,
+                    },
+                ],
+                combine: EnsembleCombine::Max,
+            }),
+            ..Default::default()
+        };
+
+        let (price, settlement, from_block) = apply_model(
+            &ModelKind::Ensemble,
+            &model_config,
+            &ModelState::new(12_000),
+            std::slice::from_ref(&block),
+            &[],
+            None,
+            100,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(price, 20_000_000_000);
+        assert_eq!(settlement, Settlement::Fast);
+        assert_eq!(from_block, 101);
+
+        // Ensemble without configuration is rejected
+        let result = apply_model(
+            &ModelKind::Ensemble,
+            &ModelConfig::default(),
+            &ModelState::new(12_000),
+            &[block],
+            &[],
+            None,
+            100,
+        )
+        .await;
+        assert!(matches!(result, Err(ModelError::InvalidConfig { .. })));
+    }
+
+    #[test]
+    fn test_validate_ensemble_model_config() {
+        use crate::config::EnsembleConfig;
+
+        assert!(validate_model_config(&ModelKind::LastMin, &ModelConfig::default()).is_ok());
+
+        // Ensembles need a valid ensemble configuration
+        let result = validate_model_config(&ModelKind::Ensemble, &ModelConfig::default());
+        assert!(matches!(result, Err(ModelError::InvalidConfig { .. })));
+
+        let empty = ModelConfig {
+            ensemble: Some(EnsembleConfig {
+                members: vec![],
+                combine: Default::default(),
+            }),
+            ..Default::default()
+        };
+        let result = validate_model_config(&ModelKind::Ensemble, &empty);
+        assert!(matches!(result, Err(ModelError::InvalidConfig { .. })));
+    }
+
+    #[test]
+    fn test_validate_filter_model_config() {
+        use crate::config::{EwmaConfig, KalmanConfig};
+
+        let model_config = ModelConfig::default();
+        assert!(validate_model_config(&ModelKind::Ewma, &model_config).is_ok());
+        assert!(validate_model_config(&ModelKind::Kalman, &model_config).is_ok());
+
+        let bad_config = ModelConfig {
+            ewma: EwmaConfig {
+                alpha: 1.5,
+                ..Default::default()
+            },
+            kalman: KalmanConfig {
+                process_noise: f64::NAN,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        for model in [ModelKind::Ewma, ModelKind::Kalman] {
+            let result = validate_model_config(&model, &bad_config);
+            assert!(matches!(result, Err(ModelError::InvalidConfig { .. })));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_self_tuning_model() {
+        let block = vec![Bucket {
+            wei: 100_000_000,
+            count: 1,
+            gas: 21_000,
+        }];
+
+        let model_config = ModelConfig::default();
+        let model_state = ModelState::new(2_000);
+
+        // Nothing to report until the agent has made a self tuning prediction
+        assert_eq!(model_state.observe_block(100, Some(100_000_000)), None);
+
+        let (price, settlement, from_block) = apply_model(
+            &ModelKind::SelfTuning,
+            &model_config,
+            &model_state,
+            std::slice::from_ref(&block),
+            &[],
+            None,
+            100,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(price, 100_000_000);
+        assert_eq!(settlement, Settlement::Fast);
+        assert_eq!(from_block, 101);
+
+        // Fast settlement on 2 second blocks is evaluated over 7 blocks
+        let status = model_state.observe_block(101, Some(150_000_000)).unwrap();
+        assert_eq!(status.resolved, 0);
+        assert_eq!(status.tracked, 1);
+
+        let status = model_state.observe_block(107, Some(150_000_000)).unwrap();
+        assert_eq!(status.resolved, 1);
+        assert_eq!(status.included, 0);
+        assert!(status.premium > 0.0);
+
+        // Base models that cannot be wrapped are rejected
+        let nested_config = ModelConfig {
+            self_tuning: SelfTuningConfig {
+                base_model: ModelKind::SelfTuning,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let result = validate_model_config(&ModelKind::SelfTuning, &nested_config);
+        assert!(matches!(result, Err(ModelError::InvalidConfig { .. })));
+        assert!(validate_model_config(&ModelKind::SelfTuning, &model_config).is_ok());
+    }
 }
@@ -3,14 +3,17 @@ Simple Weighted Moving Average (SWMA)
 This approach calculates a weighted average of recent gas prices, giving more weight to more recent blocks.
 
 How it works: This algorithm calculates the average gas price for each block, weighs them by recency, and produces a weighted average. It's simple and works well when gas prices are relatively stable.
+With gas weighting each block average is weighted by the gas of its transactions instead of their count.
 */
 
+use crate::config::Weighting;
 use crate::models::{FromBlock, ModelError, Prediction};
 use crate::types::Settlement;
-use crate::{distribution::BlockDistribution, utils::round_to_9_places};
+use crate::{blocks::gwei_to_wei, distribution::BlockDistribution};
 
 pub fn get_prediction_swma(
     block_distributions: &[BlockDistribution],
+    weighting: Weighting,
     latest_block: u64,
 ) -> Result<(Prediction, Settlement, FromBlock), ModelError> {
     if block_distributions.is_empty() {
@@ -30,15 +33,15 @@ pub fn get_prediction_swma(
         let weight = (i + 1) as f64; // Higher weights for more recent blocks
 
         // Calculate average gas price for this block
-        let total_txs: f64 = block.iter().map(|bucket| bucket.count as f64).sum();
+        let total_weight: f64 = block.iter().map(|bucket| bucket.weight(weighting)).sum();
 
-        if total_txs > 0.0 {
+        if total_weight > 0.0 {
             has_transactions = true;
             let block_avg_gas_price = block
                 .iter()
-                .map(|bucket| bucket.gwei * bucket.count as f64)
+                .map(|bucket| bucket.gwei() * bucket.weight(weighting))
                 .sum::<f64>()
-                / total_txs;
+                / total_weight;
 
             weighted_sum += block_avg_gas_price * weight;
             weight_sum += weight;
@@ -60,7 +63,7 @@ pub fn get_prediction_swma(
     };
 
     Ok((
-        round_to_9_places(predicted_price),
+        gwei_to_wei(predicted_price),
         Settlement::Fast,
         latest_block + 1,
     ))
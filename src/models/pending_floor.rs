@@ -9,15 +9,15 @@ to ensure the transaction will be included while paying the absolute minimum.
 How it works:
 1. Requires pending block distribution (future block content) - returns error if not provided
 2. Finds the minimum gas price in that distribution
-3. Adds 1 wei (0.000000001 gwei) to guarantee inclusion
+3. Adds 1 wei to guarantee inclusion
 4. Returns this as the optimal price for immediate settlement
 */
 
+use crate::distribution::BlockDistribution;
 use crate::models::{FromBlock, ModelError, Prediction};
 use crate::types::Settlement;
-use crate::{distribution::BlockDistribution, utils::round_to_9_places};
 
-const ONE_WEI_IN_GWEI: f64 = 0.000000001; // 1 wei
+const ONE_WEI: u128 = 1;
 
 pub fn get_prediction_pending_floor(
     pending_block_distribution: Option<BlockDistribution>,
@@ -38,23 +38,16 @@ pub fn get_prediction_pending_floor(
     }
 
     // Find the minimum gas price in the pending block distribution
-    let min_price =
-        pending_distribution
-            .iter()
-            .map(|bucket| bucket.gwei)
-            .fold(
-                f64::INFINITY,
-                |min, price| if price < min { price } else { min },
-            );
+    let min_price = pending_distribution
+        .iter()
+        .map(|bucket| bucket.wei)
+        .min()
+        .unwrap_or(0);
 
     // Add 1 wei to the minimum price to ensure inclusion
-    let prediction = min_price + ONE_WEI_IN_GWEI;
+    let prediction = min_price + ONE_WEI;
 
-    Ok((
-        round_to_9_places(prediction),
-        Settlement::Fast,
-        latest_block + 1,
-    ))
+    Ok((prediction, Settlement::Fast, latest_block + 1))
 }
 
 #[cfg(test)]
@@ -66,29 +59,33 @@ mod tests {
     fn test_pending_floor_with_pending_distribution() {
         let pending_distribution = vec![
             Bucket {
-                gwei: 10.0,
+                wei: 10_000_000_000,
                 count: 5,
+                gas: 105_000,
             },
             Bucket {
-                gwei: 15.0,
+                wei: 15_000_000_000,
                 count: 3,
+                gas: 63_000,
             },
             Bucket {
-                gwei: 8.0,
+                wei: 8_000_000_000,
                 count: 2,
+                gas: 42_000,
             }, // This should be the minimum
             Bucket {
-                gwei: 12.0,
+                wei: 12_000_000_000,
                 count: 4,
+                gas: 84_000,
             },
         ];
 
         let (price, settlement, from_block) =
             get_prediction_pending_floor(Some(pending_distribution), 100).unwrap();
 
-        // Should be minimum (8.0) + 1 wei (0.000000001)
-        let expected = 8.0 + ONE_WEI_IN_GWEI;
-        assert_eq!(price, round_to_9_places(expected));
+        // Should be minimum (8 gwei) + 1 wei
+        let expected = 8_000_000_000 + ONE_WEI;
+        assert_eq!(price, expected);
         assert_eq!(settlement, Settlement::Fast);
         assert_eq!(from_block, 101);
     }
@@ -122,16 +119,17 @@ mod tests {
     #[test]
     fn test_pending_floor_with_single_bucket() {
         let pending_distribution = vec![Bucket {
-            gwei: 25.5,
+            wei: 25_500_000_000,
             count: 10,
+            gas: 210_000,
         }];
 
         let (price, settlement, from_block) =
             get_prediction_pending_floor(Some(pending_distribution), 100).unwrap();
 
-        // Should be 25.5 + 1 wei
-        let expected = 25.5 + ONE_WEI_IN_GWEI;
-        assert_eq!(price, round_to_9_places(expected));
+        // Should be 25.5 gwei + 1 wei
+        let expected = 25_500_000_000 + ONE_WEI;
+        assert_eq!(price, expected);
         assert_eq!(settlement, Settlement::Fast);
         assert_eq!(from_block, 101);
     }
@@ -140,21 +138,23 @@ mod tests {
     fn test_pending_floor_with_zero_minimum() {
         let pending_distribution = vec![
             Bucket {
-                gwei: 0.0,
+                wei: 0,
                 count: 1,
+                gas: 21_000,
             },
             Bucket {
-                gwei: 5.0,
+                wei: 5_000_000_000,
                 count: 2,
+                gas: 42_000,
             },
         ];
 
         let (price, settlement, from_block) =
             get_prediction_pending_floor(Some(pending_distribution), 100).unwrap();
 
-        // Should be 0.0 + 1 wei
-        let expected = 0.0 + ONE_WEI_IN_GWEI;
-        assert_eq!(price, round_to_9_places(expected));
+        // Should be 0 + 1 wei
+        let expected = ONE_WEI;
+        assert_eq!(price, expected);
         assert_eq!(settlement, Settlement::Fast);
         assert_eq!(from_block, 101);
     }
@@ -162,16 +162,17 @@ mod tests {
     #[test]
     fn test_pending_floor_rounding() {
         let pending_distribution = vec![Bucket {
-            gwei: 1.123456789,
+            wei: 1_123_456_789,
             count: 1,
+            gas: 21_000,
         }];
 
         let (price, settlement, from_block) =
             get_prediction_pending_floor(Some(pending_distribution), 100).unwrap();
 
-        // Should be properly rounded to 9 decimal places
-        let expected = 1.123456789 + ONE_WEI_IN_GWEI;
-        assert_eq!(price, round_to_9_places(expected));
+        // Should be exact to the wei
+        let expected = 1_123_456_789 + ONE_WEI;
+        assert_eq!(price, expected);
         assert_eq!(settlement, Settlement::Fast);
         assert_eq!(from_block, 101);
     }
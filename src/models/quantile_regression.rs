@@ -0,0 +1,360 @@
+/*
+Quantile Regression on Block Features
+This approach learns a linear quantile regression of the next block's minimum price on observable conditions of the block before it.
+
+How it works: Every new block pairs the features of the block before it (gas utilization, relative base fee change, pending transaction count and time of day) with its own log minimum price. The model is updated online with one stochastic gradient step on the pinball loss of the configured quantile for each such pair, starting from the first observed minimum. Features are standardized with running means and variances so one learning rate fits all of them. The coefficients are kept in the agent's model state between predictions, and each prediction first learns from the blocks that arrived since the last one. The model is then applied to the features of the latest block to predict the quantile of the next block's minimum.
+*/
+
+use crate::config::QuantileRegressionConfig;
+use crate::features::BlockFeatures;
+use crate::models::{FromBlock, ModelError, Prediction};
+use crate::types::Settlement;
+use std::f64::consts::PI;
+use tracing::debug;
+
+const NUM_FEATURES: usize = 5;
+const SECONDS_PER_DAY: f64 = 86_400.0;
+const MIN_TRAINING_SAMPLES: u64 = 10;
+
+type Features = [f64; NUM_FEATURES];
+
+/// Turns block features into regressors. Time of day is encoded on the unit circle
+/// so midnight is next to 23:59, and pending counts are log scaled.
+fn regressors(features: &BlockFeatures) -> Features {
+    let angle = 2.0 * PI * features.seconds_of_day as f64 / SECONDS_PER_DAY;
+
+    [
+        features.utilization,
+        features.base_fee_change,
+        (features.pending_tx_count as f64).ln_1p(),
+        angle.sin(),
+        angle.cos(),
+    ]
+}
+
+/// Pairs the features of a block with the log minimum price of the block after it
+fn training_sample(block: &BlockFeatures, next_block: &BlockFeatures) -> Option<(Features, f64)> {
+    if next_block.block_number != block.block_number + 1 {
+        return None;
+    }
+
+    let block_min = next_block.block_min.filter(|wei| *wei > 0)?;
+    Some((regressors(block), (block_min as f64).ln()))
+}
+
+/// Linear quantile regression learned online, one gradient step per block
+#[derive(Debug)]
+pub struct QuantileRegression {
+    quantile: f64,
+    learning_rate: f64,
+    samples: u64,
+    /// Running mean and sum of squared deviations of each regressor
+    means: Features,
+    squared_deviations: Features,
+    intercept: f64,
+    coefficients: Features,
+    /// Latest block learned from, blocks up to it are skipped
+    last_block: Option<u64>,
+}
+
+impl QuantileRegression {
+    pub fn new(config: &QuantileRegressionConfig) -> Self {
+        Self {
+            quantile: config.quantile,
+            learning_rate: config.learning_rate,
+            samples: 0,
+            means: [0.0; NUM_FEATURES],
+            squared_deviations: [0.0; NUM_FEATURES],
+            intercept: 0.0,
+            coefficients: [0.0; NUM_FEATURES],
+            last_block: None,
+        }
+    }
+
+    /// Learns from the blocks of the feature history that arrived since the last update
+    fn learn(&mut self, block_features: &[BlockFeatures]) {
+        for pair in block_features.windows(2) {
+            if self
+                .last_block
+                .is_some_and(|last_block| pair[1].block_number <= last_block)
+            {
+                continue;
+            }
+
+            self.last_block = Some(pair[1].block_number);
+
+            if let Some((x, y)) = training_sample(&pair[0], &pair[1]) {
+                self.update(&x, y);
+            }
+        }
+    }
+
+    fn update(&mut self, x: &Features, y: f64) {
+        self.samples += 1;
+        let n = self.samples as f64;
+
+        for (i, value) in x.iter().enumerate() {
+            let delta = value - self.means[i];
+            self.means[i] += delta / n;
+            self.squared_deviations[i] += delta * (value - self.means[i]);
+        }
+
+        // Start from the first minimum so only the feature effects have to be learned
+        if self.samples == 1 {
+            self.intercept = y;
+            return;
+        }
+
+        let standardized = self.standardize(x);
+        let predicted = self.predict_standardized(&standardized);
+
+        // Subgradient of the pinball loss
+        let gradient = if y < predicted {
+            self.quantile - 1.0
+        } else {
+            self.quantile
+        };
+
+        self.intercept += self.learning_rate * gradient;
+        for (coefficient, value) in self.coefficients.iter_mut().zip(standardized) {
+            *coefficient += self.learning_rate * gradient * value;
+        }
+    }
+
+    fn standardize(&self, x: &Features) -> Features {
+        let mut standardized = [0.0; NUM_FEATURES];
+
+        for (i, value) in x.iter().enumerate() {
+            let variance = self.squared_deviations[i] / self.samples.max(1) as f64;
+            // Constant features keep a unit scale and end up centered at zero
+            let scale = if variance > f64::EPSILON {
+                variance.sqrt()
+            } else {
+                1.0
+            };
+            standardized[i] = (value - self.means[i]) / scale;
+        }
+
+        standardized
+    }
+
+    fn predict_standardized(&self, standardized: &Features) -> f64 {
+        self.intercept
+            + self
+                .coefficients
+                .iter()
+                .zip(standardized)
+                .map(|(coefficient, value)| coefficient * value)
+                .sum::<f64>()
+    }
+
+    fn predict(&self, x: &Features) -> f64 {
+        self.predict_standardized(&self.standardize(x))
+    }
+}
+
+pub fn validate_quantile_regression_config(
+    config: &QuantileRegressionConfig,
+) -> Result<(), ModelError> {
+    if !(config.quantile > 0.0 && config.quantile < 1.0) {
+        return Err(ModelError::invalid_config(
+            "QuantileRegression quantile must be between 0.0 and 1.0",
+        ));
+    }
+
+    if !(config.learning_rate > 0.0 && config.learning_rate.is_finite()) {
+        return Err(ModelError::invalid_config(
+            "QuantileRegression learning rate must be positive",
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn get_prediction_quantile_regression(
+    model: &mut QuantileRegression,
+    block_features: &[BlockFeatures],
+    config: &QuantileRegressionConfig,
+    latest_block: u64,
+) -> Result<(Prediction, Settlement, FromBlock), ModelError> {
+    let latest_features = block_features.last().ok_or_else(|| {
+        ModelError::insufficient_data("QuantileRegression model requires block features")
+    })?;
+
+    model.learn(block_features);
+
+    if model.samples < MIN_TRAINING_SAMPLES {
+        return Err(ModelError::insufficient_data(format!(
+            "QuantileRegression model requires at least {} consecutive blocks with transactions, has {}",
+            MIN_TRAINING_SAMPLES,
+            model.samples
+        )));
+    }
+
+    let log_price = model.predict(&regressors(latest_features));
+    let predicted_price = log_price.exp().round();
+
+    debug!(
+        samples = model.samples,
+        intercept = model.intercept,
+        coefficients = ?model.coefficients,
+        quantile = config.quantile,
+        price_wei = predicted_price,
+        "QuantileRegression prediction"
+    );
+
+    Ok((
+        predicted_price as Prediction,
+        Settlement::Fast,
+        latest_block + 1,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GWEI: f64 = 1_000_000_000.0;
+
+    /// Synthetic history where the next block minimum grows with the utilization of
+    /// the previous block, plus deterministic noise spread evenly over [0, 0.2)
+    fn synthetic_history(len: u64) -> Vec<BlockFeatures> {
+        let mut history = Vec::new();
+        let mut previous_utilization = 0.5;
+
+        for number in 0..len {
+            let utilization = ((number * 7) % 10) as f64 / 10.0;
+            let noise = ((number * 37) % 100) as f64 / 500.0;
+            let block_min = (10.0 * GWEI * (previous_utilization + noise).exp()).round() as u128;
+
+            history.push(BlockFeatures {
+                block_number: 1_000 + number,
+                utilization,
+                base_fee_per_gas: None,
+                base_fee_change: 0.0,
+                pending_tx_count: 0,
+                seconds_of_day: (number * 600 % 86_400) as u32,
+                block_min: Some(block_min),
+            });
+
+            previous_utilization = utilization;
+        }
+
+        history
+    }
+
+    #[test]
+    fn test_predicts_from_utilization() {
+        let config = QuantileRegressionConfig::default();
+        let mut model = QuantileRegression::new(&config);
+        let mut history = synthetic_history(5_000);
+
+        let last = history.len() - 1;
+        history[last].utilization = 0.9;
+        let (busy_price, settlement, from_block) =
+            get_prediction_quantile_regression(&mut model, &history, &config, 5_999).unwrap();
+
+        history[last].utilization = 0.1;
+        let (quiet_price, _, _) =
+            get_prediction_quantile_regression(&mut model, &history, &config, 5_999).unwrap();
+
+        assert_eq!(settlement, Settlement::Fast);
+        assert_eq!(from_block, 6_000);
+
+        // exp(0.8) is about 2.2x between a busy and a quiet previous block
+        let ratio = busy_price as f64 / quiet_price as f64;
+        assert!(ratio > 1.8 && ratio < 2.6, "ratio {ratio}");
+    }
+
+    #[test]
+    fn test_quantile_coverage() {
+        let history = synthetic_history(2_300);
+        let (training, evaluation) = history.split_at(2_000);
+
+        for quantile in [0.5, 0.9] {
+            let mut model = QuantileRegression::new(&QuantileRegressionConfig {
+                quantile,
+                ..Default::default()
+            });
+            model.learn(training);
+
+            let samples: Vec<(Features, f64)> = evaluation
+                .windows(2)
+                .filter_map(|pair| training_sample(&pair[0], &pair[1]))
+                .collect();
+            let covered = samples
+                .iter()
+                .filter(|(x, y)| *y <= model.predict(x))
+                .count() as f64
+                / samples.len() as f64;
+
+            assert!(
+                (covered - quantile).abs() < 0.08,
+                "quantile {quantile} covered {covered}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_learns_each_block_once() {
+        let config = QuantileRegressionConfig::default();
+        let mut model = QuantileRegression::new(&config);
+        let history = synthetic_history(20);
+
+        model.learn(&history[..10]);
+        assert_eq!(model.samples, 9);
+
+        // The rolling history overlaps the blocks already learned from
+        model.learn(&history[5..12]);
+        assert_eq!(model.samples, 11);
+        assert_eq!(model.last_block, Some(1_011));
+
+        let intercept = model.intercept;
+        model.learn(&history[5..12]);
+        assert_eq!(model.samples, 11);
+        assert_eq!(model.intercept, intercept);
+    }
+
+    #[test]
+    fn test_skips_gaps_and_empty_blocks() {
+        let mut history = synthetic_history(5);
+        history[2].block_number += 10;
+        history[3].block_number += 10;
+        history[4].block_number += 10;
+        history[4].block_min = None;
+
+        // The second pair has a gap and the last block has no minimum
+        let mut model = QuantileRegression::new(&QuantileRegressionConfig::default());
+        model.learn(&history);
+        assert_eq!(model.samples, 2);
+        assert_eq!(model.last_block, Some(1_014));
+    }
+
+    #[test]
+    fn test_errors() {
+        let config = QuantileRegressionConfig::default();
+        let mut model = QuantileRegression::new(&config);
+
+        let result = get_prediction_quantile_regression(&mut model, &[], &config, 100);
+        assert!(matches!(result, Err(ModelError::InsufficientData { .. })));
+
+        let result =
+            get_prediction_quantile_regression(&mut model, &synthetic_history(5), &config, 100);
+        assert!(matches!(result, Err(ModelError::InsufficientData { .. })));
+
+        assert!(validate_quantile_regression_config(&config).is_ok());
+        for bad_config in [
+            QuantileRegressionConfig {
+                quantile: 1.0,
+                ..Default::default()
+            },
+            QuantileRegressionConfig {
+                learning_rate: f64::NAN,
+                ..Default::default()
+            },
+        ] {
+            let result = validate_quantile_regression_config(&bad_config);
+            assert!(matches!(result, Err(ModelError::InvalidConfig { .. })));
+        }
+    }
+}
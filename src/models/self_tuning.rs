@@ -0,0 +1,296 @@
+/*
+Self Tuning Model
+This approach applies a multiplicative premium to a base model's prediction and tunes that premium from how its own previous predictions landed.
+
+How it works: Every prediction is tracked until its settlement block window has closed. A prediction is included when its price is at or above the lowest block minimum in that window. Each resolved prediction nudges the premium with a bounded feedback controller: misses raise it by `gain * target`, inclusions lower it by `gain * (1 - target)`, so the premium settles where the realised inclusion rate matches the target inclusion rate. The premium is clamped between the configured minimum and maximum.
+*/
+
+use crate::config::SelfTuningConfig;
+use crate::models::{FromBlock, ModelError, Prediction};
+use crate::types::ModelKind;
+use std::collections::VecDeque;
+
+/// Oldest predictions are dropped beyond this, e.g. when polling much faster than blocks arrive
+const MAX_TRACKED_PREDICTIONS: usize = 1_000;
+/// Smoothing factor of the realised inclusion rate reported in logs and metrics
+const INCLUSION_RATE_ALPHA: f64 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SelfTuningStatus {
+    /// Current premium as a fraction of the base prediction
+    pub premium: f64,
+    /// Exponentially smoothed inclusion rate, `None` until a prediction has resolved
+    pub inclusion_rate: Option<f64>,
+    /// Number of resolved predictions that were included
+    pub included: u64,
+    /// Number of resolved predictions
+    pub resolved: u64,
+    /// Predictions whose block window has not closed yet
+    pub tracked: usize,
+}
+
+#[derive(Debug)]
+struct TrackedPrediction {
+    price: Prediction,
+    from_block: FromBlock,
+    end_block: u64,
+    window_min: Option<u128>,
+}
+
+#[derive(Debug)]
+pub struct SelfTuningController {
+    config: SelfTuningConfig,
+    premium: f64,
+    inclusion_rate: Option<f64>,
+    included: u64,
+    resolved: u64,
+    tracked: VecDeque<TrackedPrediction>,
+}
+
+/// Checks the controller parameters before the controller is created
+pub fn validate_self_tuning_config(config: &SelfTuningConfig) -> Result<(), ModelError> {
+    if matches!(
+        config.base_model,
+        ModelKind::SelfTuning | ModelKind::Ensemble
+    ) {
+        return Err(ModelError::invalid_config(format!(
+            "SelfTuning base model cannot be {}",
+            config.base_model
+        )));
+    }
+
+    if !(config.target_inclusion_rate > 0.0 && config.target_inclusion_rate < 1.0) {
+        return Err(ModelError::invalid_config(
+            "SelfTuning target inclusion rate must be between 0.0 and 1.0",
+        ));
+    }
+
+    if !(config.gain.is_finite() && config.gain > 0.0) {
+        return Err(ModelError::invalid_config(
+            "SelfTuning gain must be positive",
+        ));
+    }
+
+    if !(config.min_premium > -1.0
+        && config.max_premium.is_finite()
+        && config.min_premium <= config.max_premium)
+    {
+        return Err(ModelError::invalid_config(
+            "SelfTuning premium bounds must satisfy -1.0 < min_premium <= max_premium",
+        ));
+    }
+
+    Ok(())
+}
+
+impl SelfTuningController {
+    pub fn new(config: SelfTuningConfig) -> Self {
+        let premium = 0.0_f64.clamp(config.min_premium, config.max_premium);
+
+        Self {
+            config,
+            premium,
+            inclusion_rate: None,
+            included: 0,
+            resolved: 0,
+            tracked: VecDeque::new(),
+        }
+    }
+
+    pub fn status(&self) -> SelfTuningStatus {
+        SelfTuningStatus {
+            premium: self.premium,
+            inclusion_rate: self.inclusion_rate,
+            included: self.included,
+            resolved: self.resolved,
+            tracked: self.tracked.len(),
+        }
+    }
+
+    /// Applies the current premium to a base prediction
+    pub fn apply_premium(&self, base: Prediction) -> Prediction {
+        let adjustment = (base as f64 * self.premium).round() as i128;
+        (base as i128).saturating_add(adjustment).max(0) as Prediction
+    }
+
+    /// Starts tracking a prediction evaluated over `window_blocks` blocks from `from_block`
+    pub fn track(&mut self, price: Prediction, from_block: FromBlock, window_blocks: u64) {
+        if self.tracked.len() >= MAX_TRACKED_PREDICTIONS {
+            self.tracked.pop_front();
+        }
+
+        self.tracked.push_back(TrackedPrediction {
+            price,
+            from_block,
+            end_block: from_block + window_blocks.max(1) - 1,
+            window_min: None,
+        });
+    }
+
+    /// Feeds the realised minimum of a new block to the tracked predictions and
+    /// updates the premium for every prediction whose block window has closed
+    pub fn observe_block(&mut self, block_number: u64, block_min: Option<u128>) {
+        if let Some(block_min) = block_min {
+            for prediction in self.tracked.iter_mut() {
+                if prediction.from_block <= block_number && block_number <= prediction.end_block {
+                    prediction.window_min = Some(
+                        prediction
+                            .window_min
+                            .map_or(block_min, |min| min.min(block_min)),
+                    );
+                }
+            }
+        }
+
+        let mut outcomes = Vec::new();
+
+        self.tracked.retain(|prediction| {
+            if prediction.end_block > block_number {
+                return true;
+            }
+
+            // Windows without any observed block minimum cannot be judged
+            if let Some(window_min) = prediction.window_min {
+                outcomes.push(prediction.price >= window_min);
+            }
+
+            false
+        });
+
+        for included in outcomes {
+            self.update(included);
+        }
+    }
+
+    fn update(&mut self, included: bool) {
+        let outcome = if included { 1.0 } else { 0.0 };
+        let error = self.config.target_inclusion_rate - outcome;
+
+        self.premium = (self.premium + self.config.gain * error)
+            .clamp(self.config.min_premium, self.config.max_premium);
+
+        self.inclusion_rate = Some(match self.inclusion_rate {
+            Some(rate) => rate + INCLUSION_RATE_ALPHA * (outcome - rate),
+            None => outcome,
+        });
+
+        self.resolved += 1;
+        if included {
+            self.included += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> SelfTuningConfig {
+        SelfTuningConfig {
+            gain: 0.1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_premium_rises_on_misses() {
+        let mut controller = SelfTuningController::new(config());
+        assert_eq!(controller.apply_premium(100), 100);
+
+        controller.track(100, 11, 1);
+        controller.observe_block(11, Some(120));
+
+        let status = controller.status();
+        assert!((status.premium - 0.09).abs() < 1e-9);
+        assert_eq!(status.resolved, 1);
+        assert_eq!(status.included, 0);
+        assert_eq!(status.inclusion_rate, Some(0.0));
+        assert_eq!(controller.apply_premium(100), 109);
+    }
+
+    #[test]
+    fn test_premium_is_bounded() {
+        let mut controller = SelfTuningController::new(config());
+
+        // Every prediction is included so the premium is pushed to its lower bound
+        for block in 1..=100 {
+            controller.track(100, block, 1);
+            controller.observe_block(block, Some(50));
+        }
+        assert_eq!(controller.status().premium, 0.0);
+
+        // Every prediction misses so the premium is pushed to its upper bound
+        for block in 101..=200 {
+            controller.track(100, block, 1);
+            controller.observe_block(block, Some(1_000));
+        }
+        assert_eq!(controller.status().premium, 0.5);
+        assert_eq!(controller.status().resolved, 200);
+    }
+
+    #[test]
+    fn test_window_minimum_over_several_blocks() {
+        let mut controller = SelfTuningController::new(config());
+
+        controller.track(100, 11, 3);
+
+        controller.observe_block(11, Some(150));
+        controller.observe_block(12, Some(90));
+        assert_eq!(controller.status().resolved, 0);
+        assert_eq!(controller.status().tracked, 1);
+
+        controller.observe_block(13, Some(200));
+        let status = controller.status();
+        assert_eq!(status.resolved, 1);
+        assert_eq!(status.included, 1);
+        assert_eq!(status.tracked, 0);
+    }
+
+    #[test]
+    fn test_converges_to_target_inclusion_rate() {
+        let mut controller = SelfTuningController::new(SelfTuningConfig {
+            gain: 0.005,
+            max_premium: 2.0,
+            ..Default::default()
+        });
+
+        // Block minimums are uniform between 100 and 199, so a base of 100 with
+        // a premium close to 0.89 is included 90% of the time
+        let base = 100;
+        for block in 1..=20_000u64 {
+            let price = controller.apply_premium(base);
+            controller.track(price, block, 1);
+            let block_min = 100 + (block * 37) % 100;
+            controller.observe_block(block, Some(block_min as u128));
+        }
+
+        let status = controller.status();
+        let realised = status.included as f64 / status.resolved as f64;
+        assert!((realised - 0.9).abs() < 0.02, "realised {realised}");
+        assert!(status.premium > 0.8 && status.premium < 1.0);
+    }
+
+    #[test]
+    fn test_validate_self_tuning_config() {
+        assert!(validate_self_tuning_config(&SelfTuningConfig::default()).is_ok());
+
+        let nested = SelfTuningConfig {
+            base_model: ModelKind::SelfTuning,
+            ..Default::default()
+        };
+        assert!(validate_self_tuning_config(&nested).is_err());
+
+        let bad_target = SelfTuningConfig {
+            target_inclusion_rate: 1.0,
+            ..Default::default()
+        };
+        assert!(validate_self_tuning_config(&bad_target).is_err());
+
+        let bad_bounds = SelfTuningConfig {
+            min_premium: 0.5,
+            max_premium: 0.1,
+            ..Default::default()
+        };
+        assert!(validate_self_tuning_config(&bad_bounds).is_err());
+    }
+}
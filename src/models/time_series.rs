@@ -5,9 +5,10 @@ This approach uses simple linear regression to identify trends in gas prices and
 How it works: This algorithm calculates the median gas price for each block, performs linear regression to identify the trend, and extrapolates to predict the next value. It's particularly useful when gas prices show a consistent trend over time (either increasing or decreasing).
 */
 
+use crate::config::Weighting;
 use crate::models::{FromBlock, ModelError, Prediction};
 use crate::types::Settlement;
-use crate::{distribution::BlockDistribution, utils::round_to_9_places};
+use crate::{blocks::gwei_to_wei, distribution::BlockDistribution};
 
 use super::moving_average::get_prediction_swma;
 
@@ -24,7 +25,7 @@ pub fn get_prediction_time_series(
     let num_blocks = 20.min(block_distributions.len());
     if num_blocks < 3 {
         // Not enough data for time series, fall back to SWMA
-        return get_prediction_swma(block_distributions, latest_block);
+        return get_prediction_swma(block_distributions, Weighting::Count, latest_block);
     }
 
     let blocks_to_consider = &block_distributions[block_distributions.len() - num_blocks..];
@@ -39,7 +40,7 @@ pub fn get_prediction_time_series(
         // Calculate weighted median
         let mut all_txs: Vec<(f64, u32)> = block
             .iter()
-            .map(|bucket| (bucket.gwei, bucket.count))
+            .map(|bucket| (bucket.gwei(), bucket.count))
             .collect();
 
         all_txs.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
@@ -102,7 +103,7 @@ pub fn get_prediction_time_series(
     let predicted_price = predicted_price.min(reasonable_max);
 
     Ok((
-        round_to_9_places(predicted_price),
+        gwei_to_wei(predicted_price),
         Settlement::Fast,
         latest_block + 1,
     ))
@@ -0,0 +1,288 @@
+use crate::rpc::{Block, RpcClient};
+use alloy::primitives::{keccak256, U256};
+use anyhow::{anyhow, Result};
+
+/// L1Block predeploy holding the L1 fee parameters on OP stack chains
+pub const L1_BLOCK_ADDRESS: &str = "0x4200000000000000000000000000000000000015";
+/// OP stack deposit transaction type, used for L1 deposits and the L1 attributes transaction
+pub const OP_DEPOSIT_TX_TYPE: u8 = 0x7e;
+
+/// Packed L1 attributes calldata functions since Ecotone. Later upgrades only append fields.
+const L1_ATTRIBUTES_FUNCTIONS: [&str; 3] = [
+    "setL1BlockValuesEcotone()",
+    "setL1BlockValuesIsthmus()",
+    "setL1BlockValuesJovian()",
+];
+/// Selector followed by the fee scalars, sequence number, L1 timestamp and number, L1 base fee,
+/// L1 blob base fee, L1 block hash and batcher hash
+const L1_ATTRIBUTES_MIN_LENGTH: usize = 164;
+
+/// Scale of the fee scalars, which are fixed point with 6 decimals
+const FEE_SCALAR_DECIMALS: u128 = 1_000_000;
+/// L1 calldata gas per non-zero byte, which the base fee scalar is relative to
+const CALLDATA_GAS_PER_BYTE: u128 = 16;
+
+/// L1 fee parameters of an OP stack chain since Ecotone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct L1FeeParams {
+    pub l1_base_fee: u128,
+    pub l1_blob_base_fee: u128,
+    pub base_fee_scalar: u32,
+    pub blob_base_fee_scalar: u32,
+}
+
+impl L1FeeParams {
+    /// L1 data fee in wei per byte of compressed transaction data:
+    /// `(16 * base_fee_scalar * l1_base_fee + blob_base_fee_scalar * l1_blob_base_fee) / 1e6`
+    pub fn data_fee_per_byte(&self) -> u128 {
+        let base_fee = CALLDATA_GAS_PER_BYTE
+            .saturating_mul(self.base_fee_scalar as u128)
+            .saturating_mul(self.l1_base_fee);
+        let blob_base_fee =
+            (self.blob_base_fee_scalar as u128).saturating_mul(self.l1_blob_base_fee);
+
+        base_fee.saturating_add(blob_base_fee) / FEE_SCALAR_DECIMALS
+    }
+
+    /// Decodes the packed calldata of the L1 attributes deposit transaction
+    pub fn from_l1_attributes(input: &[u8]) -> Option<Self> {
+        if input.len() < L1_ATTRIBUTES_MIN_LENGTH
+            || !L1_ATTRIBUTES_FUNCTIONS
+                .iter()
+                .any(|function| input[..4] == selector(function))
+        {
+            return None;
+        }
+
+        Some(Self {
+            base_fee_scalar: u32::from_be_bytes(input[4..8].try_into().ok()?),
+            blob_base_fee_scalar: u32::from_be_bytes(input[8..12].try_into().ok()?),
+            l1_base_fee: word_to_u128(&input[36..68]),
+            l1_blob_base_fee: word_to_u128(&input[68..100]),
+        })
+    }
+
+    /// Reads the parameters from the L1 attributes transaction of a block with full transactions
+    pub fn from_block(block: &Block) -> Option<Self> {
+        block
+            .transactions
+            .iter()
+            .filter(|tx| {
+                tx.tx_type == Some(OP_DEPOSIT_TX_TYPE)
+                    && tx
+                        .to
+                        .as_deref()
+                        .is_some_and(|to| to.eq_ignore_ascii_case(L1_BLOCK_ADDRESS))
+            })
+            .find_map(|tx| Self::from_l1_attributes(&tx.input))
+    }
+
+    /// Reads the parameters from the L1Block predeploy at the given block
+    pub async fn fetch(client: &RpcClient, block_number: u64) -> Result<Self> {
+        let call = |function: &'static str| async move {
+            let output = client
+                .call(L1_BLOCK_ADDRESS, &selector(function), block_number)
+                .await?;
+            if output.len() != 32 {
+                return Err(anyhow!("Unexpected L1Block {function} output: {output}"));
+            }
+            Ok(word_to_u128(&output))
+        };
+
+        Ok(Self {
+            l1_base_fee: call("basefee()").await?,
+            l1_blob_base_fee: call("blobBaseFee()").await?,
+            base_fee_scalar: call("baseFeeScalar()").await?.try_into()?,
+            blob_base_fee_scalar: call("blobBaseFeeScalar()").await?.try_into()?,
+        })
+    }
+}
+
+fn selector(function: &str) -> [u8; 4] {
+    let hash = keccak256(function.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// A big endian uint256 word as u128, saturating for values above `u128::MAX`
+fn word_to_u128(word: &[u8]) -> u128 {
+    U256::from_be_slice(word).saturating_to()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpc::Transaction;
+    use alloy::{hex, primitives::Bytes};
+
+    /// L1 attributes calldata of an Ecotone block: base fee scalar 2269, blob base fee scalar
+    /// 1055762, L1 base fee 7 gwei and L1 blob base fee 1 wei
+    fn ecotone_l1_attributes() -> Vec<u8> {
+        let mut input = selector("setL1BlockValuesEcotone()").to_vec();
+        input.extend_from_slice(&2269_u32.to_be_bytes());
+        input.extend_from_slice(&1_055_762_u32.to_be_bytes());
+        input.extend_from_slice(&3_u64.to_be_bytes());
+        input.extend_from_slice(&1_718_000_000_u64.to_be_bytes());
+        input.extend_from_slice(&20_100_000_u64.to_be_bytes());
+        input.extend_from_slice(&U256::from(7_000_000_000_u64).to_be_bytes::<32>());
+        input.extend_from_slice(&U256::from(1).to_be_bytes::<32>());
+        input.extend_from_slice(&[0xaa; 32]);
+        input.extend_from_slice(&[0xbb; 32]);
+        input
+    }
+
+    #[test]
+    fn test_selectors() {
+        assert_eq!(selector("setL1BlockValuesEcotone()"), hex!("440a5e20"));
+        assert_eq!(selector("basefee()"), hex!("5cf24969"));
+    }
+
+    #[test]
+    fn test_from_l1_attributes() {
+        let input = ecotone_l1_attributes();
+        assert_eq!(input.len(), L1_ATTRIBUTES_MIN_LENGTH);
+
+        let params = L1FeeParams::from_l1_attributes(&input).unwrap();
+        assert_eq!(
+            params,
+            L1FeeParams {
+                l1_base_fee: 7_000_000_000,
+                l1_blob_base_fee: 1,
+                base_fee_scalar: 2269,
+                blob_base_fee_scalar: 1_055_762,
+            }
+        );
+
+        // Isthmus appends the operator fee parameters
+        let mut isthmus = input.clone();
+        isthmus[..4].copy_from_slice(&selector("setL1BlockValuesIsthmus()"));
+        isthmus.extend_from_slice(&[0; 12]);
+        assert_eq!(L1FeeParams::from_l1_attributes(&isthmus), Some(params));
+
+        // Pre-Ecotone calldata and truncated input
+        let mut bedrock = input.clone();
+        bedrock[..4].copy_from_slice(&selector(
+            "setL1BlockValues(uint64,uint64,uint256,bytes32,uint64,bytes32,uint256,uint256)",
+        ));
+        assert_eq!(L1FeeParams::from_l1_attributes(&bedrock), None);
+        assert_eq!(L1FeeParams::from_l1_attributes(&input[..100]), None);
+    }
+
+    #[test]
+    fn test_from_block() {
+        let transaction = |tx_type: u8, to: &str, input: Vec<u8>| Transaction {
+            hash: "0x1".to_string(),
+            tx_type: Some(tx_type),
+            from: None,
+            nonce: None,
+            to: Some(to.to_string()),
+            gas_price: None,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            gas: None,
+            gas_used: None,
+            max_fee_per_blob_gas: None,
+            blob_count: 0,
+            input: input.into(),
+        };
+        let mut block = Block {
+            number: 1,
+            timestamp: chrono::DateTime::from_timestamp(1_718_000_000, 0).unwrap(),
+            gas_used: 0,
+            gas_limit: 30_000_000,
+            base_fee_per_gas: Some(1_000_000),
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            extra_data: Bytes::new(),
+            transactions: vec![
+                // A user deposit to another address
+                transaction(
+                    OP_DEPOSIT_TX_TYPE,
+                    "0x4200000000000000000000000000000000000016",
+                    ecotone_l1_attributes(),
+                ),
+                transaction(
+                    OP_DEPOSIT_TX_TYPE,
+                    L1_BLOCK_ADDRESS,
+                    ecotone_l1_attributes(),
+                ),
+            ],
+        };
+
+        assert_eq!(
+            L1FeeParams::from_block(&block).map(|params| params.base_fee_scalar),
+            Some(2269)
+        );
+
+        block.transactions.remove(1);
+        assert_eq!(L1FeeParams::from_block(&block), None);
+    }
+
+    #[test]
+    fn test_data_fee_per_byte() {
+        let params = L1FeeParams::from_l1_attributes(&ecotone_l1_attributes()).unwrap();
+
+        // (16 * 2269 * 7 gwei + 1055762 * 1) / 1e6
+        assert_eq!(params.data_fee_per_byte(), 254_128_001);
+
+        let blob_heavy = L1FeeParams {
+            l1_blob_base_fee: 1_000_000_000,
+            ..params
+        };
+        assert_eq!(blob_heavy.data_fee_per_byte(), 254_128_000 + 1_055_762_000);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_at_block() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let client = RpcClient::new(format!("http://{}", listener.local_addr().unwrap()));
+        let block_tags = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let block_tags_clone = block_tags.clone();
+
+        // Answers every eth_call with 7 and records the block it was made at
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = vec![];
+                let mut chunk = [0u8; 1024];
+
+                let body = loop {
+                    let n = stream.read(&mut chunk).await.unwrap();
+                    request.extend_from_slice(&chunk[..n]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    if let Some((_, body)) = text.split_once("\r\n\r\n") {
+                        if serde_json::from_str::<serde_json::Value>(body).is_ok() {
+                            break body.to_string();
+                        }
+                    }
+                };
+
+                let request: serde_json::Value = serde_json::from_str(&body).unwrap();
+                block_tags_clone
+                    .lock()
+                    .unwrap()
+                    .push(request["params"][1].as_str().unwrap().to_string());
+
+                let body = serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "result": format!("0x{:064x}", 7)
+                })
+                .to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        let params = L1FeeParams::fetch(&client, 0x1c9c380).await.unwrap();
+        assert_eq!(params.l1_base_fee, 7);
+        assert_eq!(params.blob_base_fee_scalar, 7);
+
+        assert_eq!(*block_tags.lock().unwrap(), vec!["0x1c9c380"; 4]);
+    }
+}
@@ -0,0 +1,138 @@
+use std::collections::VecDeque;
+
+/// Number of recent block arrivals the schedule is derived from
+const MAX_NUM_ARRIVALS: usize = 128;
+/// Arrivals needed before the schedule follows them instead of the defaults
+const MIN_NUM_ARRIVALS: usize = 8;
+/// Retry interval until enough arrivals are observed
+const DEFAULT_RETRY_INTERVAL_MS: u64 = 250;
+const MIN_RETRY_INTERVAL_MS: u64 = 25;
+/// Polls spread over the usual range of arrival delays
+const RETRIES_PER_ARRIVAL_SPREAD: u64 = 4;
+/// Arrival delay quantiles bounding the usual range of arrivals
+const EARLY_ARRIVAL_QUANTILE: f64 = 0.1;
+const LATE_ARRIVAL_QUANTILE: f64 = 0.9;
+
+/// Schedules `eth_getBlockByNumber` polls within a block from the observed delays between
+/// block timestamps and the agent receiving the block.
+/// The first poll is made just before the earliest usual arrival, and retries are spread over
+/// the usual range of arrivals. Once a block is later than usual, eg. after a missed slot,
+/// retries back off up to a quarter of the block time.
+#[derive(Debug, Clone, Default)]
+pub struct PollSchedule {
+    /// Detection latencies of recent blocks in milliseconds, oldest first
+    arrival_delays: VecDeque<u64>,
+}
+
+impl PollSchedule {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records the delay between a new block's timestamp and the agent receiving it
+    pub fn observe_arrival(&mut self, delay_ms: u64) {
+        self.arrival_delays.push_back(delay_ms);
+
+        if self.arrival_delays.len() > MAX_NUM_ARRIVALS {
+            self.arrival_delays.pop_front();
+        }
+    }
+
+    /// Time after the expected timestamp of the next block to make the first poll
+    pub fn first_poll_delay_ms(&self, block_time_ms: u64) -> u64 {
+        let Some((early, _)) = self.arrival_range() else {
+            return 0;
+        };
+
+        early.saturating_sub(self.retry_interval_ms(block_time_ms, 0))
+    }
+
+    /// Time to wait before polling again when no new block was found `elapsed_ms` after the
+    /// expected timestamp of the next block
+    pub fn retry_interval_ms(&self, block_time_ms: u64, elapsed_ms: u64) -> u64 {
+        let max_interval_ms = (block_time_ms / 4).max(MIN_RETRY_INTERVAL_MS);
+
+        let Some((early, late)) = self.arrival_range() else {
+            return DEFAULT_RETRY_INTERVAL_MS.clamp(MIN_RETRY_INTERVAL_MS, max_interval_ms);
+        };
+
+        let interval_ms = (late - early) / RETRIES_PER_ARRIVAL_SPREAD;
+        // Back off in proportion to how late the block is
+        let backoff_ms = elapsed_ms.saturating_sub(late) / RETRIES_PER_ARRIVAL_SPREAD;
+
+        interval_ms
+            .max(backoff_ms)
+            .clamp(MIN_RETRY_INTERVAL_MS, max_interval_ms)
+    }
+
+    /// Early and late quantiles of the observed arrival delays
+    fn arrival_range(&self) -> Option<(u64, u64)> {
+        if self.arrival_delays.len() < MIN_NUM_ARRIVALS {
+            return None;
+        }
+
+        let mut delays: Vec<u64> = self.arrival_delays.iter().copied().collect();
+        delays.sort_unstable();
+
+        let quantile = |q: f64| delays[((delays.len() - 1) as f64 * q).round() as usize];
+
+        Some((
+            quantile(EARLY_ARRIVAL_QUANTILE),
+            quantile(LATE_ARRIVAL_QUANTILE),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_arrivals(delays: impl IntoIterator<Item = u64>) -> PollSchedule {
+        let mut schedule = PollSchedule::new();
+        for delay in delays {
+            schedule.observe_arrival(delay);
+        }
+        schedule
+    }
+
+    #[test]
+    fn test_defaults_without_arrivals() {
+        let schedule = with_arrivals([400; MIN_NUM_ARRIVALS - 1]);
+
+        assert_eq!(schedule.first_poll_delay_ms(12_000), 0);
+        assert_eq!(schedule.retry_interval_ms(12_000, 0), 250);
+        // Capped to a quarter of the block time on fast chains
+        assert_eq!(schedule.retry_interval_ms(250, 0), 62);
+    }
+
+    #[test]
+    fn test_schedule_follows_arrivals() {
+        // Blocks arriving 200ms to 1000ms after their timestamp
+        let schedule = with_arrivals((0..=100).map(|i| 200 + i * 8));
+
+        // Spread of 640ms between the 10th and 90th percentiles
+        assert_eq!(schedule.retry_interval_ms(12_000, 0), 160);
+        assert_eq!(schedule.first_poll_delay_ms(12_000), 280 - 160);
+
+        // Backs off once the block is later than usual
+        assert_eq!(schedule.retry_interval_ms(12_000, 920), 160);
+        assert_eq!(schedule.retry_interval_ms(12_000, 2_920), 500);
+        assert_eq!(schedule.retry_interval_ms(12_000, 20_000), 3_000);
+
+        // Tight arrivals poll often, but no more than the minimum interval
+        let schedule = with_arrivals([300; 20]);
+        assert_eq!(schedule.retry_interval_ms(2_000, 0), MIN_RETRY_INTERVAL_MS);
+        assert_eq!(schedule.first_poll_delay_ms(2_000), 275);
+    }
+
+    #[test]
+    fn test_rolling_window() {
+        let mut schedule = with_arrivals([5_000; MAX_NUM_ARRIVALS]);
+        assert_eq!(schedule.first_poll_delay_ms(12_000), 5_000 - 25);
+
+        for _ in 0..MAX_NUM_ARRIVALS {
+            schedule.observe_arrival(100);
+        }
+        assert_eq!(schedule.first_poll_delay_ms(12_000), 75);
+    }
+}
@@ -1,4 +1,6 @@
 use super::constants::AGENT_PUBLISH_PATH;
+use crate::chain::types::OracleChain;
+use crate::signer::AgentSigner;
 use crate::types::AgentPayload;
 use anyhow::Result;
 use reqwest::Client;
@@ -7,11 +9,12 @@ use serde_json::json;
 pub async fn publish_agent_payload(
     client: &Client,
     collector_endpoint: &str,
-    signer_key: &str,
+    signer: &AgentSigner,
+    chain: OracleChain,
     payload: &AgentPayload,
 ) -> Result<()> {
-    let signature = payload.sign(signer_key).await?;
-    let network_signature = payload.clone().network_signature(signer_key)?;
+    let signature = payload.sign(signer).await?;
+    let network_signature = payload.network_signature(signer, chain).await?;
 
     let json = json!({
         "payload": payload,
@@ -1,14 +1,15 @@
+use crate::op_stack::OP_DEPOSIT_TX_TYPE;
+use alloy::primitives::Bytes;
 use anyhow::{anyhow, Ok, Result};
 use chrono::{DateTime, TimeZone, Utc};
 use rand::Rng;
 use reqwest::{Client, Url};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::fmt::Debug;
 use tracing::error;
 
-use crate::blocks::wei_to_gwei;
-
 #[derive(Clone)]
 pub struct RpcClient {
     host: String,
@@ -76,16 +77,69 @@ impl RpcClient {
         Ok(block)
     }
 
+    pub async fn get_block_by_number(&self, block_number: u64) -> Result<Block> {
+        let value: Value = self
+            .request(&self.create_request(
+                "eth_getBlockByNumber",
+                Some(json!([format!("0x{block_number:x}"), true])),
+            ))
+            .await?;
+
+        parse_block(&value)
+    }
+
     pub async fn get_pending_block(
         &self,
         method: &str,
         params: Option<Value>,
-    ) -> Result<Vec<Transaction>> {
+    ) -> Result<PendingTransactions> {
         let value: Value = self.request(&self.create_request(method, params)).await?;
 
-        let transactions = parse_transactions(&value)?;
+        parse_pending_block(&value)
+    }
+
+    /// Returns the executable transactions of the node's transaction pool
+    pub async fn get_txpool_content(&self) -> Result<PendingTransactions> {
+        let value: Value = self
+            .request(&self.create_request("txpool_content", None))
+            .await?;
+
+        Ok(PendingTransactions {
+            transactions: parse_txpool_content(&value)?,
+            parent_number: None,
+        })
+    }
+
+    /// Returns the `gasUsed` of every transaction in the block keyed by transaction hash
+    pub async fn get_block_receipts(&self, block_number: u64) -> Result<HashMap<String, u64>> {
+        let value: Value = self
+            .request(&self.create_request(
+                "eth_getBlockReceipts",
+                Some(json!([format!("0x{block_number:x}")])),
+            ))
+            .await?;
 
-        Ok(transactions)
+        parse_receipts_gas_used(&value)
+    }
+
+    /// Calls a contract at the state of the given block
+    pub async fn call(&self, to: &str, data: &[u8], block_number: u64) -> Result<Bytes> {
+        let value: Value = self
+            .request(&self.create_request(
+                "eth_call",
+                Some(json!([
+                    {"to": to, "data": Bytes::copy_from_slice(data)},
+                    format!("0x{block_number:x}")
+                ])),
+            ))
+            .await?;
+
+        let output = value
+            .as_str()
+            .ok_or(anyhow!("Missing or invalid eth_call output"))?
+            .parse::<Bytes>()?;
+
+        Ok(output)
     }
 
     pub async fn get_chain_id(&self) -> Result<u64> {
@@ -99,16 +153,16 @@ impl RpcClient {
         Ok(chain_id)
     }
 
-    pub async fn get_node_gas_price_estimate(&self) -> Result<f64> {
+    /// Returns the node's gas price estimate in wei
+    pub async fn get_node_gas_price_estimate(&self) -> Result<u128> {
         let value: Value = self
             .request(&self.create_request("eth_gasPrice", None))
             .await?;
 
         let hex = value.as_str().unwrap().to_string();
         let wei = parse_hex_to_u128(&hex);
-        let gwei = wei_to_gwei(wei)?;
 
-        Ok(gwei)
+        Ok(wei)
     }
 }
 
@@ -177,9 +231,27 @@ pub struct Block {
     pub gas_limit: u64,
     pub gas_used: u64,
     pub base_fee_per_gas: Option<u64>,
+    /// Total blob gas used by the block's blob transactions (EIP-4844)
+    pub blob_gas_used: Option<u64>,
+    /// Blob gas above the target accumulated over previous blocks (EIP-4844)
+    pub excess_blob_gas: Option<u64>,
+    /// Arbitrary header data, OP stack chains encode their EIP-1559 parameters here
+    #[serde(default)]
+    pub extra_data: Bytes,
     pub transactions: Vec<Transaction>,
 }
 
+impl Block {
+    /// Sets the `gas_used` of each transaction from a map of transaction hash to receipt gas used
+    pub fn apply_receipts(&mut self, gas_used: &HashMap<String, u64>) {
+        for tx in self.transactions.iter_mut() {
+            if let Some(gas_used) = gas_used.get(&tx.hash) {
+                tx.gas_used = Some(*gas_used);
+            }
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct BlockHeader {
@@ -188,6 +260,10 @@ pub struct BlockHeader {
     pub gas_limit: u64,
     pub gas_used: u64,
     pub base_fee_per_gas: Option<u64>,
+    pub blob_gas_used: Option<u64>,
+    pub excess_blob_gas: Option<u64>,
+    #[serde(default)]
+    pub extra_data: Bytes,
 }
 
 impl From<Block> for BlockHeader {
@@ -198,6 +274,9 @@ impl From<Block> for BlockHeader {
             gas_limit: block.gas_limit,
             gas_used: block.gas_used,
             base_fee_per_gas: block.base_fee_per_gas,
+            blob_gas_used: block.blob_gas_used,
+            excess_blob_gas: block.excess_blob_gas,
+            extra_data: block.extra_data,
         }
     }
 }
@@ -206,9 +285,43 @@ impl From<Block> for BlockHeader {
 #[serde(rename_all = "camelCase")]
 pub struct Transaction {
     pub hash: String,
+    /// The EIP-2718 transaction type
+    pub tx_type: Option<u8>,
+    /// The sender address (lowercase hex)
+    pub from: Option<String>,
+    /// The sender's nonce
+    #[serde(default)]
+    pub nonce: Option<u64>,
+    /// The recipient address (lowercase hex), `None` for contract creations
+    pub to: Option<String>,
     pub gas_price: Option<u128>,
     pub max_fee_per_gas: Option<u128>,
     pub max_priority_fee_per_gas: Option<u128>,
+    /// The gas limit of the transaction
+    pub gas: Option<u64>,
+    /// The gas used by the transaction, only available from receipts
+    pub gas_used: Option<u64>,
+    /// The maximum fee per blob gas of a blob transaction (type 3)
+    pub max_fee_per_blob_gas: Option<u128>,
+    /// Number of blobs carried by a blob transaction (type 3)
+    pub blob_count: usize,
+    /// Calldata, only kept for OP stack deposit transactions to limit memory use
+    #[serde(default)]
+    pub input: Bytes,
+}
+
+/// Pending transactions, with the number of the block they build on when the source reports it
+#[derive(Debug, Clone)]
+pub struct PendingTransactions {
+    pub transactions: Vec<Transaction>,
+    pub parent_number: Option<u64>,
+}
+
+impl Transaction {
+    /// The gas this transaction consumes, preferring the receipt value over the gas limit
+    pub fn gas_weight(&self) -> u64 {
+        self.gas_used.or(self.gas).unwrap_or(0)
+    }
 }
 
 pub fn get_rpc_client(rpc_url: Url) -> RpcClient {
@@ -220,6 +333,11 @@ pub async fn get_latest_block(client: &RpcClient) -> Result<Block> {
     Ok(block)
 }
 
+fn parse_hex_to_u8(hex_str: &str) -> u8 {
+    let cleaned = hex_str.strip_prefix("0x").unwrap_or(hex_str);
+    u8::from_str_radix(cleaned, 16).unwrap_or(0)
+}
+
 fn parse_hex_to_u64(hex_str: &str) -> u64 {
     let cleaned = hex_str.strip_prefix("0x").unwrap_or(hex_str);
     u64::from_str_radix(cleaned, 16).unwrap_or(0)
@@ -261,6 +379,15 @@ pub fn parse_block(value: &Value) -> Result<Block> {
         .map(parse_hex_to_u64)
         .ok_or(anyhow!("Missing or invalid gasLimit field"))?;
 
+    // Parse the blob gas fields (optional, only present after EIP-4844)
+    let blob_gas_used = value["blobGasUsed"].as_str().map(parse_hex_to_u64);
+    let excess_blob_gas = value["excessBlobGas"].as_str().map(parse_hex_to_u64);
+
+    let extra_data = value["extraData"]
+        .as_str()
+        .and_then(|hex| hex.parse::<Bytes>().ok())
+        .unwrap_or_default();
+
     // Parse transactions
     let transactions = parse_transactions(value)?;
 
@@ -270,53 +397,156 @@ pub fn parse_block(value: &Value) -> Result<Block> {
         gas_used,
         gas_limit,
         base_fee_per_gas,
+        blob_gas_used,
+        excess_blob_gas,
+        extra_data,
         transactions,
     })
 }
 
 fn parse_transactions(block: &Value) -> Result<Vec<Transaction>> {
-    if let Some(txs_array) = block["transactions"].as_array() {
-        txs_array
-            .iter()
-            .map(|tx| {
-                let tx_hash = tx["hash"]
-                    .as_str()
-                    .ok_or(anyhow!("Missing or invalid transaction hash"))?
-                    .to_string();
-
-                // Parse fee fields
-                let gas_price = tx["gasPrice"].as_str().map(parse_hex_to_u128);
-                let max_fee_per_gas = tx["maxFeePerGas"].as_str().map(parse_hex_to_u128);
-                let max_priority_fee_per_gas =
-                    tx["maxPriorityFeePerGas"].as_str().map(parse_hex_to_u128);
-
-                // Validate gas pricing: either gas_price OR (max_fee_per_gas AND max_priority_fee_per_gas)
-                let has_legacy_pricing = gas_price.is_some();
-                let has_eip1559_pricing = max_fee_per_gas.is_some() && max_priority_fee_per_gas.is_some();
-
-                if !has_legacy_pricing && !has_eip1559_pricing {
-                    return Err(anyhow!(
-                        "Transaction {} missing valid gas pricing: must have either gasPrice or both maxFeePerGas and maxPriorityFeePerGas",
-                        tx_hash
-                    ));
-                }
+    block["transactions"]
+        .as_array()
+        .ok_or(anyhow!("Missing or invalid transactions array"))?
+        .iter()
+        .map(parse_transaction)
+        .collect()
+}
 
-                Ok(Transaction {
-                    hash: tx_hash,
-                    gas_price,
-                    max_fee_per_gas,
-                    max_priority_fee_per_gas,
-                })
-            })
-            .collect::<Result<Vec<Transaction>>>()
+/// Parses a pending block, which is numbered one above the block it builds on
+fn parse_pending_block(block: &Value) -> Result<PendingTransactions> {
+    let parent_number = block["number"]
+        .as_str()
+        .map(parse_hex_to_u64)
+        .and_then(|number| number.checked_sub(1));
+
+    Ok(PendingTransactions {
+        transactions: parse_pending_transactions(block)?,
+        parent_number,
+    })
+}
+
+fn parse_pending_transactions(block: &Value) -> Result<Vec<Transaction>> {
+    block["transactions"]
+        .as_array()
+        .ok_or(anyhow!("Missing or invalid transactions array"))?
+        .iter()
+        .map(parse_pending_transaction)
+        .collect()
+}
+
+/// Parses the transaction pool of a Geth compatible `txpool_content` response, where pending
+/// transactions are nested by sender and nonce. Queued transactions are left out, as they cannot
+/// be included until the transactions before them are.
+fn parse_txpool_content(value: &Value) -> Result<Vec<Transaction>> {
+    value["pending"]
+        .as_object()
+        .ok_or(anyhow!("Missing or invalid pending transactions"))?
+        .values()
+        .filter_map(Value::as_object)
+        .flat_map(|by_nonce| by_nonce.values())
+        .map(parse_pending_transaction)
+        .collect()
+}
+
+/// Parses a transaction that is not included in a block yet. Nodes report the fee cap of
+/// pending dynamic fee transactions as their `gasPrice`, so it is dropped and they are priced by
+/// their fee cap and priority fee against the next base fee instead.
+pub fn parse_pending_transaction(tx: &Value) -> Result<Transaction> {
+    let mut transaction = parse_transaction(tx)?;
+
+    if transaction.max_fee_per_gas.is_some() && transaction.max_priority_fee_per_gas.is_some() {
+        transaction.gas_price = None;
+    }
+
+    Ok(transaction)
+}
+
+pub fn parse_transaction(tx: &Value) -> Result<Transaction> {
+    let tx_hash = tx["hash"]
+        .as_str()
+        .ok_or(anyhow!("Missing or invalid transaction hash"))?
+        .to_string();
+
+    let tx_type = tx["type"].as_str().map(parse_hex_to_u8);
+    let from = tx["from"].as_str().map(|from| from.to_lowercase());
+    let nonce = tx["nonce"].as_str().map(parse_hex_to_u64);
+    let to = tx["to"].as_str().map(|to| to.to_lowercase());
+    let input = if tx_type == Some(OP_DEPOSIT_TX_TYPE) {
+        tx["input"]
+            .as_str()
+            .and_then(|hex| hex.parse::<Bytes>().ok())
+            .unwrap_or_default()
     } else {
-        Err(anyhow!("Missing or invalid transactions array"))
+        Bytes::new()
+    };
+
+    // Parse fee fields
+    let gas_price = tx["gasPrice"].as_str().map(parse_hex_to_u128);
+    let max_fee_per_gas = tx["maxFeePerGas"].as_str().map(parse_hex_to_u128);
+    let max_priority_fee_per_gas = tx["maxPriorityFeePerGas"].as_str().map(parse_hex_to_u128);
+    let gas = tx["gas"].as_str().map(parse_hex_to_u64);
+    let max_fee_per_blob_gas = tx["maxFeePerBlobGas"].as_str().map(parse_hex_to_u128);
+    let blob_count = tx["blobVersionedHashes"].as_array().map_or(0, Vec::len);
+
+    // Validate gas pricing: either gas_price OR (max_fee_per_gas AND max_priority_fee_per_gas)
+    let has_legacy_pricing = gas_price.is_some();
+    let has_eip1559_pricing = max_fee_per_gas.is_some() && max_priority_fee_per_gas.is_some();
+
+    if !has_legacy_pricing && !has_eip1559_pricing {
+        return Err(anyhow!(
+            "Transaction {} missing valid gas pricing: must have either gasPrice or both maxFeePerGas and maxPriorityFeePerGas",
+            tx_hash
+        ));
     }
+
+    Ok(Transaction {
+        hash: tx_hash,
+        tx_type,
+        from,
+        nonce,
+        to,
+        gas_price,
+        max_fee_per_gas,
+        max_priority_fee_per_gas,
+        gas,
+        gas_used: None,
+        max_fee_per_blob_gas,
+        blob_count,
+        input,
+    })
+}
+
+fn parse_receipts_gas_used(receipts: &Value) -> Result<HashMap<String, u64>> {
+    let receipts = receipts
+        .as_array()
+        .ok_or(anyhow!("Missing or invalid receipts array"))?;
+
+    receipts
+        .iter()
+        .map(|receipt| {
+            let tx_hash = receipt["transactionHash"]
+                .as_str()
+                .ok_or(anyhow!("Missing or invalid receipt transaction hash"))?
+                .to_string();
+
+            let gas_used = receipt["gasUsed"]
+                .as_str()
+                .map(parse_hex_to_u64)
+                .ok_or(anyhow!(
+                    "Missing or invalid gasUsed for receipt {}",
+                    tx_hash
+                ))?;
+
+            Ok((tx_hash, gas_used))
+        })
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::blocks::calc_fee_wei;
     use serde_json::json;
 
     #[test]
@@ -491,4 +721,372 @@ mod tests {
             .to_string()
             .contains("Missing or invalid transactions array"));
     }
+
+    #[test]
+    fn test_parse_transactions_with_gas() {
+        let block_data = json!({
+            "transactions": [
+                {
+                    "hash": "0x6666666666666666",
+                    "gasPrice": "0x12a05f200",
+                    "gas": "0x5208"
+                },
+                {
+                    "hash": "0x7777777777777777",
+                    "gasPrice": "0x12a05f200"
+                }
+            ]
+        });
+
+        let result = parse_transactions(&block_data).unwrap();
+        assert_eq!(result[0].gas, Some(21000));
+        assert_eq!(result[0].gas_weight(), 21000);
+        assert_eq!(result[1].gas, None);
+        assert_eq!(result[1].gas_weight(), 0);
+    }
+
+    #[test]
+    fn test_parse_receipts_and_apply_to_block() {
+        let receipts = json!([
+            {
+                "transactionHash": "0x8888888888888888",
+                "gasUsed": "0x5208"
+            },
+            {
+                "transactionHash": "0x9999999999999999",
+                "gasUsed": "0x30d40"
+            }
+        ]);
+
+        let gas_used = parse_receipts_gas_used(&receipts).unwrap();
+        assert_eq!(gas_used.len(), 2);
+
+        let mut block = parse_block(&json!({
+            "number": "0x1",
+            "timestamp": "0x6553f100",
+            "gasUsed": "0x3d090",
+            "gasLimit": "0x1c9c380",
+            "baseFeePerGas": "0x3b9aca00",
+            "transactions": [
+                {
+                    "hash": "0x8888888888888888",
+                    "gasPrice": "0x12a05f200",
+                    "gas": "0x7530"
+                },
+                {
+                    "hash": "0x9999999999999999",
+                    "gasPrice": "0x12a05f200",
+                    "gas": "0x493e0"
+                }
+            ]
+        }))
+        .unwrap();
+
+        block.apply_receipts(&gas_used);
+
+        assert_eq!(block.transactions[0].gas_used, Some(21000));
+        assert_eq!(block.transactions[0].gas_weight(), 21000);
+        assert_eq!(block.transactions[1].gas_used, Some(200000));
+        assert_eq!(block.transactions[1].gas_weight(), 200000);
+    }
+
+    #[test]
+    fn test_parse_receipts_missing_gas_used() {
+        let receipts = json!([{ "transactionHash": "0xaaaaaaaaaaaaaaaa" }]);
+
+        let result = parse_receipts_gas_used(&receipts);
+        assert!(result.is_err());
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Missing or invalid gasUsed"));
+    }
+
+    #[test]
+    fn test_parse_transactions_type_and_sender() {
+        let block_data = json!({
+            "transactions": [
+                {
+                    "hash": "0xbbbbbbbbbbbbbbbb",
+                    "type": "0x7e",
+                    "from": "0xDeaDDEaDDeAdDeAdDEAdDEaddeAddEAdDEAd0001",
+                    "gasPrice": "0x0"
+                },
+                {
+                    "hash": "0xcccccccccccccccc",
+                    "type": "0x2",
+                    "maxFeePerGas": "0x174876e800",
+                    "maxPriorityFeePerGas": "0x3b9aca00"
+                },
+                {
+                    "hash": "0xdddddddddddddddd",
+                    "gasPrice": "0x12a05f200"
+                }
+            ]
+        });
+
+        let result = parse_transactions(&block_data).unwrap();
+        assert_eq!(result[0].tx_type, Some(0x7e));
+        assert_eq!(
+            result[0].from.as_deref(),
+            Some("0xdeaddeaddeaddeaddeaddeaddeaddeaddead0001")
+        );
+        assert_eq!(result[1].tx_type, Some(2));
+        assert_eq!(result[1].from, None);
+        assert_eq!(result[2].tx_type, None);
+    }
+
+    #[test]
+    fn test_parse_block_extra_data() {
+        let block = parse_block(&json!({
+            "number": "0x1",
+            "timestamp": "0x6553f100",
+            "gasUsed": "0xe4e1c0",
+            "gasLimit": "0x1c9c380",
+            "extraData": "0x00000000fa00000006",
+            "transactions": []
+        }))
+        .unwrap();
+
+        assert_eq!(
+            block.extra_data.as_ref(),
+            &[0x00, 0x00, 0x00, 0x00, 0xfa, 0x00, 0x00, 0x00, 0x06]
+        );
+        assert_eq!(BlockHeader::from(block).extra_data.len(), 9);
+    }
+
+    #[test]
+    fn test_parse_pending_block_transactions() {
+        // `eth_getBlockByNumber("pending", true)` from Geth
+        let pending_block = json!({
+            "number": "0x1502a1c",
+            "transactions": [
+                {
+                    "hash": "0x1",
+                    "type": "0x2",
+                    "gas": "0x5208",
+                    "gasPrice": "0x6fc23ac00",
+                    "maxFeePerGas": "0x6fc23ac00",
+                    "maxPriorityFeePerGas": "0x5f5e100"
+                },
+                {
+                    "hash": "0x2",
+                    "type": "0x0",
+                    "gas": "0x5208",
+                    "gasPrice": "0x2540be400"
+                }
+            ]
+        });
+
+        let pending = parse_pending_block(&pending_block).unwrap();
+        assert_eq!(pending.parent_number, Some(0x1502a1b));
+
+        let transactions = pending.transactions;
+        assert_eq!(transactions[0].gas_price, None);
+        assert_eq!(
+            calc_fee_wei(
+                &transactions[0].gas_price,
+                &transactions[0].max_fee_per_gas,
+                &transactions[0].max_priority_fee_per_gas,
+                &Some(3_000_000_000),
+            )
+            .unwrap(),
+            3_100_000_000
+        );
+        assert_eq!(transactions[1].gas_price, Some(10_000_000_000));
+
+        // Included transactions keep their effective gas price
+        let block = parse_transactions(&pending_block).unwrap();
+        assert_eq!(block[0].gas_price, Some(30_000_000_000));
+    }
+
+    #[test]
+    fn test_parse_txpool_content() {
+        let txpool = json!({
+            "pending": {
+                "0xAAAA000000000000000000000000000000000001": {
+                    "7": {
+                        "hash": "0x7",
+                        "type": "0x2",
+                        "from": "0xAAAA000000000000000000000000000000000001",
+                        "gas": "0x5208",
+                        "gasPrice": "0x174876e800",
+                        "maxFeePerGas": "0x174876e800",
+                        "maxPriorityFeePerGas": "0x3b9aca00"
+                    },
+                    "8": {
+                        "hash": "0x8",
+                        "type": "0x2",
+                        "from": "0xAAAA000000000000000000000000000000000001",
+                        "gas": "0x5208",
+                        "gasPrice": "0x174876e800",
+                        "maxFeePerGas": "0x174876e800",
+                        "maxPriorityFeePerGas": "0x77359400"
+                    }
+                },
+                "0xbbbb000000000000000000000000000000000002": {
+                    "0": {
+                        "hash": "0x0",
+                        "type": "0x0",
+                        "from": "0xbbbb000000000000000000000000000000000002",
+                        "gas": "0x5208",
+                        "gasPrice": "0x12a05f200"
+                    }
+                }
+            },
+            "queued": {
+                "0xcccc000000000000000000000000000000000003": {
+                    "5": {
+                        "hash": "0x5",
+                        "type": "0x0",
+                        "gasPrice": "0x1"
+                    }
+                }
+            }
+        });
+
+        let mut transactions = parse_txpool_content(&txpool).unwrap();
+        transactions.sort_by(|a, b| a.hash.cmp(&b.hash));
+
+        let hashes: Vec<&str> = transactions.iter().map(|tx| tx.hash.as_str()).collect();
+        assert_eq!(hashes, vec!["0x0", "0x7", "0x8"]);
+        assert_eq!(transactions[0].gas_price, Some(5_000_000_000));
+        assert_eq!(
+            transactions[2].max_priority_fee_per_gas,
+            Some(2_000_000_000)
+        );
+
+        // Dynamic fee transactions pay the next base fee plus their tip, not the fee cap Geth
+        // reports as their gas price
+        let effective_prices: Vec<u128> = transactions
+            .iter()
+            .map(|tx| {
+                calc_fee_wei(
+                    &tx.gas_price,
+                    &tx.max_fee_per_gas,
+                    &tx.max_priority_fee_per_gas,
+                    &Some(10_000_000_000),
+                )
+                .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            effective_prices,
+            vec![5_000_000_000, 11_000_000_000, 12_000_000_000]
+        );
+        assert_eq!(
+            transactions[1].from.as_deref(),
+            Some("0xaaaa000000000000000000000000000000000001")
+        );
+
+        // An empty pool
+        assert!(parse_txpool_content(&json!({"pending": {}, "queued": {}}))
+            .unwrap()
+            .is_empty());
+        assert!(parse_txpool_content(&json!({"queued": {}})).is_err());
+    }
+
+    #[test]
+    fn test_parse_deposit_transaction_input() {
+        let block = parse_block(&json!({
+            "number": "0x1",
+            "timestamp": "0x6553f100",
+            "gasUsed": "0xe4e1c0",
+            "gasLimit": "0x1c9c380",
+            "transactions": [
+                {
+                    "hash": "0xdeposit",
+                    "gasPrice": "0x0",
+                    "type": "0x7e",
+                    "to": "0x4200000000000000000000000000000000000015",
+                    "input": "0x440a5e20000008dd"
+                },
+                {
+                    "hash": "0xcall",
+                    "gasPrice": "0x3b9aca00",
+                    "type": "0x2",
+                    "to": "0xAbC0000000000000000000000000000000000001",
+                    "input": "0xa9059cbb"
+                },
+                {
+                    "hash": "0xcreate",
+                    "gasPrice": "0x3b9aca00",
+                    "type": "0x0",
+                    "input": "0x6080"
+                }
+            ]
+        }))
+        .unwrap();
+
+        let deposit = &block.transactions[0];
+        assert_eq!(
+            deposit.to.as_deref(),
+            Some("0x4200000000000000000000000000000000000015")
+        );
+        assert_eq!(
+            deposit.input.as_ref(),
+            &[0x44, 0x0a, 0x5e, 0x20, 0x00, 0x00, 0x08, 0xdd]
+        );
+
+        // Calldata of other transactions is dropped
+        let call = &block.transactions[1];
+        assert_eq!(
+            call.to.as_deref(),
+            Some("0xabc0000000000000000000000000000000000001")
+        );
+        assert!(call.input.is_empty());
+        assert_eq!(block.transactions[2].to, None);
+    }
+
+    #[test]
+    fn test_parse_block_with_blob_gas() {
+        let block_data = json!({
+            "number": "0x1",
+            "timestamp": "0x6553f100",
+            "gasUsed": "0xe4e1c0",
+            "gasLimit": "0x1c9c380",
+            "baseFeePerGas": "0x3b9aca00",
+            "blobGasUsed": "0x60000",
+            "excessBlobGas": "0x5f5e100",
+            "transactions": [
+                {
+                    "hash": "0xblob",
+                    "type": "0x3",
+                    "maxFeePerGas": "0x174876e800",
+                    "maxPriorityFeePerGas": "0x3b9aca00",
+                    "maxFeePerBlobGas": "0x3e8",
+                    "blobVersionedHashes": [
+                        "0x0100000000000000000000000000000000000000000000000000000000000001",
+                        "0x0100000000000000000000000000000000000000000000000000000000000002",
+                        "0x0100000000000000000000000000000000000000000000000000000000000003"
+                    ]
+                },
+                {
+                    "hash": "0xlegacy",
+                    "gasPrice": "0x12a05f200"
+                }
+            ]
+        });
+
+        let block = parse_block(&block_data).unwrap();
+        assert_eq!(block.blob_gas_used, Some(393_216));
+        assert_eq!(block.excess_blob_gas, Some(100_000_000));
+
+        assert_eq!(block.transactions[0].tx_type, Some(3));
+        assert_eq!(block.transactions[0].max_fee_per_blob_gas, Some(1_000));
+        assert_eq!(block.transactions[0].blob_count, 3);
+
+        assert_eq!(block.transactions[1].max_fee_per_blob_gas, None);
+        assert_eq!(block.transactions[1].blob_count, 0);
+
+        assert!(block.extra_data.is_empty());
+
+        // Blocks before EIP-4844 have no blob gas fields
+        let mut pre_cancun = block_data.clone();
+        pre_cancun.as_object_mut().unwrap().remove("blobGasUsed");
+        pre_cancun.as_object_mut().unwrap().remove("excessBlobGas");
+        let block = parse_block(&pre_cancun).unwrap();
+        assert_eq!(block.blob_gas_used, None);
+        assert_eq!(block.excess_blob_gas, None);
+    }
 }
@@ -22,6 +22,7 @@ pub async fn start_server<T: 'static + Send + Sync>(
     web::server(move || {
         let mut app = App::new()
             // ==== INTERNAL ==== //
+            .route("/metrics", get().to(responders::metrics::metrics))
             .route(
                 "/internal/probe/readiness",
                 get().to(responders::probe::readiness),
@@ -0,0 +1,15 @@
+use crate::metrics::encode_metrics;
+use ntex::web::HttpResponse;
+use tracing::error;
+
+pub async fn metrics() -> HttpResponse {
+    match encode_metrics() {
+        Ok(body) => HttpResponse::Ok()
+            .content_type("text/plain; version=0.0.4")
+            .body(body),
+        Err(e) => {
+            error!(error = %e, "Failed to encode metrics");
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
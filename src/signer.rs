@@ -0,0 +1,420 @@
+use crate::config::{ChainConfig, PasswordSource, SignerKey};
+use alloy::hex;
+use alloy::primitives::{Address, B256};
+use alloy::signers::k256::elliptic_curve::rand_core::OsRng;
+use alloy::signers::local::PrivateKeySigner;
+use alloy::signers::{Signature, Signer};
+use anyhow::{anyhow, Context, Result};
+use reqwest::Url;
+use serde_json::json;
+use std::collections::HashMap;
+use std::path::Path;
+use tracing::info;
+
+const REMOTE_SIGN_PATH: &str = "api/v1/eth1/sign";
+
+/// Signs the digests of an agent's payloads
+#[derive(Debug, Clone)]
+pub enum AgentSigner {
+    /// Private key held in process
+    Local(PrivateKeySigner),
+    /// Key held by a remote signing service
+    Remote(RemoteSigner),
+}
+
+impl AgentSigner {
+    pub fn address(&self) -> Address {
+        match self {
+            AgentSigner::Local(signer) => signer.address(),
+            AgentSigner::Remote(signer) => signer.address,
+        }
+    }
+
+    pub async fn sign_hash(&self, hash: &B256) -> Result<Signature> {
+        match self {
+            AgentSigner::Local(signer) => Ok(signer.sign_hash(hash).await?),
+            AgentSigner::Remote(signer) => signer.sign_hash(hash).await,
+        }
+    }
+}
+
+/// Client of a Web3Signer compatible signing service
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    client: reqwest::Client,
+    url: Url,
+    address: Address,
+}
+
+impl RemoteSigner {
+    pub fn new(url: Url, address: Address) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+            address,
+        }
+    }
+
+    /// Sends the digest to `/api/v1/eth1/sign/{address}` and checks that the returned
+    /// signature recovers to the signer's address
+    pub async fn sign_hash(&self, hash: &B256) -> Result<Signature> {
+        let url = format!(
+            "{}/{REMOTE_SIGN_PATH}/{}",
+            self.url.as_str().trim_end_matches('/'),
+            self.address
+        );
+
+        let response = self
+            .client
+            .post(&url)
+            .json(&json!({ "data": hash }))
+            .send()
+            .await
+            .with_context(|| format!("Requesting signature from remote signer {}", self.url))?;
+
+        let status = response.status();
+        let body = response.text().await?;
+
+        if !status.is_success() {
+            return Err(anyhow!(
+                "Remote signer {} responded with {}: {}",
+                self.url,
+                status,
+                body
+            ));
+        }
+
+        // Web3Signer responds with the hex signature as plain text, some services quote it as JSON
+        let signature = hex::decode(body.trim().trim_matches('"'))
+            .context("Decoding remote signer signature")?;
+        let signature = Signature::from_raw(&signature)?;
+
+        let recovered = signature.recover_address_from_prehash(hash)?;
+        if recovered != self.address {
+            return Err(anyhow!(
+                "Remote signer {} signed with {} instead of {}",
+                self.url,
+                recovered,
+                self.address
+            ));
+        }
+
+        Ok(signature)
+    }
+}
+
+impl PasswordSource {
+    pub fn read_password(&self, prompt: &str) -> Result<String> {
+        match self {
+            PasswordSource::Env(var) => std::env::var(var)
+                .with_context(|| format!("Reading password from environment variable {var}")),
+            PasswordSource::File(path) => {
+                let password = std::fs::read_to_string(path)
+                    .with_context(|| format!("Reading password file {}", path.display()))?;
+                Ok(password.trim_end_matches(['\r', '\n']).to_string())
+            }
+            PasswordSource::Prompt => {
+                rpassword::prompt_password(prompt).context("Reading password from the terminal")
+            }
+        }
+    }
+}
+
+impl SignerKey {
+    /// Parses the private key, decrypting the keystore if needed
+    pub fn load(&self) -> Result<AgentSigner> {
+        match self {
+            SignerKey::PrivateKey(key) => Ok(AgentSigner::Local(
+                key.parse().context("Parsing signer private key")?,
+            )),
+            SignerKey::Keystore { keystore, password } => {
+                let password = password
+                    .read_password(&format!("Password for keystore {}: ", keystore.display()))?;
+
+                let signer = PrivateKeySigner::decrypt_keystore(keystore, password)
+                    .with_context(|| format!("Decrypting keystore {}", keystore.display()))?;
+                Ok(AgentSigner::Local(signer))
+            }
+            SignerKey::Remote {
+                remote_signer,
+                address,
+            } => {
+                let url = Url::parse(remote_signer).context("Invalid remote signer url")?;
+                Ok(AgentSigner::Remote(RemoteSigner::new(url, *address)))
+            }
+        }
+    }
+}
+
+/// Loads the signers of every agent, in the same order as the chain configurations and their agents.
+/// Each distinct key is only loaded once, so a shared keystore is only decrypted and prompted for once.
+pub fn load_signers(chain_configs: &[ChainConfig]) -> Result<Vec<Vec<AgentSigner>>> {
+    let mut loaded: HashMap<&SignerKey, AgentSigner> = HashMap::new();
+
+    chain_configs
+        .iter()
+        .map(|chain_config| {
+            chain_config
+                .agents
+                .iter()
+                .map(|agent| {
+                    if let Some(signer) = loaded.get(&agent.signer_key) {
+                        return Ok(signer.clone());
+                    }
+
+                    let signer = agent.signer_key.load().with_context(|| {
+                        format!(
+                            "Loading signer key of {} agent for {} {}",
+                            agent.kind, chain_config.system, chain_config.network
+                        )
+                    })?;
+                    info!(
+                        system = %chain_config.system,
+                        network = %chain_config.network,
+                        agent = %agent.kind,
+                        address = %signer.address(),
+                        "Loaded signer"
+                    );
+                    loaded.insert(&agent.signer_key, signer.clone());
+                    Ok(signer)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Generates a random key and writes it to a new encrypted JSON keystore at `path`
+pub fn generate_keystore(path: &Path, password: &str) -> Result<PrivateKeySigner> {
+    if path.exists() {
+        return Err(anyhow!("Keystore {} already exists", path.display()));
+    }
+
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| anyhow!("Invalid keystore path {}", path.display()))?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let (signer, _uuid) = PrivateKeySigner::new_keystore(dir, &mut OsRng, password, Some(name))
+        .with_context(|| format!("Writing keystore {}", path.display()))?;
+
+    Ok(signer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::types::{OracleChain, EVM_SYSTEM_ID};
+    use crate::config::AgentConfig;
+    use serde_json::json;
+
+    // Fixed private key for reproducibility (DO NOT USE IN PROD)
+    const SIGNER_KEY: &str = "0x59c6995e998f97a5a0044976f3ac3b8c9f27a7d9b3bcd2b0d7aeb5f3e9eae7c6";
+
+    #[test]
+    fn test_signer_key_config() {
+        let agent: AgentConfig = serde_json::from_value(json!({
+            "kind": "target",
+            "signer_key": SIGNER_KEY,
+            "prediction_trigger": "block"
+        }))
+        .unwrap();
+        assert_eq!(
+            agent.signer_key,
+            SignerKey::PrivateKey(SIGNER_KEY.to_string())
+        );
+        assert!(!format!("{agent:?}").contains(&SIGNER_KEY[2..]));
+
+        let agent: AgentConfig = serde_json::from_value(json!({
+            "kind": "target",
+            "signer_key": {"keystore": "/keys/agent.json", "password": {"env": "AGENT_PASSWORD"}},
+            "prediction_trigger": "block"
+        }))
+        .unwrap();
+        assert_eq!(
+            agent.signer_key,
+            SignerKey::Keystore {
+                keystore: "/keys/agent.json".into(),
+                password: PasswordSource::Env("AGENT_PASSWORD".to_string()),
+            }
+        );
+
+        let password: PasswordSource = serde_json::from_value(json!("prompt")).unwrap();
+        assert_eq!(password, PasswordSource::Prompt);
+    }
+
+    #[test]
+    fn test_keystore_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let keystore = dir.path().join("agent.json");
+        let password_file = dir.path().join("password");
+        std::fs::write(&password_file, "correct horse\n").unwrap();
+
+        let signer = generate_keystore(&keystore, "correct horse").unwrap();
+        assert!(generate_keystore(&keystore, "correct horse").is_err());
+
+        let signer_key = SignerKey::Keystore {
+            keystore: keystore.clone(),
+            password: PasswordSource::File(password_file),
+        };
+        assert_eq!(signer_key.load().unwrap().address(), signer.address());
+
+        let wrong_password = dir.path().join("wrong_password");
+        std::fs::write(&wrong_password, "battery staple").unwrap();
+        let signer_key = SignerKey::Keystore {
+            keystore,
+            password: PasswordSource::File(wrong_password),
+        };
+        assert!(signer_key.load().is_err());
+    }
+
+    #[test]
+    fn test_load_private_key() {
+        let signer = SignerKey::PrivateKey(SIGNER_KEY.to_string())
+            .load()
+            .unwrap();
+        assert!(matches!(
+            signer,
+            AgentSigner::Local(signer) if signer == SIGNER_KEY.parse::<PrivateKeySigner>().unwrap()
+        ));
+
+        assert!(SignerKey::PrivateKey("0x1234".to_string()).load().is_err());
+    }
+
+    /// Serves `/api/v1/eth1/sign/{address}` like Web3Signer, signing with `key` and
+    /// recording the requested paths
+    async fn mock_remote_signer(
+        key: PrivateKeySigner,
+    ) -> (Url, std::sync::Arc<std::sync::Mutex<Vec<String>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        let paths = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let paths_clone = paths.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = vec![];
+                let mut chunk = [0u8; 1024];
+
+                // Read the headers, then the rest of the body
+                let (path, body) = loop {
+                    let n = stream.read(&mut chunk).await.unwrap();
+                    request.extend_from_slice(&chunk[..n]);
+                    let text = String::from_utf8_lossy(&request).to_string();
+                    let Some((head, body)) = text.split_once("\r\n\r\n") else {
+                        continue;
+                    };
+                    let content_length = head
+                        .lines()
+                        .find_map(|line| {
+                            let (name, value) = line.split_once(':')?;
+                            name.eq_ignore_ascii_case("content-length")
+                                .then(|| value.trim().parse::<usize>().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if body.len() >= content_length {
+                        let path = head.split_whitespace().nth(1).unwrap().to_string();
+                        break (path, body.to_string());
+                    }
+                };
+
+                paths_clone.lock().unwrap().push(path);
+
+                let data: serde_json::Value = serde_json::from_str(&body).unwrap();
+                let hash: B256 = data["data"].as_str().unwrap().parse().unwrap();
+                let signature = key.sign_hash(&hash).await.unwrap();
+                let body = hex::encode_prefixed(signature.as_bytes());
+
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+
+        (url, paths)
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer() {
+        let key: PrivateKeySigner = SIGNER_KEY.parse().unwrap();
+        let address = key.address();
+        let (url, paths) = mock_remote_signer(key).await;
+
+        let signer = SignerKey::Remote {
+            remote_signer: url.to_string(),
+            address,
+        }
+        .load()
+        .unwrap();
+        assert_eq!(signer.address(), address);
+
+        let hash = alloy::primitives::keccak256(b"payload");
+        let signature = signer.sign_hash(&hash).await.unwrap();
+        assert_eq!(
+            signature.recover_address_from_prehash(&hash).unwrap(),
+            address
+        );
+        assert_eq!(
+            paths.lock().unwrap().as_slice(),
+            [format!("/api/v1/eth1/sign/{address}")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_wrong_key() {
+        // The service signs with a different key than the configured address
+        let (url, _) = mock_remote_signer(PrivateKeySigner::random()).await;
+        let address = SIGNER_KEY.parse::<PrivateKeySigner>().unwrap().address();
+        let signer = RemoteSigner::new(url, address);
+
+        let hash = alloy::primitives::keccak256(b"payload");
+        assert!(signer.sign_hash(&hash).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_payload_signatures() {
+        use crate::types::{AgentPayload, Network, PriceKind, PriceUnit, Settlement, System};
+
+        let key: PrivateKeySigner = SIGNER_KEY.parse().unwrap();
+        let address = key.address();
+        let (url, _) = mock_remote_signer(key).await;
+        let signer = AgentSigner::Remote(RemoteSigner::new(url, address));
+
+        let payload = AgentPayload {
+            schema_version: "1".to_string(),
+            from_block: 12345,
+            settlement: Settlement::Fast,
+            timestamp: chrono::Utc::now(),
+            system: System::Ethereum,
+            network: Network::Mainnet,
+            unit: PriceUnit::Wei,
+            kind: PriceKind::GasPrice,
+            price: "20000000000".to_string(),
+            records: vec![],
+        };
+
+        let signature = payload.sign(&signer).await.unwrap();
+        assert_eq!(payload.validate_signature(&signature).unwrap(), address);
+
+        let chain = OracleChain {
+            system_id: EVM_SYSTEM_ID,
+            chain_id: 1,
+        };
+        let network_signature = payload.network_signature(&signer, chain).await.unwrap();
+        assert_eq!(
+            payload
+                .validate_network_signature(&network_signature, chain)
+                .unwrap(),
+            address
+        );
+    }
+}
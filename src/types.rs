@@ -1,18 +1,25 @@
-use crate::chain::{sign::PayloadSigner, types::SignedOraclePayloadV2};
-#[cfg(test)]
-use alloy::signers::Signature;
+use crate::blocks::decode_holocene_extra_data;
+use crate::chain::{
+    records::RecordType,
+    sign::PayloadSigner,
+    types::{OracleChain, OraclePayloadV2, SignedOraclePayloadV2, EVM_SYSTEM_ID},
+};
+use crate::config::{BaseFeeParams, BlobParams};
+use crate::rpc::BlockHeader;
+use crate::signer::AgentSigner;
 use alloy::{
     hex,
-    primitives::{keccak256, B256},
-    signers::{local::PrivateKeySigner, Signer},
+    primitives::{keccak256, Address, B256},
+    signers::Signature,
 };
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::{fmt, str::FromStr};
-use strum_macros::{Display, EnumString};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, EnumString};
 
-#[derive(Debug, Clone, EnumString, Display, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, EnumString, Display, Deserialize, Serialize)]
 #[strum(serialize_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 pub enum ModelKind {
@@ -23,6 +30,11 @@ pub enum ModelKind {
     TimeSeries,
     LastMin,
     PendingFloor,
+    Ensemble,
+    SelfTuning,
+    Ewma,
+    Kalman,
+    QuantileRegression,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -33,6 +45,8 @@ pub enum AgentKind {
     Node,
     /// Will publish the actual min price for new blocks
     Target,
+    /// Will publish the blob base fee of the next block
+    BlobFee,
     /// Will publish a estimate based on the model kind
     Model(ModelKind),
 }
@@ -42,11 +56,22 @@ impl fmt::Display for AgentKind {
         match self {
             AgentKind::Node => write!(f, "node"),
             AgentKind::Target => write!(f, "target"),
+            AgentKind::BlobFee => write!(f, "blob_fee"),
             AgentKind::Model(kind) => write!(f, "{kind}"),
         }
     }
 }
 
+impl AgentKind {
+    /// What the price published by this agent measures
+    pub fn price_kind(&self) -> PriceKind {
+        match self {
+            AgentKind::BlobFee => PriceKind::BlobBaseFee,
+            AgentKind::Node | AgentKind::Target | AgentKind::Model(_) => PriceKind::GasPrice,
+        }
+    }
+}
+
 impl FromStr for AgentKind {
     type Err = String;
 
@@ -60,6 +85,7 @@ impl FromStr for AgentKind {
         match s.to_lowercase().as_str() {
             "node" => Ok(AgentKind::Node),
             "target" => Ok(AgentKind::Target),
+            "blob_fee" => Ok(AgentKind::BlobFee),
             _ => Err(format!("Unknown mode: {s}")),
         }
     }
@@ -89,9 +115,23 @@ pub struct AgentPayload {
     /// The unit of the `price` field (currently only wei)
     #[serde(default = "PriceUnit::default_wei")]
     pub unit: PriceUnit,
+    /// What the `price` field measures. Omitted for gas prices.
+    #[serde(default, skip_serializing_if = "PriceKind::is_gas_price")]
+    pub kind: PriceKind,
     /// The estimated price as a decimal string. Interpretation depends on `unit`.
     /// For `wei`, this MUST be an integer decimal string with no leading zeros (except "0").
     pub price: String,
+    /// Additional oracle records published in the same signed payload
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub records: Vec<PayloadRecord>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct PayloadRecord {
+    #[serde(rename = "type")]
+    pub record_type: RecordType,
+    /// Value in wei as a decimal string, following the same rules as `AgentPayload::price`
+    pub value: String,
 }
 
 impl AgentPayload {
@@ -119,9 +159,35 @@ impl AgentPayload {
         let price = self.price.clone();
         let unit = self.unit.to_string().to_lowercase();
 
+        // `kind` is only part of the canonical JSON when it is not the default gas price,
+        // so gas price signatures stay unchanged
+        let kind = if self.kind.is_gas_price() {
+            String::new()
+        } else {
+            format!("\"kind\":\"{}\",", self.kind)
+        };
+
+        // `records` is likewise only included when the payload has additional records
+        let records = if self.records.is_empty() {
+            String::new()
+        } else {
+            let records = self
+                .records
+                .iter()
+                .map(|record| {
+                    format!(
+                        "{{\"type\":\"{}\",\"value\":\"{}\"}}",
+                        record.record_type, record.value
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+            format!("\"records\":[{records}],")
+        };
+
         format!(
-            "{{\"from_block\":\"{}\",\"network\":\"{}\",\"price\":\"{}\",\"schema_version\":\"{}\",\"settlement\":\"{}\",\"system\":\"{}\",\"timestamp\":\"{}\",\"unit\":\"{}\"}}",
-            from_block, network, price, schema_version, settlement, system, timestamp, unit
+            "{{\"from_block\":\"{}\",{}\"network\":\"{}\",\"price\":\"{}\",{}\"schema_version\":\"{}\",\"settlement\":\"{}\",\"system\":\"{}\",\"timestamp\":\"{}\",\"unit\":\"{}\"}}",
+            from_block, kind, network, price, records, schema_version, settlement, system, timestamp, unit
         )
     }
 
@@ -131,23 +197,26 @@ impl AgentPayload {
     }
 
     /// Sign the Keccak-256 digest of the canonical JSON per spec v1.0.0.
-    pub async fn sign(&self, signer_key: &str) -> Result<String> {
-        let signer: PrivateKeySigner = signer_key.parse()?;
+    pub async fn sign(&self, signer: &AgentSigner) -> Result<String> {
         let digest = self.canonical_digest();
         let signature = signer.sign_hash(&digest).await?;
         let hex_signature = hex::encode(signature.as_bytes());
         Ok(format!("0x{hex_signature}"))
     }
 
-    pub fn network_signature(self, signer_key: &str) -> Result<String> {
+    /// Sign the binary oracle encoding of this payload, with `chain` in its header
+    pub async fn network_signature(
+        &self,
+        signer: &AgentSigner,
+        chain: OracleChain,
+    ) -> Result<String> {
         let mut opv2 = SignedOraclePayloadV2 {
-            payload: self.into(),
+            payload: OraclePayloadV2::from_agent_payload(self, chain)?,
             signature: None,
         };
 
         let mut buf = vec![];
-        let signer: PrivateKeySigner = signer_key.parse()?;
-        opv2.to_signed_payload(&mut buf, signer)?;
+        opv2.to_signed_payload(&mut buf, signer).await?;
 
         let hex_signature = hex::encode(
             opv2.signature
@@ -158,22 +227,133 @@ impl AgentPayload {
 
         Ok(format!("0x{hex_signature}"))
     }
+
+    /// Recover the address that signed the canonical JSON of this payload
+    pub fn validate_signature(&self, signature: &str) -> Result<Address> {
+        let signature = parse_signature(signature)?;
+        let digest = self.canonical_digest();
+        Ok(signature.recover_address_from_prehash(&digest)?)
+    }
+
+    /// Recover the address that signed the binary oracle encoding of this payload
+    pub fn validate_network_signature(
+        &self,
+        network_signature: &str,
+        chain: OracleChain,
+    ) -> Result<Address> {
+        let signed = SignedOraclePayloadV2 {
+            payload: OraclePayloadV2::from_agent_payload(self, chain)?,
+            signature: Some(parse_signature(network_signature)?),
+        };
+        Ok(signed.recover_signer()?)
+    }
 }
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize, Display, EnumString)]
+fn parse_signature(signature: &str) -> Result<Signature> {
+    let sig_bytes = hex::decode(signature).context("Decoding signature hex")?;
+    Ok(Signature::from_raw(&sig_bytes)?)
+}
+
+/// Prefix of systems and networks without built-in parameters, eg. `custom:scroll`
+const CUSTOM_PREFIX: &str = "custom:";
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize, Display, EnumIter)]
 #[strum(serialize_all = "lowercase")]
-#[serde(rename_all = "lowercase")]
+#[serde(try_from = "String", into = "String")]
 pub enum System {
     Ethereum,
     Base,
     Polygon,
+    Arbitrum,
+    Optimism,
+    Linea,
+    Bsc,
+    /// Avalanche C-Chain
+    Avalanche,
+    /// A system without built-in parameters, configured as `custom:<name>` with a custom `chain`
+    #[strum(to_string = "custom:{0}")]
+    Custom(String),
 }
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize, Display, EnumString)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize, Display, EnumIter)]
 #[strum(serialize_all = "lowercase")]
-#[serde(rename_all = "lowercase")]
+#[serde(try_from = "String", into = "String")]
 pub enum Network {
     Mainnet,
+    Sepolia,
+    Holesky,
+    Amoy,
+    /// A network without built-in parameters, configured as `custom:<name>` with a custom `chain`
+    #[strum(to_string = "custom:{0}")]
+    Custom(String),
+}
+
+/// Parses a built-in name, or `custom:<name>` for the custom variant. Unknown names are
+/// rejected so a misspelt system or network does not become a custom one.
+fn parse_with_custom<T>(s: &str, kind: &str, custom: fn(String) -> T) -> Result<T, String>
+where
+    T: IntoEnumIterator + fmt::Display,
+{
+    if let Some(name) = s.strip_prefix(CUSTOM_PREFIX) {
+        if name.is_empty() {
+            return Err(format!(
+                "Custom {kind} needs a name, eg. \"{CUSTOM_PREFIX}<name>\""
+            ));
+        }
+        return Ok(custom(name.to_string()));
+    }
+
+    T::iter()
+        .find(|variant| variant.to_string() == s)
+        .ok_or_else(|| {
+            format!(
+                "Unknown {kind} \"{s}\". A {kind} without built-in parameters is written as \"{CUSTOM_PREFIX}{s}\""
+            )
+        })
+}
+
+impl FromStr for System {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_with_custom(s, "system", System::Custom)
+    }
+}
+
+impl TryFrom<String> for System {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<System> for String {
+    fn from(system: System) -> Self {
+        system.to_string()
+    }
+}
+
+impl FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_with_custom(s, "network", Network::Custom)
+    }
+}
+
+impl TryFrom<String> for Network {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<Network> for String {
+    fn from(network: Network) -> Self {
+        network.to_string()
+    }
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize, Display, EnumString)]
@@ -190,24 +370,41 @@ impl PriceUnit {
     }
 }
 
+#[derive(
+    Debug, Clone, Copy, Default, Hash, PartialEq, Eq, Serialize, Deserialize, Display, EnumString,
+)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+/// What the price field in AgentPayload measures
+pub enum PriceKind {
+    /// Gas price for inclusion of a regular transaction
+    #[default]
+    GasPrice,
+    /// Base fee per blob gas (EIP-4844)
+    BlobBaseFee,
+}
+
+impl PriceKind {
+    pub fn is_gas_price(&self) -> bool {
+        *self == PriceKind::GasPrice
+    }
+
+    /// The oracle record type the price is published in. Gas prices keep type 340, which
+    /// existing oracle consumers read agent prices from.
+    pub fn record_type(&self) -> RecordType {
+        match self {
+            PriceKind::GasPrice => RecordType::MaxPriorityFeePerGasP99,
+            PriceKind::BlobBaseFee => RecordType::BlobBaseFeePerGas,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use alloy::{primitives::Address, signers::local::PrivateKeySigner};
+    use alloy::signers::local::PrivateKeySigner;
     use chrono::{DateTime, Utc};
 
-    // Tests-only helper to validate a canonical JSON signature against this payload
-    impl AgentPayload {
-        pub fn validate_signature(&self, signature: &str) -> anyhow::Result<Address> {
-            let sig_bytes = signature.strip_prefix("0x").unwrap_or(signature);
-            let sig_bytes = alloy::hex::decode(sig_bytes)?;
-            let signature = Signature::from_raw(&sig_bytes)?;
-            let digest = self.canonical_digest();
-            let recovered = signature.recover_address_from_prehash(&digest)?;
-            Ok(recovered)
-        }
-    }
-
     #[tokio::test]
     async fn test_canonical_sign_and_recover_roundtrip() {
         let timestamp = DateTime::parse_from_rfc3339("2024-01-01T12:00:00.500000000Z")
@@ -224,17 +421,151 @@ mod tests {
             system: System::Ethereum,
             network: Network::Mainnet,
             unit: PriceUnit::Wei,
+            kind: PriceKind::GasPrice,
             price: "20000000000".to_string(),
+            records: vec![],
         };
 
         // Sign
-        let sig = payload.sign(sk_hex).await.unwrap();
+        let sig = payload
+            .sign(&AgentSigner::Local(signer.clone()))
+            .await
+            .unwrap();
         assert!(sig.starts_with("0x"));
 
         // Recover and ensure matches the signer address
         let recovered = payload.validate_signature(&sig).unwrap();
         assert_eq!(recovered, signer.address());
     }
+
+    #[test]
+    fn test_canonical_json_price_kind() {
+        let timestamp = DateTime::parse_from_rfc3339("2024-01-01T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        let mut payload = AgentPayload {
+            schema_version: "1".to_string(),
+            from_block: 12345,
+            settlement: Settlement::Immediate,
+            timestamp,
+            system: System::Ethereum,
+            network: Network::Mainnet,
+            unit: PriceUnit::Wei,
+            kind: PriceKind::GasPrice,
+            price: "1".to_string(),
+            records: vec![],
+        };
+
+        // Gas prices keep the original canonical form and record type
+        assert_eq!(PriceKind::GasPrice.record_type().id(), 340);
+        assert_eq!(
+            payload.canonical_json_string(),
+            "{\"from_block\":\"12345\",\"network\":\"mainnet\",\"price\":\"1\",\"schema_version\":\"1\",\"settlement\":\"immediate\",\"system\":\"ethereum\",\"timestamp\":\"1704110400000000000\",\"unit\":\"wei\"}"
+        );
+        assert!(serde_json::to_value(&payload)
+            .unwrap()
+            .get("kind")
+            .is_none());
+
+        payload.kind = PriceKind::BlobBaseFee;
+        assert_eq!(
+            payload.canonical_json_string(),
+            "{\"from_block\":\"12345\",\"kind\":\"blob_base_fee\",\"network\":\"mainnet\",\"price\":\"1\",\"schema_version\":\"1\",\"settlement\":\"immediate\",\"system\":\"ethereum\",\"timestamp\":\"1704110400000000000\",\"unit\":\"wei\"}"
+        );
+        assert_eq!(
+            serde_json::to_value(&payload).unwrap()["kind"],
+            "blob_base_fee"
+        );
+
+        let chain = SystemNetworkKey::new(System::Ethereum, Network::Mainnet)
+            .to_chain_params()
+            .unwrap()
+            .oracle_chain();
+        let oracle_payload = OraclePayloadV2::from_agent_payload(&payload, chain).unwrap();
+        assert_eq!(
+            oracle_payload.records[0].typ,
+            RecordType::BlobBaseFeePerGas.id()
+        );
+    }
+
+    #[test]
+    fn test_chain_params() {
+        let chains = [
+            (System::Ethereum, Network::Mainnet, 1, 12000),
+            (System::Ethereum, Network::Sepolia, 11_155_111, 12000),
+            (System::Ethereum, Network::Holesky, 17000, 12000),
+            (System::Base, Network::Mainnet, 8453, 2000),
+            (System::Base, Network::Sepolia, 84532, 2000),
+            (System::Polygon, Network::Mainnet, 137, 2000),
+            (System::Polygon, Network::Amoy, 80002, 2000),
+            (System::Arbitrum, Network::Mainnet, 42161, 250),
+            (System::Arbitrum, Network::Sepolia, 421_614, 250),
+            (System::Optimism, Network::Mainnet, 10, 2000),
+            (System::Optimism, Network::Sepolia, 11_155_420, 2000),
+            (System::Linea, Network::Mainnet, 59144, 2000),
+            (System::Linea, Network::Sepolia, 59141, 2000),
+            (System::Bsc, Network::Mainnet, 56, 450),
+            (System::Avalanche, Network::Mainnet, 43114, 2000),
+        ];
+
+        for (system, network, chain_id, block_time_ms) in chains {
+            let key = SystemNetworkKey::new(system, network);
+            let params = key.to_chain_params().unwrap();
+
+            assert_eq!(params.chain_id, chain_id);
+            assert_eq!(params.system_id, EVM_SYSTEM_ID);
+            assert_eq!(params.block_time_ms, block_time_ms);
+            assert_eq!(params.blob_params.is_some(), key.system == System::Ethereum);
+            assert_eq!(SystemNetworkKey::from_chain_id(chain_id), Some(key));
+        }
+
+        let params = |system| {
+            SystemNetworkKey::new(system, Network::Mainnet)
+                .to_chain_params()
+                .unwrap()
+        };
+        assert_eq!(params(System::Ethereum).fee_market, FeeMarket::Eip1559);
+        assert_eq!(params(System::Arbitrum).fee_market, FeeMarket::BaseFeeOnly);
+        assert_eq!(params(System::Bsc).fee_market, FeeMarket::Legacy);
+        assert_eq!(
+            params(System::Optimism).base_fee_params,
+            Some(BaseFeeParams::OP_STACK)
+        );
+        assert_eq!(params(System::Avalanche).base_fee_params, None);
+
+        assert_eq!(
+            SystemNetworkKey::new(System::Polygon, Network::Sepolia).to_chain_params(),
+            None
+        );
+        assert_eq!(SystemNetworkKey::from_chain_id(534352), None);
+    }
+
+    #[test]
+    fn test_custom_system_and_network() {
+        let system: System = serde_json::from_str("\"custom:scroll\"").unwrap();
+        let network: Network = serde_json::from_str("\"mainnet\"").unwrap();
+        assert_eq!(system, System::Custom("scroll".to_string()));
+        assert_eq!(network, Network::Mainnet);
+        assert_eq!(system.to_string(), "custom:scroll");
+        assert_eq!(serde_json::to_string(&system).unwrap(), "\"custom:scroll\"");
+        assert_eq!(serde_json::to_string(&network).unwrap(), "\"mainnet\"");
+        assert_eq!(Network::from_str("sepolia").unwrap(), Network::Sepolia);
+        assert_eq!(
+            Network::from_str("custom:devnet").unwrap(),
+            Network::Custom("devnet".to_string())
+        );
+
+        // Misspelt names are rejected instead of becoming custom chains
+        let err = serde_json::from_str::<System>("\"etherum\"").unwrap_err();
+        assert!(err.to_string().contains("Unknown system \"etherum\""));
+        assert!(Network::from_str("sepolya").is_err());
+        assert!(System::from_str("custom:").is_err());
+        assert_eq!(
+            SystemNetworkKey::new(system, network).to_chain_params(),
+            None
+        );
+    }
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
@@ -248,37 +579,134 @@ impl SystemNetworkKey {
         Self { system, network }
     }
 
-    pub fn to_chain_id(&self) -> u64 {
-        match self {
-            SystemNetworkKey {
-                system: System::Ethereum,
-                network: Network::Mainnet,
-            } => 1,
-            SystemNetworkKey {
-                system: System::Base,
-                network: Network::Mainnet,
-            } => 8453,
-            SystemNetworkKey {
-                system: System::Polygon,
-                network: Network::Mainnet,
-            } => 137,
+    /// Built-in parameters of the chain, `None` for chains that are not supported
+    pub fn to_chain_params(&self) -> Option<ChainParams> {
+        let (chain_id, block_time_ms) = match (&self.system, &self.network) {
+            (System::Ethereum, Network::Mainnet) => (1, 12000),
+            (System::Ethereum, Network::Sepolia) => (11_155_111, 12000),
+            (System::Ethereum, Network::Holesky) => (17000, 12000),
+            (System::Base, Network::Mainnet) => (8453, 2000),
+            (System::Base, Network::Sepolia) => (84532, 2000),
+            (System::Polygon, Network::Mainnet) => (137, 2000),
+            (System::Polygon, Network::Amoy) => (80002, 2000),
+            (System::Arbitrum, Network::Mainnet) => (42161, 250),
+            (System::Arbitrum, Network::Sepolia) => (421_614, 250),
+            (System::Optimism, Network::Mainnet) => (10, 2000),
+            (System::Optimism, Network::Sepolia) => (11_155_420, 2000),
+            (System::Linea, Network::Mainnet) => (59144, 2000),
+            (System::Linea, Network::Sepolia) => (59141, 2000),
+            (System::Bsc, Network::Mainnet) => (56, 450),
+            (System::Avalanche, Network::Mainnet) => (43114, 2000),
+            _ => return None,
+        };
+
+        let (fee_market, base_fee_params) = match self.system {
+            System::Ethereum | System::Linea => (FeeMarket::Eip1559, Some(BaseFeeParams::ETHEREUM)),
+            System::Base | System::Optimism => (FeeMarket::Eip1559, Some(BaseFeeParams::OP_STACK)),
+            System::Polygon => (FeeMarket::Eip1559, Some(BaseFeeParams::POLYGON)),
+            // ArbOS prices L2 gas from its backlog, priority fees are ignored
+            System::Arbitrum => (FeeMarket::BaseFeeOnly, None),
+            // The base fee tracks gas usage over time (ACP-176) instead of per block
+            System::Avalanche => (FeeMarket::Eip1559, None),
+            // Validators enforce a minimum gas price, the base fee is always zero
+            System::Bsc => (FeeMarket::Legacy, None),
+            System::Custom(_) => return None,
+        };
+
+        Some(ChainParams {
+            chain_id,
+            system_id: EVM_SYSTEM_ID,
+            block_time_ms,
+            fee_market,
+            base_fee_params,
+            base_fee_params_from_extra_data: self.is_op_stack(),
+            blob_params: self.to_blob_params(),
+            op_stack: self.is_op_stack(),
+        })
+    }
+
+    /// Inverse of `to_chain_params`, `None` for chains that are not supported
+    pub fn from_chain_id(chain_id: u64) -> Option<Self> {
+        System::iter()
+            .flat_map(|system| {
+                Network::iter().map(move |network| Self::new(system.clone(), network))
+            })
+            .find(|key| {
+                key.to_chain_params()
+                    .is_some_and(|params| params.chain_id == chain_id)
+            })
+    }
+
+    /// Blob parameters of the active fork, `None` for systems without blob transactions
+    fn to_blob_params(&self) -> Option<BlobParams> {
+        match self.system {
+            // Fusaka BPO2, active on mainnet and the testnets
+            System::Ethereum => Some(BlobParams {
+                target_blobs_per_block: 14,
+                max_blobs_per_block: 21,
+                base_fee_update_fraction: 11_684_671,
+                bounded_by_execution_cost: true,
+            }),
+            _ => None,
         }
     }
+    /// OP stack rollups, which charge an L1 data fee and encode their EIP-1559 parameters in
+    /// `extraData`
+    fn is_op_stack(&self) -> bool {
+        matches!(self.system, System::Base | System::Optimism)
+    }
+}
 
-    pub fn to_block_time(&self) -> u64 {
-        match self {
-            SystemNetworkKey {
-                system: System::Ethereum,
-                network: Network::Mainnet,
-            } => 12000,
-            SystemNetworkKey {
-                system: System::Base,
-                network: Network::Mainnet,
-            } => 2000,
-            SystemNetworkKey {
-                system: System::Polygon,
-                network: Network::Mainnet,
-            } => 2000,
+/// How transactions pay for gas on a chain
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Display)]
+#[strum(serialize_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum FeeMarket {
+    /// A base fee per block, with priority fees on top (EIP-1559)
+    #[default]
+    Eip1559,
+    /// An EIP-1559 base fee without priority fees, every transaction pays the base fee
+    BaseFeeOnly,
+    /// Only a gas price per transaction, any base fee reported by the node is ignored
+    Legacy,
+}
+
+/// Parameters of the chain an agent publishes for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainParams {
+    /// Chain ID, checked against the RPC and published in the oracle payload header
+    pub chain_id: u64,
+    /// System ID published in the oracle payload header
+    pub system_id: u8,
+    pub block_time_ms: u64,
+    pub fee_market: FeeMarket,
+    /// How the next base fee follows from the latest block, `None` when it is not derived from
+    /// the latest block alone and is expected to stay unchanged
+    pub base_fee_params: Option<BaseFeeParams>,
+    /// Whether blocks encode the EIP-1559 parameters of their child in `extraData`, which take
+    /// precedence over `base_fee_params` (OP stack since Holocene)
+    pub base_fee_params_from_extra_data: bool,
+    pub blob_params: Option<BlobParams>,
+    /// Whether the chain is an OP stack rollup charging an L1 data fee
+    pub op_stack: bool,
+}
+
+impl ChainParams {
+    /// EIP-1559 parameters of the block after `latest_block`
+    pub fn next_base_fee_params(&self, latest_block: &BlockHeader) -> Option<BaseFeeParams> {
+        if self.base_fee_params_from_extra_data {
+            if let Some(params) = decode_holocene_extra_data(&latest_block.extra_data) {
+                return Some(params);
+            }
+        }
+
+        self.base_fee_params
+    }
+
+    pub fn oracle_chain(&self) -> OracleChain {
+        OracleChain {
+            system_id: self.system_id,
+            chain_id: self.chain_id,
         }
     }
 }
@@ -297,3 +725,21 @@ pub enum Settlement {
     /// 1 hour
     Slow,
 }
+
+impl Settlement {
+    /// Target settlement time in milliseconds
+    pub fn to_millis(&self) -> u64 {
+        match self {
+            Settlement::Immediate => 0,
+            Settlement::Fast => 15_000,
+            Settlement::Medium => 900_000,
+            Settlement::Slow => 3_600_000,
+        }
+    }
+
+    /// Number of blocks a prediction is evaluated over, `floor(settlement_ms / block_time_ms)`.
+    /// The window always contains at least the next block.
+    pub fn to_block_window(&self, block_time_ms: u64) -> u64 {
+        (self.to_millis() / block_time_ms.max(1)).max(1)
+    }
+}
@@ -1,15 +1,72 @@
-use alloy::signers::local::PrivateKeySigner;
-use anyhow::Result;
+use crate::chain::decode::DecodedPayload;
+use crate::config::{GenerateKeysArgs, PasswordSource, VerifyArgs};
+use crate::signer::generate_keystore;
+use crate::verify::{verify_published_payload, PublishedPayload};
+use alloy::{hex, signers::local::PrivateKeySigner};
+use anyhow::{anyhow, Context, Result};
+use std::io::Read;
 
-pub fn round_to_9_places(v: f64) -> f64 {
-    (v * 1_000_000_000.0).round() / 1_000_000_000.0
-}
+pub fn generate_key_pair(args: &GenerateKeysArgs) -> Result<()> {
+    let Some(keystore) = &args.keystore else {
+        let signer = PrivateKeySigner::random();
+
+        println!("Private Key: {}", signer.to_bytes());
+        println!("Address: {}", signer.address());
+
+        return Ok(());
+    };
+
+    let password_source = args.password_source();
+    let password = password_source.read_password("Keystore password: ")?;
+
+    if password.is_empty() {
+        return Err(anyhow!("Keystore password cannot be empty"));
+    }
 
-pub fn generate_key_pair() -> Result<()> {
-    let signer = PrivateKeySigner::random();
+    if matches!(password_source, PasswordSource::Prompt)
+        && password_source.read_password("Confirm keystore password: ")? != password
+    {
+        return Err(anyhow!("Passwords do not match"));
+    }
 
-    println!("Private Key: {}", signer.to_bytes());
+    let signer = generate_keystore(keystore, &password)?;
+
+    println!("Keystore: {}", keystore.display());
     println!("Address: {}", signer.address());
 
     Ok(())
 }
+
+pub fn decode_payload(payload: &str) -> Result<()> {
+    let bytes = hex::decode(payload.trim()).context("Decoding payload hex")?;
+    let decoded = DecodedPayload::from_bytes(&bytes).context("Decoding oracle payload")?;
+
+    println!("{}", serde_json::to_string_pretty(&decoded)?);
+
+    Ok(())
+}
+
+pub fn verify_payload(args: &VerifyArgs) -> Result<()> {
+    let published = &args.published;
+    let published = if published == "-" {
+        let mut input = String::new();
+        std::io::stdin()
+            .read_to_string(&mut input)
+            .context("Reading published payload from stdin")?;
+        input
+    } else {
+        published.to_string()
+    };
+
+    let published: PublishedPayload =
+        serde_json::from_str(&published).context("Parsing published payload")?;
+    let report = verify_published_payload(&published, args.oracle_chain())?;
+
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    if !report.is_valid() {
+        return Err(anyhow!("Published payload failed verification"));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,362 @@
+use crate::chain::types::OracleChain;
+use crate::types::{AgentPayload, SystemNetworkKey};
+use alloy::primitives::Address;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Payload fields that are part of the canonical JSON
+const CANONICAL_FIELDS: [&str; 10] = [
+    "from_block",
+    "kind",
+    "network",
+    "price",
+    "records",
+    "schema_version",
+    "settlement",
+    "system",
+    "timestamp",
+    "unit",
+];
+
+/// A payload as published to the collector
+#[derive(Debug, Deserialize)]
+pub struct PublishedPayload {
+    pub payload: Value,
+    pub signature: String,
+    pub network_signature: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VerifyReport {
+    /// Canonical JSON rebuilt from the payload, whose Keccak-256 digest is signed
+    pub canonical_json: String,
+    /// Address recovered from `signature`
+    pub signer: Option<Address>,
+    /// Address recovered from `network_signature`
+    pub network_signer: Option<Address>,
+    /// Whether both signatures recover to the same address
+    pub signers_match: bool,
+    /// Fields whose published encoding differs from what is signed
+    pub issues: Vec<String>,
+}
+
+impl VerifyReport {
+    pub fn is_valid(&self) -> bool {
+        self.signers_match && self.issues.is_empty()
+    }
+}
+
+/// Verifies a published payload. The network signature is checked against the oracle header of
+/// `chain`, or of the payload's system and network when `None`.
+pub fn verify_published_payload(
+    published: &PublishedPayload,
+    chain: Option<OracleChain>,
+) -> Result<VerifyReport> {
+    let payload: AgentPayload = serde_json::from_value(published.payload.clone())
+        .context("Parsing payload as an agent payload")?;
+
+    let chain = match chain {
+        Some(chain) => chain,
+        None => SystemNetworkKey::new(payload.system.clone(), payload.network.clone())
+            .to_chain_params()
+            .map(|params| params.oracle_chain())
+            .with_context(|| {
+                format!(
+                    "No built-in parameters for {} {}, set the chain ID of the payload",
+                    payload.system, payload.network
+                )
+            })?,
+    };
+
+    let mut issues = match published.payload.as_object() {
+        Some(fields) => canonical_encoding_issues(fields),
+        None => vec![],
+    };
+
+    let signer = payload
+        .validate_signature(&published.signature)
+        .map_err(|e| issues.push(format!("signature: {e}")))
+        .ok();
+    let network_signer = payload
+        .validate_network_signature(&published.network_signature, chain)
+        .map_err(|e| issues.push(format!("network_signature: {e}")))
+        .ok();
+
+    Ok(VerifyReport {
+        canonical_json: payload.canonical_json_string(),
+        signers_match: signer.is_some() && signer == network_signer,
+        signer,
+        network_signer,
+        issues,
+    })
+}
+
+/// Compares the published payload fields with how they are encoded in the canonical JSON
+fn canonical_encoding_issues(fields: &Map<String, Value>) -> Vec<String> {
+    let mut issues = vec![];
+
+    for key in fields.keys() {
+        if !CANONICAL_FIELDS.contains(&key.as_str()) {
+            issues.push(format!(
+                "{key}: not part of the canonical JSON, so it is not covered by the signature"
+            ));
+        }
+    }
+
+    for (key, default) in [("schema_version", "1"), ("unit", "wei")] {
+        if !fields.contains_key(key) {
+            issues.push(format!(
+                "{key}: missing, signed with the default \"{default}\""
+            ));
+        }
+    }
+
+    if fields.get("kind").and_then(Value::as_str) == Some("gas_price") {
+        issues.push("kind: \"gas_price\" is omitted from the canonical JSON".to_string());
+    }
+
+    if let Some(timestamp) = fields.get("timestamp").and_then(Value::as_str) {
+        if !timestamp.ends_with('Z') {
+            issues.push(format!(
+                "timestamp: \"{timestamp}\" is not in UTC, it is signed as nanoseconds since the epoch"
+            ));
+        }
+    }
+
+    if let Some(price) = fields.get("price") {
+        check_wei_string("price", price, &mut issues);
+    }
+
+    match fields.get("records") {
+        Some(Value::Array(records)) if records.is_empty() => {
+            issues.push("records: empty records are omitted from the canonical JSON".to_string());
+        }
+        Some(Value::Array(records)) => {
+            for (i, record) in records.iter().enumerate() {
+                if let Some(value) = record.get("value") {
+                    check_wei_string(&format!("records[{i}].value"), value, &mut issues);
+                }
+            }
+        }
+        _ => {}
+    }
+
+    issues
+}
+
+/// Wei values are signed verbatim and must be integer decimal strings without leading zeros
+fn check_wei_string(field: &str, value: &Value, issues: &mut Vec<String>) {
+    let Some(value) = value.as_str() else {
+        issues.push(format!("{field}: must be a string, got {value}"));
+        return;
+    };
+
+    let is_canonical = !value.is_empty()
+        && value.bytes().all(|b| b.is_ascii_digit())
+        && (value == "0" || !value.starts_with('0'));
+
+    if !is_canonical {
+        issues.push(format!(
+            "{field}: \"{value}\" is not an integer decimal string without leading zeros"
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chain::records::RecordType;
+    use crate::chain::types::EVM_SYSTEM_ID;
+    use crate::signer::AgentSigner;
+    use crate::types::{Network, PayloadRecord, PriceKind, PriceUnit, Settlement, System};
+    use alloy::signers::local::PrivateKeySigner;
+    use chrono::{DateTime, Utc};
+    use serde_json::json;
+
+    // Fixed private keys for reproducibility (DO NOT USE IN PROD)
+    const SIGNER_KEY: &str = "0x59c6995e998f97a5a0044976f3ac3b8c9f27a7d9b3bcd2b0d7aeb5f3e9eae7c6";
+    const OTHER_SIGNER_KEY: &str =
+        "0x8b3a350cf5c34c9194ca85829a2df0ec3153be0318b5e2d3348e872092edffba";
+
+    fn test_payload() -> AgentPayload {
+        AgentPayload {
+            schema_version: "1".to_string(),
+            from_block: 12345,
+            settlement: Settlement::Fast,
+            timestamp: DateTime::parse_from_rfc3339("2024-01-01T12:00:00.5Z")
+                .unwrap()
+                .with_timezone(&Utc),
+            system: System::Ethereum,
+            network: Network::Mainnet,
+            unit: PriceUnit::Wei,
+            kind: PriceKind::GasPrice,
+            price: "20000000000".to_string(),
+            records: vec![],
+        }
+    }
+
+    const MAINNET: OracleChain = OracleChain {
+        system_id: EVM_SYSTEM_ID,
+        chain_id: 1,
+    };
+
+    async fn publish(
+        payload: &AgentPayload,
+        network_signer_key: &str,
+        chain: OracleChain,
+    ) -> PublishedPayload {
+        let signer = AgentSigner::Local(SIGNER_KEY.parse().unwrap());
+        let network_signer = AgentSigner::Local(network_signer_key.parse().unwrap());
+
+        PublishedPayload {
+            payload: serde_json::to_value(payload).unwrap(),
+            signature: payload.sign(&signer).await.unwrap(),
+            network_signature: payload
+                .network_signature(&network_signer, chain)
+                .await
+                .unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_published_payload() {
+        let published = publish(&test_payload(), SIGNER_KEY, MAINNET).await;
+        let report = verify_published_payload(&published, None).unwrap();
+
+        let address = SIGNER_KEY.parse::<PrivateKeySigner>().unwrap().address();
+        assert_eq!(report.signer, Some(address));
+        assert_eq!(report.network_signer, Some(address));
+        assert!(report.is_valid(), "{:?}", report.issues);
+        assert_eq!(
+            report.canonical_json,
+            test_payload().canonical_json_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_mismatched_signers() {
+        let published = publish(&test_payload(), OTHER_SIGNER_KEY, MAINNET).await;
+        let report = verify_published_payload(&published, None).unwrap();
+
+        assert!(report.signer.is_some());
+        assert!(report.network_signer.is_some());
+        assert!(!report.signers_match);
+        assert!(!report.is_valid());
+    }
+
+    #[tokio::test]
+    async fn test_verify_tampered_payload() {
+        let mut published = publish(&test_payload(), SIGNER_KEY, MAINNET).await;
+        published.payload["price"] = json!("20000000001");
+
+        // Both signatures still recover, but to addresses other than the signer's
+        let report = verify_published_payload(&published, None).unwrap();
+        let address = SIGNER_KEY.parse::<PrivateKeySigner>().unwrap().address();
+        assert_ne!(report.signer, Some(address));
+        assert_ne!(report.network_signer, Some(address));
+        assert!(!report.signers_match);
+    }
+
+    #[tokio::test]
+    async fn test_verify_malformed_wei_values() {
+        let mut payload = test_payload();
+        payload.records = vec![PayloadRecord {
+            record_type: RecordType::BaseFeePerGas,
+            value: "12000000000".to_string(),
+        }];
+        let published = publish(&payload, SIGNER_KEY, MAINNET).await;
+
+        for (field, pointer) in [
+            ("price", "/price"),
+            ("records[0].value", "/records/0/value"),
+        ] {
+            let mut published = PublishedPayload {
+                payload: published.payload.clone(),
+                signature: published.signature.clone(),
+                network_signature: published.network_signature.clone(),
+            };
+            *published.payload.pointer_mut(pointer).unwrap() = json!("1.5");
+
+            // The malformed value is reported instead of failing the oracle encoding
+            let report = verify_published_payload(&published, None).unwrap();
+            assert!(report.signer.is_some());
+            assert_eq!(report.network_signer, None);
+            assert!(!report.is_valid());
+            assert!(report
+                .issues
+                .iter()
+                .any(|issue| issue.starts_with(&format!("{field}: \"1.5\""))));
+            assert!(report
+                .issues
+                .iter()
+                .any(|issue| issue == "network_signature: \"1.5\" is not a decimal wei value"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verify_custom_chain() {
+        let mut payload = test_payload();
+        payload.system = System::Custom("scroll".to_string());
+
+        let chain = OracleChain {
+            system_id: EVM_SYSTEM_ID,
+            chain_id: 534352,
+        };
+        let published = publish(&payload, SIGNER_KEY, chain).await;
+
+        // The payload's system has no built-in chain ID to rebuild the oracle header with
+        let err = verify_published_payload(&published, None).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("No built-in parameters for custom:scroll"));
+
+        let report = verify_published_payload(&published, Some(chain)).unwrap();
+        assert!(report.is_valid(), "{:?}", report.issues);
+
+        // A different chain ID in the header recovers a different signer
+        let other_chain = OracleChain {
+            chain_id: 1,
+            ..chain
+        };
+        let report = verify_published_payload(&published, Some(other_chain)).unwrap();
+        assert!(!report.signers_match);
+    }
+
+    #[test]
+    fn test_canonical_encoding_issues() {
+        let payload = json!({
+            "from_block": 12345,
+            "settlement": "fast",
+            "timestamp": "2024-01-01T13:00:00.5+01:00",
+            "system": "ethereum",
+            "network": "mainnet",
+            "kind": "gas_price",
+            "price": "020000000000",
+            "records": [{"type": "base_fee_per_gas", "value": "1.5"}],
+            "confidence": 99
+        });
+
+        let issues = canonical_encoding_issues(payload.as_object().unwrap());
+        let fields: Vec<&str> = issues
+            .iter()
+            .map(|issue| issue.split(':').next().unwrap())
+            .collect();
+
+        assert_eq!(
+            fields,
+            vec![
+                "confidence",
+                "schema_version",
+                "unit",
+                "kind",
+                "timestamp",
+                "price",
+                "records[0].value"
+            ]
+        );
+
+        let canonical = serde_json::to_value(test_payload()).unwrap();
+        assert!(canonical_encoding_issues(canonical.as_object().unwrap()).is_empty());
+    }
+}